@@ -0,0 +1,43 @@
+use std::io::Cursor;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use deadrop::crypto::{CHUNK_SIZE, EncryptionKey, Pipeline};
+
+const PAYLOAD: usize = 64 * 1024 * 1024;
+
+fn chunk_sizes(c: &mut Criterion) {
+    let key = EncryptionKey::generate();
+    let data = vec![0x5au8; PAYLOAD];
+    let mut group = c.benchmark_group("encrypt");
+    group.throughput(Throughput::Bytes(PAYLOAD as u64));
+    group.sample_size(10);
+
+    for chunk_size in [
+        16 * 1024,
+        CHUNK_SIZE,
+        256 * 1024,
+        1024 * 1024,
+        4 * 1024 * 1024,
+    ] {
+        for workers in [1, Pipeline::default().workers] {
+            let pipeline = Pipeline {
+                chunk_size,
+                workers,
+            };
+            let id = BenchmarkId::new(format!("{}KiB", chunk_size / 1024), workers);
+            group.bench_with_input(id, &pipeline, |b, pipeline| {
+                let mut out = Vec::with_capacity(PAYLOAD + PAYLOAD / 1024);
+                b.iter(|| {
+                    out.clear();
+                    pipeline
+                        .encrypt(&mut Cursor::new(&data), &key, &mut out, |_| {})
+                        .unwrap()
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, chunk_sizes);
+criterion_main!(benches);
@@ -17,13 +17,23 @@ fn truncate_archive_prefix(name: &str) -> String {
     }
     // Truncate and trim trailing whitespace/hyphens/underscores for cleanliness
     let truncated = &name[..MAX_ARCHIVE_PREFIX];
-    truncated
-        .trim_end_matches(|c: char| c == ' ' || c == '-' || c == '_')
-        .to_string()
+    truncated.trim_end_matches([' ', '-', '_']).to_string()
 }
 
-/// Archives a folder into an in-memory .tar.gz and returns (bytes, display_name)
-pub fn compress_folder(path: &Path, progress: &ProgressBar) -> anyhow::Result<(Vec<u8>, String)> {
+/// Name a folder is sent under: its (shortened) name plus `.tar.gz`
+pub fn archive_name(path: &Path) -> String {
+    let folder_name = path.file_name().unwrap_or_default().to_string_lossy();
+    // FIX: Truncate long folder names to avoid tar 100-byte path limit
+    format!("{}.tar.gz", truncate_archive_prefix(&folder_name))
+}
+
+/// Archives a folder as .tar.gz into `output` while walking it. Returns
+/// the number of files archived.
+pub fn write_folder<W: Write>(
+    path: &Path,
+    output: W,
+    progress: &ProgressBar,
+) -> anyhow::Result<u64> {
     if !path.is_dir() {
         return Err(anyhow::anyhow!("{} is not a directory", path.display()));
     }
@@ -33,10 +43,7 @@ pub fn compress_folder(path: &Path, progress: &ProgressBar) -> anyhow::Result<(V
         .unwrap_or_default()
         .to_string_lossy()
         .to_string();
-
-    // FIX: Truncate long folder names to avoid tar 100-byte path limit
     let short_prefix = truncate_archive_prefix(&folder_name);
-    let archive_name = format!("{}.tar.gz", short_prefix);
 
     progress.set_message(format!("Archiving {}...", folder_name));
 
@@ -45,31 +52,73 @@ pub fn compress_folder(path: &Path, progress: &ProgressBar) -> anyhow::Result<(V
     progress.set_length(total_files);
     progress.set_position(0);
 
-    // Create tar.gz in memory
-    let mut compressed_bytes: Vec<u8> = Vec::new();
-    {
-        let encoder = GzEncoder::new(&mut compressed_bytes, Compression::fast());
-        let mut tar_builder = tar::Builder::new(encoder);
+    let encoder = GzEncoder::new(output, Compression::fast());
+    let mut tar_builder = tar::Builder::new(encoder);
+
+    // Follow symlinks for safety, don't include parent dirs
+    tar_builder.follow_symlinks(false);
 
-        // Follow symlinks for safety, don't include parent dirs
-        tar_builder.follow_symlinks(false);
+    // Recursively add the folder — use short_prefix as root inside archive
+    add_dir_recursive(&mut tar_builder, path, Path::new(&short_prefix), progress)?;
 
-        // Recursively add the folder — use short_prefix as root inside archive
-        add_dir_recursive(&mut tar_builder, path, Path::new(&short_prefix), progress)?;
+    // Finalize tar
+    let encoder = tar_builder.into_inner()?;
+    encoder.finish()?.flush()?;
 
-        // Finalize tar
-        let encoder = tar_builder.into_inner()?;
-        encoder.finish()?;
+    progress.finish_with_message(format!("Archived {} ({} files)", folder_name, total_files));
+    Ok(total_files)
+}
+
+/// Archives a folder into a .tar.gz temp file and returns (file, display_name),
+/// the file rewound to the start. It is deleted once closed.
+pub fn compress_folder(
+    path: &Path,
+    progress: &ProgressBar,
+) -> anyhow::Result<(std::fs::File, String)> {
+    let mut file = tempfile::tempfile()?;
+    write_folder(path, std::io::BufWriter::new(&mut file), progress)?;
+    std::io::Seek::rewind(&mut file)?;
+    Ok((file, archive_name(path)))
+}
+
+/// Archive a folder on a thread of its own. The stream reads the .tar.gz
+/// as it is written, so the folder is never held whole anywhere.
+pub fn stream_folder(path: &Path, progress: ProgressBar) -> anyhow::Result<FolderStream> {
+    if !path.is_dir() {
+        return Err(anyhow::anyhow!("{} is not a directory", path.display()));
     }
+    let (reader, writer) = std::io::pipe()?;
+    let folder = path.to_path_buf();
+    let archiver = std::thread::spawn(move || write_folder(&folder, writer, &progress));
+    Ok(FolderStream {
+        reader,
+        archiver: Some(archiver),
+    })
+}
 
-    progress.finish_with_message(format!(
-        "Archived {} ({} files → {})",
-        folder_name,
-        total_files,
-        bytesize::ByteSize::b(compressed_bytes.len() as u64)
-    ));
+/// A folder's .tar.gz, read while [`stream_folder`] writes it. The end of
+/// the stream is an error if archiving failed, so a cut-off archive can't
+/// pass for a whole one.
+pub struct FolderStream {
+    reader: std::io::PipeReader,
+    archiver: Option<std::thread::JoinHandle<anyhow::Result<u64>>>,
+}
 
-    Ok((compressed_bytes, archive_name))
+impl std::io::Read for FolderStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        if n == 0
+            && !buf.is_empty()
+            && let Some(archiver) = self.archiver.take()
+        {
+            match archiver.join() {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(std::io::Error::other(e)),
+                Err(_) => return Err(std::io::Error::other("archiving panicked")),
+            }
+        }
+        Ok(n)
+    }
 }
 
 /// Bundle multiple files and/or folders into a single .tar.gz on disk
@@ -0,0 +1,209 @@
+//! Tamper-evident audit log (`--audit-log <file>`, `ded audit verify`).
+//!
+//! One JSON object per line. Each entry carries its sequence number and the
+//! previous entry's hash, and ends with its own: `hash` is the SHA-256 of
+//! the line as written without the trailing `,"hash":"…"` member. Editing,
+//! reordering or cutting lines out of the middle breaks the chain; every
+//! run is bracketed by `audit_opened` / `audit_closed`, so a cut-off tail
+//! shows up as a run that never closed.
+//!
+//! Links and key fragments are never written — the `ready` event is skipped.
+
+use crate::events::{Event, Record, Sink};
+use anyhow::{Context, Result, bail};
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// `,"hash":"` + 64 hex digits + `"}`
+const HASH_SUFFIX_LEN: usize = 9 + 64 + 2;
+
+/// Appends chained entries to the audit file
+pub struct AuditLog {
+    chain: Mutex<Chain>,
+}
+
+struct Chain {
+    file: std::fs::File,
+    seq: u64,
+    prev: String,
+}
+
+impl AuditLog {
+    /// Open (or create) the log, continuing the chain already in it
+    pub fn open(path: &Path) -> Result<Self> {
+        let (seq, prev) = if path.exists() {
+            let summary = verify(path).with_context(|| {
+                format!(
+                    "Refusing to append to {} — its chain does not verify",
+                    path.display()
+                )
+            })?;
+            (summary.entries, summary.last_hash)
+        } else {
+            (0, GENESIS.to_string())
+        };
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+
+        let log = Self {
+            chain: Mutex::new(Chain { file, seq, prev }),
+        };
+        log.append(
+            "audit_opened",
+            serde_json::json!({
+                "version": env!("CARGO_PKG_VERSION"),
+                "pid": std::process::id(),
+            }),
+        )?;
+        Ok(log)
+    }
+
+    /// Write one entry: `{seq, prev, ts, event, ...fields, hash}`.
+    /// A `ts` among the fields (the event's own time) wins over now.
+    fn append(&self, event: &str, fields: serde_json::Value) -> Result<()> {
+        let mut chain = self.chain.lock().unwrap();
+        let mut entry = serde_json::Map::new();
+        entry.insert("seq".into(), chain.seq.into());
+        entry.insert("prev".into(), chain.prev.clone().into());
+        entry.insert("ts".into(), chrono::Utc::now().to_rfc3339().into());
+        entry.insert("event".into(), event.into());
+        if let serde_json::Value::Object(fields) = fields {
+            for (k, v) in fields {
+                if !matches!(k.as_str(), "seq" | "prev" | "event" | "hash") {
+                    entry.insert(k, v);
+                }
+            }
+        }
+
+        let body = serde_json::Value::Object(entry).to_string();
+        let hash = hex_sha256(body.as_bytes());
+        let line = format!("{},\"hash\":\"{}\"}}\n", &body[..body.len() - 1], hash);
+        chain.file.write_all(line.as_bytes())?;
+        chain.file.sync_data()?;
+        chain.seq += 1;
+        chain.prev = hash;
+        Ok(())
+    }
+}
+
+impl Sink for AuditLog {
+    fn emit(&self, record: &Record) {
+        if matches!(record.event, Event::Ready { .. }) {
+            return;
+        }
+        let Ok(serde_json::Value::Object(mut fields)) = serde_json::to_value(record) else {
+            return;
+        };
+        let event = fields
+            .remove("event")
+            .and_then(|e| e.as_str().map(str::to_string))
+            .unwrap_or_default();
+        if let Err(e) = self.append(&event, serde_json::Value::Object(fields)) {
+            eprintln!(
+                " {} Audit log write failed: {}",
+                console::style("⚠").yellow(),
+                e
+            );
+        }
+    }
+
+    fn close(&self) {
+        let _ = self.append("audit_closed", serde_json::json!({}));
+    }
+}
+
+/// What a successful verification found
+#[derive(Debug)]
+pub struct Summary {
+    pub entries: u64,
+    /// Runs (`audit_opened` entries) in the log
+    pub runs: u64,
+    /// Earlier runs that never wrote `audit_closed` (killed mid-run)
+    pub unclosed: u64,
+    /// False when the last run has no `audit_closed` — still running,
+    /// killed, or the tail was cut off
+    pub sealed: bool,
+    pub last_hash: String,
+}
+
+/// Check every entry's hash, sequence number and link to its predecessor
+pub fn verify(path: &Path) -> Result<Summary> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+    let mut prev = GENESIS.to_string();
+    let mut entries = 0u64;
+    let mut runs = 0u64;
+    let mut unclosed = 0u64;
+    let mut open_run = false;
+
+    for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let n = i + 1;
+        let line = line?;
+        if line.len() < HASH_SUFFIX_LEN + 2 || !line.ends_with("\"}") {
+            bail!("line {}: not an audit entry", n);
+        }
+        let (head, suffix) = line.split_at(line.len() - HASH_SUFFIX_LEN);
+        let Some(hash) = suffix
+            .strip_prefix(",\"hash\":\"")
+            .and_then(|s| s.strip_suffix("\"}"))
+        else {
+            bail!("line {}: missing hash", n);
+        };
+        let body = format!("{}}}", head);
+        if hex_sha256(body.as_bytes()) != hash {
+            bail!("line {}: hash mismatch — entry was modified", n);
+        }
+
+        let entry: serde_json::Value =
+            serde_json::from_str(&body).with_context(|| format!("line {}: invalid JSON", n))?;
+        if entry["seq"].as_u64() != Some(entries) {
+            bail!(
+                "line {}: expected seq {}, found {} — entries missing or reordered",
+                n,
+                entries,
+                entry["seq"]
+            );
+        }
+        if entry["prev"].as_str() != Some(prev.as_str()) {
+            bail!(
+                "line {}: chain broken — previous entry was removed or altered",
+                n
+            );
+        }
+        match entry["event"].as_str() {
+            Some("audit_opened") => {
+                if open_run {
+                    unclosed += 1;
+                }
+                open_run = true;
+                runs += 1;
+            }
+            Some("audit_closed") => open_run = false,
+            _ => {}
+        }
+
+        prev = hash.to_string();
+        entries += 1;
+    }
+
+    Ok(Summary {
+        entries,
+        runs,
+        unclosed,
+        sealed: !open_run,
+        last_hash: prev,
+    })
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
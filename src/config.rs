@@ -1,6 +1,12 @@
 use anyhow::{Result, anyhow};
 use std::path::PathBuf;
 
+/// How long shutdown waits for in-flight transfers unless `--drain-timeout` says otherwise
+pub const DEFAULT_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Largest single upload receive mode takes unless `--max-size` says otherwise
+pub const DEFAULT_MAX_UPLOAD: u64 = 2 * 1024 * 1024 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct DropConfig {
     pub file: PathBuf,
@@ -12,9 +18,24 @@ pub struct DropConfig {
     pub bind: String,
     pub no_qr: bool,
     pub recipients: Vec<String>,
+    pub decoy: Option<PathBuf>,
+    pub decoy_password: Option<String>,
+    pub tls: bool,
+    pub trusted_proxies: Vec<String>,
+    pub policy: crate::policy::AccessPolicy,
+    pub tui: bool,
+    pub json: bool,
+    pub hooks: crate::hooks::HookConfig,
+    pub metrics: Option<String>,
+    pub audit_log: Option<PathBuf>,
+    /// Longest shutdown waits for running transfers (0 = don't wait)
+    pub drain_timeout: std::time::Duration,
+    /// Plaintext bytes per encrypted chunk, recorded in the container header
+    pub chunk_size: usize,
 }
 
 impl DropConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         file: PathBuf,
         port: u16,
@@ -41,11 +62,40 @@ impl DropConfig {
             bind,
             no_qr,
             recipients,
+            decoy: None,
+            decoy_password: None,
+            tls: false,
+            trusted_proxies: Vec::new(),
+            policy: crate::policy::AccessPolicy::default(),
+            tui: false,
+            json: false,
+            hooks: Default::default(),
+            metrics: None,
+            audit_log: None,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            chunk_size: crate::crypto::CHUNK_SIZE,
         })
     }
+
+    /// Attach a decoy payload that opens under a second (duress) password
+    pub fn with_decoy(mut self, decoy: PathBuf, decoy_password: String) -> Result<Self> {
+        if !decoy.exists() {
+            return Err(anyhow!("Decoy path not found: {}", decoy.display()));
+        }
+        match &self.password {
+            None => return Err(anyhow!("--decoy requires --pw")),
+            Some(pw) if *pw == decoy_password => {
+                return Err(anyhow!("Decoy password must differ from the real password"));
+            }
+            Some(_) => {}
+        }
+        self.decoy = Some(decoy);
+        self.decoy_password = Some(decoy_password);
+        Ok(self)
+    }
 }
 
-fn parse_duration(s: &str) -> Result<chrono::Duration> {
+pub(crate) fn parse_duration(s: &str) -> Result<chrono::Duration> {
     let s = s.trim().to_lowercase();
 
     if let Some(num) = s.strip_suffix('s') {
@@ -82,9 +132,30 @@ fn parse_duration(s: &str) -> Result<chrono::Duration> {
 pub struct ReceiveConfig {
     pub output_dir: std::path::PathBuf,
     pub port: u16,
+    pub expire: String,
     pub expiry_duration: chrono::Duration,
     pub bind: String,
     pub no_qr: bool,
+    pub tls: bool,
+    pub json: bool,
+    pub hooks: crate::hooks::HookConfig,
+    pub metrics: Option<String>,
+    pub audit_log: Option<PathBuf>,
+    /// Longest shutdown waits for running transfers (0 = don't wait)
+    pub drain_timeout: std::time::Duration,
+    /// How many files, how much data, and for how long the link accepts
+    pub limits: crate::receive::Limits,
+    /// What happens when an upload's name is taken
+    pub on_conflict: crate::receive::OnConflict,
+    /// Accept hidden files and folders (`.bashrc`, `.git/`)
+    pub allow_dotfiles: bool,
+    /// Scan uploads before they reach `output_dir` (`--clamd`, `--scan-cmd`)
+    pub scanner: Option<crate::scan::Scanner>,
+    /// Where uploads wait for their verdict (default: `.deadrop-quarantine`
+    /// inside the output directory); infected ones stay there
+    pub quarantine_dir: Option<PathBuf>,
+    /// Keep received files encrypted to this key (`--vault`)
+    pub vault: Option<crate::vault::Vault>,
 }
 
 impl ReceiveConfig {
@@ -99,9 +170,34 @@ impl ReceiveConfig {
         Ok(Self {
             output_dir: output,
             port,
+            expire,
             expiry_duration,
             bind,
             no_qr,
+            tls: false,
+            json: false,
+            hooks: Default::default(),
+            metrics: None,
+            audit_log: None,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            limits: Default::default(),
+            on_conflict: Default::default(),
+            allow_dotfiles: false,
+            scanner: None,
+            quarantine_dir: None,
+            vault: None,
         })
     }
+
+    /// `-o -`: stream the upload to stdout instead of saving it
+    pub fn to_stdout(&self) -> bool {
+        self.output_dir.as_os_str() == "-"
+    }
+
+    /// `--quarantine-dir`, or its default inside the output directory
+    pub fn quarantine_path(&self) -> PathBuf {
+        self.quarantine_dir
+            .clone()
+            .unwrap_or_else(|| self.output_dir.join(crate::scan::QUARANTINE_DIR))
+    }
 }
@@ -9,7 +9,10 @@ use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use zeroize::Zeroize;
 
-pub const CHUNK_SIZE: usize = 64 * 1024; // 64KB chunks
+pub const CHUNK_SIZE: usize = 64 * 1024; // 64KB chunks (default)
+/// Smallest and largest chunk size a sender may pick
+pub const MIN_CHUNK_SIZE: usize = 16 * 1024;
+pub const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
 const AUTH_TAG_SIZE: usize = 16;
 
 /// Maximum length for display filenames (prevents UI/path issues)
@@ -114,17 +117,21 @@ impl EncryptionKey {
 /// Sanitize and truncate a filename to prevent path-length issues on Windows
 /// and display problems in the browser UI.
 ///
-/// - Strips path traversal characters (`..`, `/`, `\`)
+/// - Strips path traversal characters (`..`, `/`, `\`), control characters
+///   and the characters Windows forbids in names (`<>:"|?*`)
+/// - Drops trailing dots and spaces, which Windows silently removes
 /// - Truncates to MAX_FILENAME_LEN chars while preserving extension
 /// - Falls back to "file" / "archive.tar.gz" if name is empty
 pub fn safe_filename(name: &str, is_archive: bool) -> String {
     // Strip dangerous characters
     let sanitized: String = name
         .replace("..", "")
-        .replace('/', "")
-        .replace(std::path::MAIN_SEPARATOR, "")
-        .trim()
-        .to_string();
+        .chars()
+        .filter(|c| {
+            !c.is_control() && !matches!(c, '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*')
+        })
+        .collect();
+    let sanitized = sanitized.trim().trim_end_matches(['.', ' ']).to_string();
 
     if sanitized.is_empty() {
         return if is_archive {
@@ -140,40 +147,61 @@ pub fn safe_filename(name: &str, is_archive: bool) -> String {
     }
 
     // Truncate while preserving extension
-    let path = Path::new(&sanitized);
-    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-
-    // Handle double extensions like .tar.gz
-    let full_ext = if sanitized.ends_with(".tar.gz") {
-        "tar.gz"
-    } else if sanitized.ends_with(".tar.xz") {
-        "tar.xz"
-    } else if sanitized.ends_with(".tar.bz2") {
-        "tar.bz2"
-    } else {
-        ext
-    };
-
-    let ext_with_dot = if full_ext.is_empty() {
-        String::new()
-    } else {
-        format!(".{}", full_ext)
-    };
+    let (stem, ext_with_dot) = split_extension(&sanitized);
 
     // Calculate how many chars we can keep for the stem
     let max_stem = MAX_FILENAME_LEN.saturating_sub(ext_with_dot.len() + 1); // +1 for safety
-    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
-
-    // For .tar.gz, file_stem returns "name.tar", so strip that too
-    let stem = stem.strip_suffix(".tar").unwrap_or(stem);
+    let stem = if stem.is_empty() { "file" } else { stem };
 
     if stem.len() > max_stem {
-        format!("{}{}", &stem[..max_stem], ext_with_dot)
+        format!(
+            "{}{}",
+            &stem[..stem.floor_char_boundary(max_stem)],
+            ext_with_dot
+        )
     } else {
         format!("{}{}", stem, ext_with_dot)
     }
 }
 
+/// Split `name` into stem and extension (with its dot), keeping double
+/// extensions like `.tar.gz` — and a vault's `.pdf.age` — together
+fn split_extension(name: &str) -> (&str, &str) {
+    if let Some(inner) = name.strip_suffix(".age").filter(|n| !n.is_empty()) {
+        let (stem, _) = split_extension(inner);
+        return name.split_at(stem.len());
+    }
+    for double in [".tar.gz", ".tar.xz", ".tar.bz2"] {
+        if name.len() > double.len() && name.ends_with(double) {
+            return name.split_at(name.len() - double.len());
+        }
+    }
+    match name.rfind('.') {
+        // A leading dot (`.bashrc`) is part of the name, not an extension
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name, ""),
+    }
+}
+
+/// The `n`th alternative to an existing file: `report (1).pdf`,
+/// `backup (2).tar.gz`, `report (1).pdf.age`
+pub fn numbered_filename(name: &str, n: u32) -> String {
+    let (stem, ext) = split_extension(name);
+    format!("{} ({}){}", stem, n, ext)
+}
+
+/// Names Windows reserves for devices, with or without an extension
+/// (`CON`, `nul.txt`, `COM1`…). Writing one there talks to the device.
+pub fn is_reserved_name(name: &str) -> bool {
+    let base = name.split('.').next().unwrap_or(name).trim_end();
+    let upper = base.to_ascii_uppercase();
+    matches!(upper.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || ((upper.starts_with("COM") || upper.starts_with("LPT"))
+            && upper.len() == 4
+            && upper.as_bytes()[3].is_ascii_digit()
+            && upper.as_bytes()[3] != b'0')
+}
+
 /// Extract a safe filename from a path, with fallback
 pub fn safe_filename_from_path(path: &Path, is_archive: bool) -> String {
     let raw = path
@@ -189,21 +217,108 @@ pub fn safe_filename_from_path(path: &Path, is_archive: bool) -> String {
     safe_filename(&raw, is_archive)
 }
 
+/// Folder levels kept from an uploaded relative path
+const MAX_PATH_DEPTH: usize = 16;
+
+/// Sanitize a relative path sent by the upload page (`photos/2024/a.jpg`).
+///
+/// - Splits on `/` and `\`, dropping empty, `.` and `..` components, so
+///   the result can never be absolute or climb out of the output directory
+/// - Runs every component through [`safe_filename`]
+/// - Keeps only the innermost MAX_PATH_DEPTH levels
+pub fn safe_relative_path(path: &str) -> std::path::PathBuf {
+    let parts: Vec<String> = path
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|part| !matches!(*part, "" | "." | ".."))
+        .map(|part| safe_filename(&part.replace(':', "_"), false))
+        .collect();
+    let skip = parts.len().saturating_sub(MAX_PATH_DEPTH);
+    let relative: std::path::PathBuf = parts[skip..].iter().collect();
+    if relative.as_os_str().is_empty() {
+        "file".into()
+    } else {
+        relative
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 
-/// Header written before encrypted data
+/// Size of the standard container for `plaintext_len` bytes: header, then
+/// a length prefix and Poly1305 tag per chunk
+pub fn encrypted_len(plaintext_len: u64) -> u64 {
+    encrypted_len_with(plaintext_len, CHUNK_SIZE)
+}
+
+/// [`encrypted_len`] for a container cut into `chunk_size` chunks
+pub fn encrypted_len_with(plaintext_len: u64, chunk_size: usize) -> u64 {
+    let chunks = plaintext_len.div_ceil(chunk_size as u64);
+    EncryptedHeader::len_for(chunk_size) as u64 + plaintext_len + chunks * (4 + 16)
+}
+
+/// Check a sender's chunk size against [`MIN_CHUNK_SIZE`]..=[`MAX_CHUNK_SIZE`]
+pub fn check_chunk_size(chunk_size: u64) -> anyhow::Result<usize> {
+    if !(MIN_CHUNK_SIZE as u64..=MAX_CHUNK_SIZE as u64).contains(&chunk_size) {
+        anyhow::bail!(
+            "Chunk size must be between {} and {}",
+            bytesize::ByteSize::b(MIN_CHUNK_SIZE as u64),
+            bytesize::ByteSize::b(MAX_CHUNK_SIZE as u64)
+        );
+    }
+    Ok(chunk_size as usize)
+}
+
+/// Marks a header that records its chunk size
+const SIZED_HEADER_MAGIC: &[u8; 4] = b"DDcs";
+
+/// Header written before encrypted data.
+///
+/// With the default chunk size it is the original 40 bytes:
+///   [nonce 24][total_chunks u64 LE][original_size u64 LE]
+/// Any other chunk size is recorded in front:
+///   ["DDcs"][chunk_size u32 LE][nonce 24][total_chunks][original_size]
+/// A default-size nonce never starts with the magic (see [`fresh_nonce`]).
 #[derive(Debug)]
 pub struct EncryptedHeader {
     pub nonce: [u8; 24],
     pub total_chunks: u64,
     pub original_size: u64,
+    pub chunk_size: usize,
 }
 
 impl EncryptedHeader {
     pub const SIZE: usize = 24 + 8 + 8; // nonce + chunk_count + original_size = 40 bytes
+    /// A header that records its chunk size
+    pub const SIZED: usize = 4 + 4 + Self::SIZE;
+
+    /// Header length for a container cut into `chunk_size` chunks
+    pub fn len_for(chunk_size: usize) -> usize {
+        match chunk_size {
+            CHUNK_SIZE => Self::SIZE,
+            _ => Self::SIZED,
+        }
+    }
+
+    /// Header length, judging by the first bytes of a container (at
+    /// least 4 of them for a sized header to show)
+    pub fn len_of(data: &[u8]) -> usize {
+        match data.starts_with(SIZED_HEADER_MAGIC) {
+            true => Self::SIZED,
+            false => Self::SIZE,
+        }
+    }
+
+    /// Bytes this header takes up
+    pub fn encoded_len(&self) -> usize {
+        Self::len_for(self.chunk_size)
+    }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(Self::SIZE);
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        if self.chunk_size != CHUNK_SIZE {
+            buf.extend_from_slice(SIZED_HEADER_MAGIC);
+            buf.extend_from_slice(&(self.chunk_size as u32).to_le_bytes());
+        }
         buf.extend_from_slice(&self.nonce);
         buf.extend_from_slice(&self.total_chunks.to_le_bytes());
         buf.extend_from_slice(&self.original_size.to_le_bytes());
@@ -211,6 +326,16 @@ impl EncryptedHeader {
     }
 
     pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        let (chunk_size, data) = match data.strip_prefix(SIZED_HEADER_MAGIC) {
+            Some(rest) if rest.len() >= 4 => {
+                let recorded = u32::from_le_bytes(rest[..4].try_into()?) as u64;
+                let chunk_size = check_chunk_size(recorded)
+                    .map_err(|_| anyhow::anyhow!("Bad chunk size in header: {}", recorded))?;
+                (chunk_size, &rest[4..])
+            }
+            Some(_) => anyhow::bail!("Header too short"),
+            None => (CHUNK_SIZE, data),
+        };
         if data.len() < Self::SIZE {
             return Err(anyhow::anyhow!("Header too short"));
         }
@@ -222,10 +347,23 @@ impl EncryptedHeader {
             nonce,
             total_chunks,
             original_size,
+            chunk_size,
         })
     }
 }
 
+/// A random base nonce. One that would read as the sized-header magic is
+/// drawn again, so a default-size header can't be taken for a sized one.
+pub fn fresh_nonce() -> [u8; 24] {
+    let mut nonce = [0u8; 24];
+    loop {
+        rand::rng().fill_bytes(&mut nonce);
+        if !nonce.starts_with(SIZED_HEADER_MAGIC) {
+            return nonce;
+        }
+    }
+}
+
 /// Info about an encrypted file stored on disk
 pub struct EncryptedFileInfo {
     pub path: PathBuf,
@@ -234,21 +372,172 @@ pub struct EncryptedFileInfo {
     pub total_chunks: u64,
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// Parallel encryption pipeline
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Every chunk has its own nonce, so chunks seal independently: a reader
+// thread cuts the input into chunks, a pool of workers encrypts them, and
+// the calling thread writes them back in order. Bounded queues keep memory
+// at a few chunks per worker however large the input.
+
+/// Chunks queued per worker, on each side of the pool
+const PIPELINE_DEPTH: usize = 4;
+
+/// How [`Pipeline::encrypt`] splits the work
+#[derive(Clone, Copy, Debug)]
+pub struct Pipeline {
+    /// Plaintext bytes per chunk
+    pub chunk_size: usize,
+    /// Encrypting threads
+    pub workers: usize,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            chunk_size: CHUNK_SIZE,
+            workers: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl Pipeline {
+    /// Encrypt all of `input` into `output` as `[u32 len][ct+tag]` chunks.
+    /// Returns the header that belongs in front of them; `progress` gets
+    /// the plaintext bytes written so far.
+    pub fn encrypt(
+        &self,
+        input: &mut (impl Read + Send),
+        key: &EncryptionKey,
+        output: &mut impl Write,
+        progress: impl Fn(u64),
+    ) -> anyhow::Result<EncryptedHeader> {
+        self.encrypt_with_nonce(input, key, fresh_nonce(), output, progress)
+    }
+
+    /// Encrypt all of `input` into a whole container in memory
+    pub fn encrypt_to_vec(
+        &self,
+        input: &mut (impl Read + Send),
+        key: &EncryptionKey,
+        size_hint: u64,
+        progress: impl Fn(u64),
+    ) -> anyhow::Result<Vec<u8>> {
+        let header_len = EncryptedHeader::len_for(self.chunk_size);
+        let mut ciphertext =
+            Vec::with_capacity(encrypted_len_with(size_hint, self.chunk_size) as usize);
+        ciphertext.resize(header_len, 0);
+        let header = self.encrypt(input, key, &mut ciphertext, progress)?;
+        ciphertext[..header_len].copy_from_slice(&header.to_bytes());
+        Ok(ciphertext)
+    }
+
+    fn encrypt_with_nonce(
+        &self,
+        input: &mut (impl Read + Send),
+        key: &EncryptionKey,
+        nonce_bytes: [u8; 24],
+        output: &mut impl Write,
+        progress: impl Fn(u64),
+    ) -> anyhow::Result<EncryptedHeader> {
+        use std::sync::{Arc, Mutex, mpsc};
+
+        let cipher = XChaCha20Poly1305::new_from_slice(&key.0)
+            .map_err(|e| anyhow::anyhow!("Cipher init error: {}", e))?;
+        let nonce = chacha20poly1305::XNonce::from(nonce_bytes);
+
+        let chunk_size = self.chunk_size;
+        let workers = self.workers.max(1);
+        let (plain_tx, plain_rx) = mpsc::sync_channel::<(u64, Vec<u8>)>(workers * PIPELINE_DEPTH);
+        // Shared by the workers; gone once they all are, which stops the reader
+        let plain_rx = Arc::new(Mutex::new(plain_rx));
+        let (sealed_tx, sealed_rx) = mpsc::sync_channel(workers * PIPELINE_DEPTH);
+
+        std::thread::scope(|scope| {
+            let reader = scope.spawn(move || -> std::io::Result<()> {
+                for index in 0u64.. {
+                    let mut chunk = vec![0u8; chunk_size];
+                    let n = read_exact_or_eof(input, &mut chunk)?;
+                    if n == 0 {
+                        break;
+                    }
+                    chunk.truncate(n);
+                    if plain_tx.send((index, chunk)).is_err() {
+                        break; // The writer gave up
+                    }
+                }
+                Ok(())
+            });
+
+            for _ in 0..workers {
+                let (plain_rx, sealed_tx, cipher) = (plain_rx.clone(), sealed_tx.clone(), &cipher);
+                scope.spawn(move || {
+                    loop {
+                        let next = plain_rx.lock().unwrap().recv();
+                        let Ok((index, chunk)) = next else { break };
+                        let chunk_nonce = derive_chunk_nonce(&nonce, index);
+                        let sealed = cipher
+                            .encrypt(&chacha20poly1305::XNonce::from(chunk_nonce), &chunk[..])
+                            .map(|ct| (index, ct))
+                            .map_err(|e| {
+                                anyhow::anyhow!("Encryption error at chunk {}: {}", index, e)
+                            });
+                        if sealed_tx.send(sealed).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop((plain_rx, sealed_tx));
+
+            let written = write_in_order(sealed_rx, output, progress);
+            let read = reader.join().expect("reader thread panicked");
+            let (total_chunks, original_size) = written?;
+            read?;
+            Ok(EncryptedHeader {
+                nonce: nonce_bytes,
+                total_chunks,
+                original_size,
+                chunk_size,
+            })
+        })
+    }
+}
+
+/// Write sealed chunks as they come back from the pool, in index order.
+/// Returns the chunk count and plaintext size.
+fn write_in_order(
+    sealed: std::sync::mpsc::Receiver<anyhow::Result<(u64, Vec<u8>)>>,
+    output: &mut impl Write,
+    progress: impl Fn(u64),
+) -> anyhow::Result<(u64, u64)> {
+    // Chunks that finished before the one due next
+    let mut ahead = std::collections::BTreeMap::new();
+    let mut next: u64 = 0;
+    let mut bytes: u64 = 0;
+    for result in sealed {
+        let (index, ct) = result?;
+        ahead.insert(index, ct);
+        while let Some(ct) = ahead.remove(&next) {
+            output.write_all(&(ct.len() as u32).to_le_bytes())?;
+            output.write_all(&ct)?;
+            bytes += (ct.len() - AUTH_TAG_SIZE) as u64;
+            next += 1;
+            progress(bytes);
+        }
+    }
+    Ok((next, bytes))
+}
+
 /// Encrypt file streaming from disk → encrypted temp file on disk.
-/// Memory usage: constant ~128KB regardless of file size.
+/// Memory usage: a few chunks per core regardless of file size.
 pub fn encrypt_file_to_disk(
-    input: &mut impl Read,
+    input: &mut (impl Read + Send),
     key: &EncryptionKey,
     _original_size: u64,
     progress_callback: impl Fn(u64),
 ) -> anyhow::Result<EncryptedFileInfo> {
-    let cipher = XChaCha20Poly1305::new_from_slice(&key.0)
-        .map_err(|e| anyhow::anyhow!("Cipher init error: {}", e))?;
-
-    let mut nonce_bytes = [0u8; 24];
-    rand::rng().fill_bytes(&mut nonce_bytes);
-    let nonce = chacha20poly1305::XNonce::from(nonce_bytes);
-
     // Create temp file for encrypted output
     // NOTE: tempfile::NamedTempFile::new() already uses short random names
     // like /tmp/.tmpXXXXXX, so this is safe on Windows (no long path issue)
@@ -261,46 +550,12 @@ pub fn encrypt_file_to_disk(
 
     // Write placeholder header (we'll update chunk count after)
     writer.write_all(&[0u8; EncryptedHeader::SIZE])?;
-
-    let mut buf = vec![0u8; CHUNK_SIZE];
-    let mut chunk_index: u64 = 0;
-    let mut bytes_processed: u64 = 0;
-
-    loop {
-        let bytes_read = read_exact_or_eof(input, &mut buf)?;
-        if bytes_read == 0 {
-            break;
-        }
-
-        let chunk_nonce = derive_chunk_nonce(&nonce, chunk_index);
-        let encrypted = cipher
-            .encrypt(
-                &chacha20poly1305::XNonce::from(chunk_nonce),
-                &buf[..bytes_read],
-            )
-            .map_err(|e| anyhow::anyhow!("Encryption error at chunk {}: {}", chunk_index, e))?;
-
-        // Write: [chunk_len (4 bytes LE)][encrypted_chunk_with_auth_tag]
-        let len = (encrypted.len() as u32).to_le_bytes();
-        writer.write_all(&len)?;
-        writer.write_all(&encrypted)?;
-
-        bytes_processed += bytes_read as u64;
-        chunk_index += 1;
-        progress_callback(bytes_processed);
-    }
-
+    let header = Pipeline::default().encrypt(input, key, &mut writer, progress_callback)?;
     writer.flush()?;
 
     // Seek back and write the real header with actual chunk count
     let mut file = writer.into_inner()?;
     file.seek(SeekFrom::Start(0))?;
-
-    let header = EncryptedHeader {
-        nonce: nonce_bytes,
-        total_chunks: chunk_index,
-        original_size: bytes_processed,
-    };
     file.write_all(&header.to_bytes())?;
     file.flush()?;
 
@@ -309,68 +564,721 @@ pub fn encrypt_file_to_disk(
     Ok(EncryptedFileInfo {
         path: temp_path,
         total_size,
-        original_size: bytes_processed,
-        total_chunks: chunk_index,
+        original_size: header.original_size,
+        total_chunks: header.total_chunks,
     })
 }
 
+/// A standard container served while it is still being written. The
+/// header goes down first, sized from the plaintext length the caller
+/// already knows, so readers can start on it before the chunks exist.
+/// An input whose length nobody knows yet (a folder archived as it is
+/// sealed) gets a header with its size left open instead, filled in once
+/// the input runs out.
+pub struct GrowingContainer {
+    header: EncryptedHeader,
+}
+
+/// What an open-size header carries until it is filled in: no reader
+/// could take it for a whole (or an empty) container
+const OPEN_SIZE: u64 = u64::MAX;
+
+impl GrowingContainer {
+    /// Write the header for `plaintext_len` bytes in `chunk_size` chunks
+    pub fn start(
+        output: &mut impl Write,
+        plaintext_len: u64,
+        chunk_size: usize,
+    ) -> std::io::Result<Self> {
+        let header = EncryptedHeader {
+            nonce: fresh_nonce(),
+            total_chunks: plaintext_len.div_ceil(chunk_size as u64),
+            original_size: plaintext_len,
+            chunk_size,
+        };
+        output.write_all(&header.to_bytes())?;
+        output.flush()?;
+        Ok(Self { header })
+    }
+
+    /// Write a header for input of a length not known yet, in
+    /// `chunk_size` chunks. Seal it with [`GrowingContainer::seal_open`].
+    pub fn start_open(output: &mut impl Write, chunk_size: usize) -> std::io::Result<Self> {
+        let header = EncryptedHeader {
+            nonce: fresh_nonce(),
+            total_chunks: OPEN_SIZE,
+            original_size: OPEN_SIZE,
+            chunk_size,
+        };
+        output.write_all(&header.to_bytes())?;
+        output.flush()?;
+        Ok(Self { header })
+    }
+
+    pub fn header(&self) -> &EncryptedHeader {
+        &self.header
+    }
+
+    /// Encrypt `input` behind the header. Each chunk is handed to `output`
+    /// before `progress` reports it, so pass an unbuffered writer. Fails if
+    /// the input was not the announced length — the header would be wrong.
+    pub fn seal(
+        self,
+        input: &mut (impl Read + Send),
+        key: &EncryptionKey,
+        output: &mut impl Write,
+        progress: impl Fn(u64),
+    ) -> anyhow::Result<EncryptedHeader> {
+        let pipeline = Pipeline {
+            chunk_size: self.header.chunk_size,
+            ..Default::default()
+        };
+        let sealed =
+            pipeline.encrypt_with_nonce(input, key, self.header.nonce, output, progress)?;
+        if sealed.total_chunks != self.header.total_chunks
+            || sealed.original_size != self.header.original_size
+        {
+            anyhow::bail!(
+                "Input changed while encrypting: expected {} bytes, read {}",
+                self.header.original_size,
+                sealed.original_size
+            );
+        }
+        Ok(sealed)
+    }
+
+    /// Encrypt `input` to its end behind an open-size header, then write
+    /// the header again with the chunk count and length it came to
+    pub fn seal_open(
+        self,
+        input: &mut (impl Read + Send),
+        key: &EncryptionKey,
+        output: &mut (impl Write + Seek),
+        progress: impl Fn(u64),
+    ) -> anyhow::Result<EncryptedHeader> {
+        let pipeline = Pipeline {
+            chunk_size: self.header.chunk_size,
+            ..Default::default()
+        };
+        let sealed =
+            pipeline.encrypt_with_nonce(input, key, self.header.nonce, output, progress)?;
+        output.seek(SeekFrom::Start(0))?;
+        output.write_all(&sealed.to_bytes())?;
+        output.flush()?;
+        output.seek(SeekFrom::End(0))?;
+        Ok(sealed)
+    }
+}
+
 /// Legacy in-memory encrypt (kept for backward compat / small files)
 pub fn encrypt_file_streaming(
-    reader: &mut impl Read,
+    reader: &mut (impl Read + Send),
     key: &EncryptionKey,
     file_size: u64,
     progress_callback: impl Fn(u64),
 ) -> anyhow::Result<Vec<u8>> {
-    let cipher = XChaCha20Poly1305::new_from_slice(&key.0)
-        .map_err(|e| anyhow::anyhow!("Cipher init error: {}", e))?;
+    Pipeline::default().encrypt_to_vec(reader, key, file_size, progress_callback)
+}
 
-    let mut nonce_bytes = [0u8; 24];
-    rand::rng().fill_bytes(&mut nonce_bytes);
-    let nonce = chacha20poly1305::XNonce::from(nonce_bytes);
+// ═══════════════════════════════════════════════════════════════════════════
+// Deniable two-slot container (password drops)
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Layout: [slot 0][slot 1], each slot a standard container of identical size.
+// One slot holds the real payload, the other either a decoy (under a second
+// password) or random filler framed exactly like ciphertext. Slot order is
+// random, so neither the byte count nor the position reveals a decoy.
+//
+// Slot plaintext: [data_len u64 LE][name_len u16 LE][name][data][zero padding]
 
-    let estimated_size = file_size as usize
-        + (file_size as usize / CHUNK_SIZE + 1) * (AUTH_TAG_SIZE + 4)
-        + EncryptedHeader::SIZE;
+/// Number of slots in a deniable container
+pub const DENIABLE_SLOTS: u64 = 2;
 
-    let mut ciphertext = Vec::with_capacity(estimated_size);
-    ciphertext.extend_from_slice(&[0u8; EncryptedHeader::SIZE]);
+/// Size of the length prefix at the start of every slot plaintext
+const SLOT_PREFIX_SIZE: usize = 8 + 2;
 
-    let mut buf = vec![0u8; CHUNK_SIZE];
-    let mut chunk_index: u64 = 0;
-    let mut bytes_processed: u64 = 0;
+/// One payload to seal into a deniable slot
+pub struct SlotInput<'a> {
+    pub reader: &'a mut (dyn Read + Send),
+    pub len: u64,
+    pub filename: String,
+    pub key: &'a EncryptionKey,
+}
 
-    loop {
-        let bytes_read = read_exact_or_eof(reader, &mut buf)?;
-        if bytes_read == 0 {
-            break;
+impl SlotInput<'_> {
+    fn padded_len(&self) -> u64 {
+        (SLOT_PREFIX_SIZE + self.filename.len()) as u64 + self.len
+    }
+}
+
+/// Where a deniable container's bytes go: both slots share this layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeniableLayout {
+    pub padded_len: u64,   // Plaintext per slot, prefix and padding included
+    pub chunk_size: usize, // Plaintext per chunk, the same in both slots
+    pub slot_size: u64,    // Sealed bytes per slot
+    pub chunks: u64,       // Chunks per slot
+}
+
+impl DeniableLayout {
+    /// Plaintext `encrypt_deniable` processes across both slots
+    pub fn plaintext_len(&self) -> u64 {
+        self.padded_len * DENIABLE_SLOTS
+    }
+
+    /// Size of the whole container
+    pub fn size(&self) -> u64 {
+        self.slot_size * DENIABLE_SLOTS
+    }
+
+    /// Whether `input` on its own would land in a smaller bucket. Opened,
+    /// its slot then shows more padding than a drop of it alone would
+    /// have: a hint that the other slot holds something bigger.
+    pub fn outgrows(&self, input: &SlotInput<'_>) -> bool {
+        input.padded_len().next_power_of_two() < self.padded_len
+    }
+}
+
+/// Layout for a real payload and an optional decoy cut into `chunk_size`
+/// chunks. Both slots are padded to the power of two at or above the
+/// larger of the two, so opening either one tells its bucket and nothing
+/// finer about the other slot's size.
+pub fn deniable_layout(
+    real: &SlotInput<'_>,
+    decoy: Option<&SlotInput<'_>>,
+    chunk_size: usize,
+) -> DeniableLayout {
+    let padded_len = real
+        .padded_len()
+        .max(decoy.map(|d| d.padded_len()).unwrap_or(0))
+        .next_power_of_two();
+    DeniableLayout {
+        padded_len,
+        chunk_size,
+        slot_size: encrypted_len_with(padded_len, chunk_size),
+        chunks: padded_len.div_ceil(chunk_size as u64),
+    }
+}
+
+/// Seal a slot's plaintext on the parallel pipeline. `progress` gets
+/// `done` plus the slot's bytes.
+fn seal_slot(
+    writer: &mut impl Write,
+    plaintext: &mut (impl Read + Send),
+    key: &EncryptionKey,
+    layout: &DeniableLayout,
+    done: u64,
+    progress: &impl Fn(u64),
+) -> anyhow::Result<()> {
+    let container = GrowingContainer::start(writer, layout.padded_len, layout.chunk_size)?;
+    container.seal(plaintext, key, writer, |bytes| progress(done + bytes))?;
+    Ok(())
+}
+
+/// Seal a payload into one slot, padded with zeros to the layout
+fn write_sealed_slot(
+    writer: &mut impl Write,
+    input: &mut SlotInput<'_>,
+    layout: &DeniableLayout,
+    done: u64,
+    progress: &impl Fn(u64),
+) -> anyhow::Result<()> {
+    let mut prefix = Vec::with_capacity(SLOT_PREFIX_SIZE + input.filename.len());
+    prefix.extend_from_slice(&input.len.to_le_bytes());
+    prefix.extend_from_slice(&(input.filename.len() as u16).to_le_bytes());
+    prefix.extend_from_slice(input.filename.as_bytes());
+    let padding = layout.padded_len - input.padded_len();
+
+    let mut plaintext = std::io::Cursor::new(prefix)
+        .chain((&mut input.reader).take(input.len))
+        .chain(std::io::repeat(0).take(padding));
+    seal_slot(writer, &mut plaintext, input.key, layout, done, progress)
+}
+
+/// Seal zeros under a key nobody keeps. The slot is framed exactly like a
+/// sealed one and, as it goes through the same pipeline, lands on disk at
+/// the same pace — a drop served while sealing can't give the filler away.
+fn write_filler_slot(
+    writer: &mut impl Write,
+    layout: &DeniableLayout,
+    done: u64,
+    progress: &impl Fn(u64),
+) -> anyhow::Result<()> {
+    let mut zeros = std::io::repeat(0).take(layout.padded_len);
+    let throwaway = EncryptionKey::generate();
+    seal_slot(writer, &mut zeros, &throwaway, layout, done, progress)
+}
+
+/// Seal a real payload and an optional decoy into a two-slot container
+/// of `chunk_size` chunks.
+///
+/// Both slots follow [`deniable_layout`]. Without a decoy the second slot
+/// is filler. Each slot's bytes are handed to `writer` before `progress`
+/// reports them, so an unbuffered file can be served as it grows.
+pub fn encrypt_deniable(
+    writer: &mut impl Write,
+    mut real: SlotInput<'_>,
+    mut decoy: Option<SlotInput<'_>>,
+    chunk_size: usize,
+    progress_callback: impl Fn(u64),
+) -> anyhow::Result<DeniableLayout> {
+    let layout = deniable_layout(&real, decoy.as_ref(), chunk_size);
+
+    let real_first = rand::random::<bool>();
+    for slot in 0..DENIABLE_SLOTS {
+        let done = slot * layout.padded_len;
+        if (slot == 0) == real_first {
+            write_sealed_slot(writer, &mut real, &layout, done, &progress_callback)?;
+        } else if let Some(ref mut decoy) = decoy {
+            write_sealed_slot(writer, decoy, &layout, done, &progress_callback)?;
+        } else {
+            write_filler_slot(writer, &layout, done, &progress_callback)?;
+        }
+    }
+    writer.flush()?;
+
+    Ok(layout)
+}
+
+/// Open whichever slot of a deniable container `key` unlocks.
+/// Returns the stored filename and the unpadded payload.
+pub fn decrypt_deniable(data: &[u8], key: &EncryptionKey) -> anyhow::Result<(String, Vec<u8>)> {
+    if data.is_empty() || !(data.len() as u64).is_multiple_of(DENIABLE_SLOTS) {
+        anyhow::bail!("Not a deniable container");
+    }
+    let slot_len = data.len() / DENIABLE_SLOTS as usize;
+
+    for slot in data.chunks(slot_len) {
+        if let Ok(mut plaintext) = decrypt_container(slot, key) {
+            let result = unpack_slot_plaintext(&plaintext);
+            plaintext.zeroize();
+            return result;
         }
+    }
+    anyhow::bail!("No slot could be opened — wrong password or corrupted data")
+}
+
+/// Strip the length prefix and padding from a decrypted slot
+fn unpack_slot_plaintext(plaintext: &[u8]) -> anyhow::Result<(String, Vec<u8>)> {
+    if plaintext.len() < SLOT_PREFIX_SIZE {
+        anyhow::bail!("Slot too short");
+    }
+    let data_len = u64::from_le_bytes(plaintext[..8].try_into()?) as usize;
+    let name_len = u16::from_le_bytes(plaintext[8..10].try_into()?) as usize;
+    let name_end = SLOT_PREFIX_SIZE + name_len;
+    if name_end + data_len > plaintext.len() {
+        anyhow::bail!("Slot length prefix exceeds slot size");
+    }
+    let filename = String::from_utf8_lossy(&plaintext[SLOT_PREFIX_SIZE..name_end]).to_string();
+    Ok((filename, plaintext[name_end..name_end + data_len].to_vec()))
+}
 
+/// Decrypt a single standard container held in memory
+pub fn decrypt_container(data: &[u8], key: &EncryptionKey) -> anyhow::Result<Vec<u8>> {
+    let header = EncryptedHeader::from_bytes(data)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(&key.0)
+        .map_err(|e| anyhow::anyhow!("Cipher init error: {}", e))?;
+    let nonce = chacha20poly1305::XNonce::from(header.nonce);
+
+    let mut plaintext = Vec::with_capacity(header.original_size as usize);
+    let mut offset = header.encoded_len();
+    for chunk_index in 0..header.total_chunks {
+        if offset + 4 > data.len() {
+            anyhow::bail!("Truncated chunk length at chunk {}", chunk_index);
+        }
+        let chunk_len = u32::from_le_bytes(data[offset..offset + 4].try_into()?) as usize;
+        if chunk_len > header.chunk_size + AUTH_TAG_SIZE {
+            anyhow::bail!(
+                "Chunk {} is larger than the recorded chunk size",
+                chunk_index
+            );
+        }
+        offset += 4;
+        if offset + chunk_len > data.len() {
+            anyhow::bail!("Truncated chunk data at chunk {}", chunk_index);
+        }
         let chunk_nonce = derive_chunk_nonce(&nonce, chunk_index);
-        let encrypted = cipher
-            .encrypt(
+        let decrypted = cipher
+            .decrypt(
                 &chacha20poly1305::XNonce::from(chunk_nonce),
-                &buf[..bytes_read],
+                &data[offset..offset + chunk_len],
             )
-            .map_err(|e| anyhow::anyhow!("Encryption error at chunk {}: {}", chunk_index, e))?;
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Decryption failed at chunk {} — wrong key or corrupted",
+                    chunk_index
+                )
+            })?;
+        plaintext.extend_from_slice(&decrypted);
+        offset += chunk_len;
+    }
+    Ok(plaintext)
+}
+
+/// Decrypts a standard container as its bytes arrive, for consumers that
+/// can't wait for the whole thing (`ded receive -o -`). Each chunk's tag is
+/// checked before its plaintext is handed out; [`StreamDecryptor::finish`]
+/// catches a stream that stops short.
+pub struct StreamDecryptor {
+    cipher: XChaCha20Poly1305,
+    header: Option<EncryptedHeader>,
+    /// Bytes of the chunk (or header) not complete yet
+    pending: Vec<u8>,
+    next_chunk: u64,
+    produced: u64,
+    hasher: sha2::Sha256,
+}
+
+impl StreamDecryptor {
+    pub fn new(key: &EncryptionKey) -> anyhow::Result<Self> {
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new_from_slice(&key.0)
+                .map_err(|e| anyhow::anyhow!("Cipher init error: {}", e))?,
+            header: None,
+            pending: Vec::new(),
+            next_chunk: 0,
+            produced: 0,
+            hasher: sha2::Digest::new(),
+        })
+    }
+
+    /// Plaintext size from the header, once it has arrived
+    pub fn original_size(&self) -> Option<u64> {
+        self.header.as_ref().map(|h| h.original_size)
+    }
+
+    /// Feed the next ciphertext bytes; returns the plaintext of every chunk
+    /// they complete
+    pub fn push(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.pending.extend_from_slice(data);
+        if self.header.is_none() {
+            let header_len = EncryptedHeader::len_of(&self.pending);
+            if self.pending.len() < header_len {
+                return Ok(Vec::new());
+            }
+            self.header = Some(EncryptedHeader::from_bytes(&self.pending)?);
+            self.pending.drain(..header_len);
+        }
+        let header = self.header.as_ref().unwrap();
+        let nonce = chacha20poly1305::XNonce::from(header.nonce);
+
+        let mut plaintext = Vec::new();
+        let mut offset = 0;
+        while self.next_chunk < header.total_chunks && self.pending.len() >= offset + 4 {
+            let chunk_len =
+                u32::from_le_bytes(self.pending[offset..offset + 4].try_into()?) as usize;
+            if chunk_len > header.chunk_size + AUTH_TAG_SIZE {
+                anyhow::bail!("Chunk {} is too large", self.next_chunk);
+            }
+            if self.pending.len() < offset + 4 + chunk_len {
+                break;
+            }
+            let chunk_nonce = derive_chunk_nonce(&nonce, self.next_chunk);
+            let decrypted = self
+                .cipher
+                .decrypt(
+                    &chacha20poly1305::XNonce::from(chunk_nonce),
+                    &self.pending[offset + 4..offset + 4 + chunk_len],
+                )
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "Decryption failed at chunk {} — wrong key or corrupted",
+                        self.next_chunk
+                    )
+                })?;
+            plaintext.extend_from_slice(&decrypted);
+            offset += 4 + chunk_len;
+            self.next_chunk += 1;
+        }
+        self.pending.drain(..offset);
+
+        sha2::Digest::update(&mut self.hasher, &plaintext);
+        self.produced += plaintext.len() as u64;
+        if self.produced > header.original_size {
+            anyhow::bail!("More data than the header announced");
+        }
+        if self.next_chunk == header.total_chunks && !self.pending.is_empty() {
+            anyhow::bail!("Trailing data after the last chunk");
+        }
+        Ok(plaintext)
+    }
+
+    /// The stream is over: every chunk must be in. Returns the plaintext size.
+    pub fn finish(&self) -> anyhow::Result<u64> {
+        let Some(header) = &self.header else {
+            anyhow::bail!("Data too short for header");
+        };
+        if self.next_chunk < header.total_chunks {
+            anyhow::bail!("Truncated at chunk {}", self.next_chunk);
+        }
+        if self.produced != header.original_size {
+            anyhow::bail!("Size does not match the header");
+        }
+        Ok(self.produced)
+    }
 
-        let len = (encrypted.len() as u32).to_le_bytes();
-        ciphertext.extend_from_slice(&len);
-        ciphertext.extend_from_slice(&encrypted);
+    /// SHA-256 of the plaintext handed out so far
+    pub fn sha256(&self) -> [u8; 32] {
+        sha2::Digest::finalize(self.hasher.clone()).into()
+    }
+}
+
+/// Reads a container as its plaintext, a chunk at a time, so an upload
+/// spooled to disk is decrypted on its way to the output without ever
+/// being in memory whole. Hitting the end of the container checks that
+/// every chunk was there and, with `expected`, that the plaintext has the
+/// sender's SHA-256. A container that fails either way, or a chunk that
+/// does not decrypt, is an `InvalidData` error and is kept in
+/// [`DecryptingReader::failure`].
+pub struct DecryptingReader<R> {
+    inner: R,
+    decryptor: StreamDecryptor,
+    expected: Option<[u8; 32]>,
+    /// Plaintext decrypted and not yet read
+    buffer: Vec<u8>,
+    position: usize,
+    done: bool,
+    failure: Option<String>,
+}
+
+impl<R: Read> DecryptingReader<R> {
+    /// Read the header off `inner`
+    pub fn new(
+        mut inner: R,
+        key: &EncryptionKey,
+        expected: Option<[u8; 32]>,
+    ) -> anyhow::Result<Self> {
+        let mut decryptor = StreamDecryptor::new(key)?;
+        let mut header = [0u8; EncryptedHeader::SIZED];
+        let n = read_exact_or_eof(&mut inner, &mut header)?;
+        let buffer = decryptor.push(&header[..n])?;
+        if decryptor.original_size().is_none() {
+            anyhow::bail!("Data too short for header");
+        }
+        Ok(Self {
+            inner,
+            decryptor,
+            expected,
+            buffer,
+            position: 0,
+            done: false,
+            failure: None,
+        })
+    }
 
-        bytes_processed += bytes_read as u64;
-        chunk_index += 1;
-        progress_callback(bytes_processed);
+    /// Plaintext size from the header
+    pub fn original_size(&self) -> u64 {
+        self.decryptor.original_size().unwrap_or(0)
     }
 
-    let header = EncryptedHeader {
-        nonce: nonce_bytes,
-        total_chunks: chunk_index,
-        original_size: bytes_processed,
+    /// SHA-256 of the plaintext read so far
+    pub fn sha256(&self) -> [u8; 32] {
+        self.decryptor.sha256()
+    }
+
+    /// Why the container was refused, if it was: `Decryption failed: …` or
+    /// `Integrity check failed: …`
+    pub fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+
+    fn refill(&mut self) -> std::io::Result<()> {
+        if self.position < self.buffer.len() || self.done {
+            return Ok(());
+        }
+        let mut ciphertext = vec![0u8; CHUNK_SIZE + AUTH_TAG_SIZE + 4];
+        while self.position == self.buffer.len() && !self.done {
+            let n = self.inner.read(&mut ciphertext)?;
+            if n == 0 {
+                self.done = true;
+                if let Err(e) = self.decryptor.finish() {
+                    return Err(self.refuse(format!("Decryption failed: {}", e)));
+                }
+                if self
+                    .expected
+                    .is_some_and(|sha| sha != self.decryptor.sha256())
+                {
+                    return Err(self.refuse(
+                        "Integrity check failed: SHA-256 does not match the sender's — the file was altered in transit"
+                            .into(),
+                    ));
+                }
+                break;
+            }
+            match self.decryptor.push(&ciphertext[..n]) {
+                Ok(plaintext) => self.buffer = plaintext,
+                Err(e) => return Err(self.refuse(format!("Decryption failed: {}", e))),
+            }
+            self.position = 0;
+        }
+        Ok(())
+    }
+
+    fn refuse(&mut self, failure: String) -> std::io::Error {
+        self.failure = Some(failure.clone());
+        std::io::Error::new(std::io::ErrorKind::InvalidData, failure)
+    }
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(failure) = &self.failure {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                failure.clone(),
+            ));
+        }
+        self.refill()?;
+        let n = buf.len().min(self.buffer.len() - self.position);
+        buf[..n].copy_from_slice(&self.buffer[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+/// Passes reads through while taking the SHA-256 of the plaintext, so the
+/// digest a recipient's receipt refers to comes out of the encryption pass
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: sha2::Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub fn new(inner: R) -> Self {
+        use sha2::Digest;
+        Self {
+            inner,
+            hasher: sha2::Sha256::new(),
+        }
+    }
+
+    /// SHA-256 of everything read so far
+    pub fn finish(self) -> [u8; 32] {
+        use sha2::Digest;
+        self.hasher.finalize().into()
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use sha2::Digest;
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Plaintext digest
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// The SHA-256 of the plaintext travels sealed under the content key, so the
+// server (or a tunnel) learns nothing from it, while whoever holds the key
+// can check the whole file came out exactly as it went in — chunk tags alone
+// don't cover a stream cut short with a doctored chunk count.
+//
+// Sealed digest (URL-safe base64): [nonce 24][sha256 32 + tag 16]
+
+/// Associated data binding a sealed digest to its purpose
+const DIGEST_CONTEXT: &[u8] = b"deadrop-digest-v1";
+
+/// SHA-256 of `data`
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    use sha2::Digest;
+    sha2::Sha256::digest(data).into()
+}
+
+/// Lowercase hex, as digests are shown
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Seal a plaintext digest under the content key
+pub fn seal_digest(key: &EncryptionKey, digest: &[u8; 32]) -> anyhow::Result<String> {
+    use base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD};
+    use chacha20poly1305::aead::Payload;
+    let cipher = XChaCha20Poly1305::new_from_slice(&key.0)
+        .map_err(|e| anyhow::anyhow!("Cipher init error: {}", e))?;
+    let mut nonce = [0u8; 24];
+    rand::rng().fill_bytes(&mut nonce);
+    let sealed = cipher
+        .encrypt(
+            &chacha20poly1305::XNonce::from(nonce),
+            Payload {
+                msg: digest,
+                aad: DIGEST_CONTEXT,
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("Digest encryption error: {}", e))?;
+    Ok(URL_SAFE_NO_PAD.encode([&nonce[..], &sealed].concat()))
+}
+
+/// Seal one digest per deniable slot, joined by `.` in random order: the
+/// real payload's under `real`'s key, the decoy's under its own, or
+/// without a decoy random bytes under a key nobody keeps. Each password
+/// opens its own, and nothing tells whether the other opens at all.
+pub fn seal_slot_digests(
+    real: (&EncryptionKey, &[u8; 32]),
+    decoy: Option<(&EncryptionKey, &[u8; 32])>,
+) -> anyhow::Result<String> {
+    let real = seal_digest(real.0, real.1)?;
+    let other = match decoy {
+        Some((key, digest)) => seal_digest(key, digest)?,
+        None => {
+            let mut filler = [0u8; 32];
+            rand::rng().fill_bytes(&mut filler);
+            seal_digest(&EncryptionKey::generate(), &filler)?
+        }
     };
-    let header_bytes = header.to_bytes();
-    ciphertext[..EncryptedHeader::SIZE].copy_from_slice(&header_bytes);
+    Ok(match rand::random::<bool>() {
+        true => format!("{}.{}", real, other),
+        false => format!("{}.{}", other, real),
+    })
+}
+
+/// Open a digest sealed with [`seal_digest`], or whichever of
+/// [`seal_slot_digests`]' `key` opens
+pub fn open_digest(key: &EncryptionKey, sealed: &str) -> anyhow::Result<[u8; 32]> {
+    let mut opened = Err(anyhow::anyhow!("Malformed digest"));
+    for part in sealed.trim().split('.') {
+        opened = open_sealed_digest(key, part);
+        if opened.is_ok() {
+            break;
+        }
+    }
+    opened
+}
+
+fn open_sealed_digest(key: &EncryptionKey, sealed: &str) -> anyhow::Result<[u8; 32]> {
+    use base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD};
+    use chacha20poly1305::aead::Payload;
+    let sealed = URL_SAFE_NO_PAD
+        .decode(sealed)
+        .map_err(|e| anyhow::anyhow!("Malformed digest: {}", e))?;
+    if sealed.len() != 24 + 32 + AUTH_TAG_SIZE {
+        anyhow::bail!("Malformed digest: {} bytes", sealed.len());
+    }
+    let cipher = XChaCha20Poly1305::new_from_slice(&key.0)
+        .map_err(|e| anyhow::anyhow!("Cipher init error: {}", e))?;
+    let nonce: [u8; 24] = sealed[..24].try_into()?;
+    let digest = cipher
+        .decrypt(
+            &chacha20poly1305::XNonce::from(nonce),
+            Payload {
+                msg: &sealed[24..],
+                aad: DIGEST_CONTEXT,
+            },
+        )
+        .map_err(|_| anyhow::anyhow!("Digest does not open with this key"))?;
+    Ok(digest.try_into().unwrap())
+}
 
-    Ok(ciphertext)
+/// Check a plaintext's digest against the one the sender sealed
+pub fn verify_digest(key: &EncryptionKey, sealed: &str, digest: &[u8; 32]) -> anyhow::Result<()> {
+    if open_digest(key, sealed)? != *digest {
+        anyhow::bail!("SHA-256 does not match the sender's — the file was altered in transit");
+    }
+    Ok(())
 }
 
 /// Read exactly buf.len() bytes or fewer if EOF
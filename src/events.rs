@@ -0,0 +1,242 @@
+//! Structured lifecycle events for scripting (`--json`).
+//!
+//! Send and receive mode report what happens to a drop — ready, sealed,
+//! download started/completed/aborted, receipts, blocked, expired, burned,
+//! received, quarantined, receive closed — as [`Event`]s. Each one is
+//! stamped and handed to every registered [`Sink`]; `--json` registers
+//! [`JsonLines`], which writes one object per line to stdout while the
+//! human-readable output stays on stderr; the hooks in [`crate::hooks`]
+//! are another sink.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Every address a drop (or receive page) is reachable at
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Urls {
+    pub lan: String,
+    pub localhost: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tunnel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onion: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Listening; the links are live
+    Ready {
+        mode: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        drop_id: Option<String>,
+        urls: Urls,
+        key_fragment: String,
+        expires_at: DateTime<Utc>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_downloads: Option<u32>,
+    },
+    /// A drop is being served. The digest is of the ciphertext, so it
+    /// identifies what was served without revealing it; a drop still
+    /// encrypting has none yet and reports it in [`Event::Sealed`].
+    Created {
+        drop_id: String,
+        size: u64,
+        encrypted_size: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sha256: Option<String>,
+        expires_at: DateTime<Utc>,
+        max_downloads: u32,
+    },
+    /// A drop served while it was still encrypting finished sealing.
+    /// `sha256` is of the ciphertext, as in [`Event::Created`]. A folder
+    /// archived as it sealed has its sizes here; `created` had them as 0.
+    Sealed {
+        drop_id: String,
+        size: u64,
+        encrypted_size: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sha256: Option<String>,
+    },
+    DownloadStarted {
+        drop_id: String,
+        session: u64,
+        ip: String,
+        transport: &'static str,
+    },
+    DownloadCompleted {
+        drop_id: String,
+        session: u64,
+        ip: String,
+        transport: &'static str,
+        bytes: u64,
+        count: u32,
+        max_downloads: u32,
+    },
+    DownloadAborted {
+        drop_id: String,
+        session: u64,
+        ip: String,
+        transport: &'static str,
+        bytes: u64,
+    },
+    /// The access policy refused a request
+    Blocked {
+        drop_id: String,
+        ip: String,
+        reason: &'static str,
+    },
+    /// Refused because every download slot is taken (HTTP 410)
+    Exhausted {
+        drop_id: String,
+        ip: String,
+    },
+    Expired {
+        drop_id: String,
+    },
+    /// Destroyed before expiry: download limit reached or revoked
+    Burned {
+        drop_id: String,
+        reason: &'static str,
+    },
+    /// The recipient's page decrypted the whole drop and proved it with a
+    /// receipt. `decoy` means it was the duress password that opened it.
+    ReceiptVerified {
+        drop_id: String,
+        ip: String,
+        decoy: bool,
+    },
+    /// A receipt arrived whose MAC does not verify
+    ReceiptRejected {
+        drop_id: String,
+        ip: String,
+    },
+    /// Receive mode saved an upload. `sha256` is the plaintext's; `verified`
+    /// says the uploader sent a digest and it matched.
+    Received {
+        filename: String,
+        path: String,
+        size: u64,
+        sha256: String,
+        verified: bool,
+    },
+    /// The scanner did not pass an upload; it stays in quarantine at `path`.
+    /// `verdict` is "infected" or "scan_failed".
+    Quarantined {
+        filename: String,
+        path: String,
+        verdict: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        threat: Option<String>,
+    },
+    /// The receive session stopped taking uploads: the page finished, a
+    /// limit was reached, or the link expired
+    ReceiveClosed {
+        reason: &'static str,
+        files: u32,
+        size: u64,
+    },
+}
+
+/// An event with the time it happened — the unit sinks receive
+#[derive(Clone, Debug, Serialize)]
+pub struct Record {
+    pub ts: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// Somewhere events go
+pub trait Sink: Send + Sync {
+    fn emit(&self, record: &Record);
+
+    /// Records accepted but not yet delivered
+    fn pending(&self) -> usize {
+        0
+    }
+
+    /// The run is over; no more records will come
+    fn close(&self) {}
+}
+
+impl<S: Sink + ?Sized> Sink for Arc<S> {
+    fn emit(&self, record: &Record) {
+        (**self).emit(record)
+    }
+
+    fn pending(&self) -> usize {
+        (**self).pending()
+    }
+
+    fn close(&self) {
+        (**self).close()
+    }
+}
+
+/// Fan-out to every registered sink. Cheap to clone; with no sinks,
+/// emitting is a no-op.
+#[derive(Clone, Default)]
+pub struct Events {
+    sinks: Vec<Arc<dyn Sink>>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_sink(mut self, sink: impl Sink + 'static) -> Self {
+        self.sinks.push(Arc::new(sink));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    pub fn emit(&self, event: Event) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        let record = Record {
+            ts: Utc::now(),
+            event,
+        };
+        for sink in &self.sinks {
+            sink.emit(&record);
+        }
+    }
+
+    /// Wait (up to `timeout`) for sinks to deliver what they have queued,
+    /// so the last events of a run aren't lost on exit
+    pub async fn settle(&self, timeout: std::time::Duration) {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.sinks.iter().any(|s| s.pending() > 0) && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+    }
+
+    /// End of run: let queued deliveries settle, then close every sink
+    pub async fn finish(&self, timeout: std::time::Duration) {
+        self.settle(timeout).await;
+        for sink in &self.sinks {
+            sink.close();
+        }
+    }
+}
+
+/// Newline-delimited JSON on stdout
+pub struct JsonLines;
+
+impl Sink for JsonLines {
+    fn emit(&self, record: &Record) {
+        let Ok(line) = serde_json::to_string(record) else {
+            return;
+        };
+        let mut out = std::io::stdout().lock();
+        let _ = writeln!(out, "{}", line);
+        let _ = out.flush();
+    }
+}
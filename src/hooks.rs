@@ -0,0 +1,279 @@
+//! Lifecycle hooks: local commands (`--on-download`, `--on-expire`,
+//! `--on-receive`) and an HMAC-signed webhook (`--webhook`).
+//!
+//! Both are an [`events::Sink`]: they see the same records `--json` prints,
+//! except that `ready` loses its key — the link's `#fragment` is for the
+//! recipient, not for whatever runs the hook. Deliveries run in order on a
+//! background task so a slow endpoint never holds up a transfer.
+
+use crate::events::{Event, Record, Sink};
+use ring::hmac;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Commands get this long before they are killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+/// Webhook POST timeout, per attempt
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+/// What to run, and where to post, from the CLI
+#[derive(Clone, Debug, Default)]
+pub struct HookConfig {
+    /// Shell command run after each completed download
+    pub on_download: Option<String>,
+    /// Shell command run when a drop expires or burns
+    pub on_expire: Option<String>,
+    /// Shell command run after receive mode saves a file
+    pub on_receive: Option<String>,
+    /// URL that gets every event as a signed JSON POST
+    pub webhook: Option<String>,
+    /// HMAC-SHA256 key for `X-Deadrop-Signature`
+    pub webhook_secret: Option<String>,
+}
+
+impl HookConfig {
+    pub fn is_empty(&self) -> bool {
+        self.on_download.is_none()
+            && self.on_expire.is_none()
+            && self.on_receive.is_none()
+            && self.webhook.is_none()
+    }
+
+    /// Check the webhook URL and make sure there is a signing secret,
+    /// generating (and printing) one when none was given
+    pub fn prepare(mut self) -> anyhow::Result<Self> {
+        let Some(url) = &self.webhook else {
+            return Ok(self);
+        };
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| anyhow::anyhow!("Invalid --webhook URL '{}': {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            anyhow::bail!("--webhook must be an http:// or https:// URL");
+        }
+        if self.webhook_secret.is_none() {
+            use base64::Engine;
+            let mut secret = [0u8; 32];
+            rand::fill(&mut secret);
+            let secret = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(secret);
+            eprintln!(
+                " {} Webhook secret (verify X-Deadrop-Signature with it): {}",
+                console::style("🔑").bold(),
+                console::style(&secret).yellow()
+            );
+            self.webhook_secret = Some(secret);
+        }
+        Ok(self)
+    }
+
+    /// The command for an event, if one is configured
+    fn command_for(&self, event: &str) -> Option<&str> {
+        match event {
+            "download_completed" => self.on_download.as_deref(),
+            "expired" | "burned" => self.on_expire.as_deref(),
+            "received" => self.on_receive.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Hex `sha256=` signature of a webhook body
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+    let tag = hmac::sign(&key, body);
+    let hex: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+/// Runs configured hooks for each event, in order, off the request path
+pub struct Hooks {
+    queue: mpsc::UnboundedSender<Record>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl Hooks {
+    /// Start the delivery task. Must be called inside the Tokio runtime;
+    /// events may be emitted from any thread afterwards.
+    pub fn spawn(config: HookConfig) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()?;
+        let (queue, mut rx) = mpsc::unbounded_channel::<Record>();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let done = pending.clone();
+
+        tokio::spawn(async move {
+            while let Some(record) = rx.recv().await {
+                let Ok(body) = serde_json::to_vec(&redacted(&record)) else {
+                    done.fetch_sub(1, Ordering::SeqCst);
+                    continue;
+                };
+                let event = event_name(&record);
+                if let Some(command) = config.command_for(&event) {
+                    run_command(command, &event, &body).await;
+                }
+                if let Some(url) = &config.webhook {
+                    let secret = config.webhook_secret.as_deref().unwrap_or_default();
+                    post_webhook(&client, url, secret.as_bytes(), &event, body).await;
+                }
+                done.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+
+        Ok(Self { queue, pending })
+    }
+}
+
+impl Sink for Hooks {
+    fn emit(&self, record: &Record) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        if self.queue.send(record.clone()).is_err() {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+}
+
+/// The record as hooks get it: `ready` without `key_fragment`, and its
+/// URLs without their `#key`
+pub fn redacted(record: &Record) -> serde_json::Value {
+    let mut value = serde_json::to_value(record).unwrap_or_default();
+    if matches!(record.event, Event::Ready { .. }) {
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("key_fragment");
+        }
+        if let Some(urls) = value["urls"].as_object_mut() {
+            for url in urls.values_mut() {
+                if let Some((bare, _)) = url.as_str().and_then(|u| u.split_once('#')) {
+                    *url = bare.into();
+                }
+            }
+        }
+    }
+    value
+}
+
+fn event_name(record: &Record) -> String {
+    serde_json::to_value(record)
+        .ok()
+        .and_then(|v| v["event"].as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Run a hook through the shell. The event JSON arrives on stdin and its
+/// top-level fields as `DEADROP_*` environment variables.
+async fn run_command(command: &str, event: &str, body: &[u8]) {
+    use tokio::io::AsyncWriteExt;
+
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut c = tokio::process::Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut c = tokio::process::Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    };
+
+    if let Ok(serde_json::Value::Object(fields)) = serde_json::from_slice(body) {
+        for (key, value) in fields {
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => continue,
+            };
+            cmd.env(format!("DEADROP_{}", key.to_uppercase()), value);
+        }
+    }
+    cmd.stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .kill_on_drop(true);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!(
+                " {} Hook for {} failed to start: {}",
+                console::style("⚠").yellow(),
+                event,
+                e
+            );
+            return;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(body).await;
+    }
+
+    match tokio::time::timeout(COMMAND_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) if status.success() => {}
+        Ok(Ok(status)) => eprintln!(
+            " {} Hook for {} exited with {}",
+            console::style("⚠").yellow(),
+            event,
+            status
+        ),
+        Ok(Err(e)) => eprintln!(
+            " {} Hook for {} failed: {}",
+            console::style("⚠").yellow(),
+            event,
+            e
+        ),
+        Err(_) => eprintln!(
+            " {} Hook for {} timed out after {}s",
+            console::style("⚠").yellow(),
+            event,
+            COMMAND_TIMEOUT.as_secs()
+        ),
+    }
+}
+
+/// POST the event, retrying transient failures with a short backoff
+async fn post_webhook(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &[u8],
+    event: &str,
+    body: Vec<u8>,
+) {
+    let signature = sign(secret, &body);
+    let mut last_error = String::new();
+    for attempt in 0..WEBHOOK_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(Duration::from_millis(500 << attempt)).await;
+        }
+        let result = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("X-Deadrop-Event", event)
+            .header("X-Deadrop-Signature", &signature)
+            .body(body.clone())
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => return,
+            // The endpoint answered; retrying a rejection won't change it
+            Ok(resp) if resp.status().is_client_error() => {
+                last_error = format!("HTTP {}", resp.status());
+                break;
+            }
+            Ok(resp) => last_error = format!("HTTP {}", resp.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    eprintln!(
+        " {} Webhook for {} failed: {}",
+        console::style("⚠").yellow(),
+        event,
+        last_error
+    );
+}
@@ -1,11 +1,23 @@
 #![allow(dead_code)]
 
 pub mod archive;
+pub mod audit;
 pub mod config;
 pub mod crypto;
+pub mod events;
+pub mod hooks;
+pub mod metrics;
+pub mod policy;
 pub mod progress;
+pub mod proxy;
 pub mod qr;
+pub mod receipt;
+pub mod receive;
+pub mod scan;
 pub mod server;
 pub mod store;
+pub mod tls;
 pub mod tor;
+pub mod tui;
 pub mod tunnel;
+pub mod vault;
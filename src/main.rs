@@ -1,7 +1,9 @@
 #![allow(dead_code, unused_imports)]
 
 use clap::{Args, Parser, Subcommand};
-use deadrop::{archive, config, server, tor, tunnel};
+use deadrop::{
+    archive, audit, config, crypto, hooks, policy, proxy, receive, scan, server, tor, tunnel, vault,
+};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -22,11 +24,42 @@ struct Cli {
 enum Commands {
     /// Send file(s) — also works as `ded <files...>` without "send"
     #[command(alias = "s")]
-    Send(SendArgs),
+    Send(Box<SendArgs>),
 
-    /// Receive a file from another device (phone → PC)
+    /// Receive files from another device (phone → PC)
     #[command(alias = "r")]
-    Receive(ReceiveArgs),
+    Receive(Box<ReceiveArgs>),
+
+    /// Decrypt files received with `--vault`
+    Open(OpenArgs),
+
+    /// Work with `--audit-log` files
+    #[command(subcommand)]
+    Audit(AuditCommand),
+}
+
+#[derive(Args, Debug)]
+struct OpenArgs {
+    /// Sealed files (`*.age`) to decrypt
+    #[arg(value_name = "FILE", num_args = 1.., required = true)]
+    files: Vec<PathBuf>,
+
+    /// age identity file with the vault's secret key (from age-keygen)
+    #[arg(short = 'i', long, value_name = "FILE")]
+    identity: PathBuf,
+
+    /// Write the plaintext here (default: next to each file), or "-" for stdout
+    #[arg(short = 'o', long, value_name = "DIR")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum AuditCommand {
+    /// Check an audit log's hash chain for edits, reordering or removed entries
+    Verify {
+        /// Audit log file
+        log: PathBuf,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -66,21 +99,115 @@ struct SendArgs {
     /// Disable Cloudflare tunnel (local network only)
     #[arg(long)]
     no_tunnel: bool,
+
+    /// Serve HTTPS/WSS with an ephemeral self-signed certificate (pinned in the link)
+    #[arg(long)]
+    tls: bool,
+
+    /// Reverse proxy IP or CIDR whose X-Forwarded-For / CF-Connecting-IP is trusted. Repeatable.
+    #[arg(long = "trust-proxy", value_name = "CIDR")]
+    trust_proxy: Vec<String>,
+
     /// Recipient public keys (URL-safe base64 X25519 32-byte public keys). Repeatable.
     #[arg(long = "recipient")]
     recipients: Vec<String>,
+
+    /// Decoy file or folder revealed by the duress password (requires --pw)
+    #[arg(long, requires_all = ["password", "decoy_password"])]
+    decoy: Option<PathBuf>,
+
+    /// Duress password that opens the decoy instead of the real payload
+    #[arg(long = "decoy-pw", requires = "decoy")]
+    decoy_password: Option<String>,
+
+    /// Only allow downloads from this IP or CIDR. Repeatable.
+    #[arg(long, value_name = "CIDR")]
+    allow: Vec<String>,
+
+    /// Refuse downloads from this IP or CIDR (wins over --allow). Repeatable.
+    #[arg(long, value_name = "CIDR")]
+    deny: Vec<String>,
+
+    /// Only allow downloads from user agents containing this text (case-insensitive). Repeatable.
+    #[arg(long = "allow-ua", value_name = "TEXT")]
+    allow_ua: Vec<String>,
+
+    /// Refuse downloads from user agents containing this text (wins over --allow-ua). Repeatable.
+    #[arg(long = "deny-ua", value_name = "TEXT")]
+    deny_ua: Vec<String>,
+
+    /// Refuse downloads before this time (RFC 3339, or a delay like 10m)
+    #[arg(long, value_name = "TIME")]
+    not_before: Option<String>,
+
+    /// Only allow downloads between these local times each day (e.g. 09:00-17:00)
+    #[arg(long, value_name = "HH:MM-HH:MM")]
+    between: Option<String>,
+
+    /// Distinct devices allowed to download (1 = pin to first, 0 = unlimited)
+    #[arg(long, default_value_t = 1, conflicts_with = "token")]
+    max_ips: u32,
+
+    /// Require a one-time access token (in the link): one download, first device, within 10 minutes
+    #[arg(long)]
+    token: bool,
+
+    /// Full-screen dashboard: drops, countdowns, connections, blocked attempts, QR
+    #[arg(long)]
+    tui: bool,
+
+    /// Print lifecycle events as JSON lines on stdout (no banner or QR)
+    #[arg(long, conflicts_with = "tui")]
+    json: bool,
+
+    /// Run a shell command after each completed download (event JSON on stdin)
+    #[arg(long, value_name = "CMD")]
+    on_download: Option<String>,
+
+    /// Run a shell command when the drop expires or self-destructs
+    #[arg(long, value_name = "CMD")]
+    on_expire: Option<String>,
+
+    /// POST every event as HMAC-signed JSON to this URL
+    #[arg(long, value_name = "URL")]
+    webhook: Option<String>,
+
+    /// HMAC-SHA256 key for webhook signatures (generated and printed if omitted)
+    #[arg(long, value_name = "SECRET", requires = "webhook")]
+    webhook_secret: Option<String>,
+
+    /// Serve Prometheus metrics on this admin address (e.g. 127.0.0.1:9100)
+    #[arg(long, value_name = "ADDR")]
+    metrics: Option<String>,
+
+    /// Append a hash-chained audit record to this file (check with `ded audit verify`)
+    #[arg(long, value_name = "FILE")]
+    audit_log: Option<PathBuf>,
+
+    /// On shutdown, wait this long for running transfers to finish (0s = don't wait)
+    #[arg(long, value_name = "DURATION", default_value = "5m")]
+    drain_timeout: String,
+
+    /// Plaintext per encrypted chunk, 16KiB to 16MiB
+    #[arg(long, value_name = "SIZE", default_value = "64KiB")]
+    chunk_size: bytesize::ByteSize,
 }
 
 #[derive(Args, Debug)]
+#[command(group(clap::ArgGroup::new("scanner").args(["clamd", "scan_cmd"])))]
 struct ReceiveArgs {
     /// Port to listen on
     #[arg(short = 'p', long, default_value_t = 8080)]
     port: u16,
 
-    /// Output directory for received files
+    /// Output directory for received files, or "-" to stream one upload to stdout
     #[arg(short = 'o', long, default_value = ".")]
     output: PathBuf,
 
+    /// Close the upload link after this long (e.g. 30s, 10m, 1h, 7d)
+    #[arg(short = 'e', long, default_value = "1h")]
+    expire: String,
+
     /// Bind address
     #[arg(short = 'b', long, default_value = "0.0.0.0")]
     bind: String,
@@ -96,6 +223,82 @@ struct ReceiveArgs {
     /// Disable Cloudflare tunnel (local network only)
     #[arg(long)]
     no_tunnel: bool,
+
+    /// Serve HTTPS/WSS with an ephemeral self-signed certificate (pinned in the link)
+    #[arg(long)]
+    tls: bool,
+
+    /// Print lifecycle events as JSON lines on stdout (no banner or QR)
+    #[arg(long)]
+    json: bool,
+
+    /// Run a shell command after each saved file (event JSON on stdin)
+    #[arg(long, value_name = "CMD")]
+    on_receive: Option<String>,
+
+    /// POST every event as HMAC-signed JSON to this URL
+    #[arg(long, value_name = "URL")]
+    webhook: Option<String>,
+
+    /// HMAC-SHA256 key for webhook signatures (generated and printed if omitted)
+    #[arg(long, value_name = "SECRET", requires = "webhook")]
+    webhook_secret: Option<String>,
+
+    /// Serve Prometheus metrics on this admin address (e.g. 127.0.0.1:9100)
+    #[arg(long, value_name = "ADDR")]
+    metrics: Option<String>,
+
+    /// Append a hash-chained audit record to this file (check with `ded audit verify`)
+    #[arg(long, value_name = "FILE")]
+    audit_log: Option<PathBuf>,
+
+    /// On shutdown, wait this long for running transfers to finish (0s = don't wait)
+    #[arg(long, value_name = "DURATION", default_value = "5m")]
+    drain_timeout: String,
+
+    /// Refuse any single file bigger than this (e.g. 100MB)
+    #[arg(long, value_name = "SIZE", default_value = "2GiB")]
+    max_size: bytesize::ByteSize,
+
+    /// Refuse uploads that would leave less than this free on the output disk
+    #[arg(long, value_name = "SIZE")]
+    min_free_disk: Option<bytesize::ByteSize>,
+
+    /// Stop after this many files (0 = unlimited)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    max_files: u32,
+
+    /// Stop once this much data has arrived (e.g. 500MB, 2GiB)
+    #[arg(long, value_name = "SIZE")]
+    max_total_size: Option<bytesize::ByteSize>,
+
+    /// Keep accepting uploads until the link expires, not just one batch
+    #[arg(long)]
+    until_expiry: bool,
+
+    /// When a received file's name is taken: save as "name (1).ext", keep the old one, or replace it
+    #[arg(long, value_enum, value_name = "MODE", default_value = "rename")]
+    on_conflict: receive::OnConflict,
+
+    /// Accept hidden files and folders (.bashrc, .git/) — refused by default
+    #[arg(long)]
+    allow_dotfiles: bool,
+
+    /// Scan each file with clamd (unix socket path or HOST:PORT) before saving it
+    #[arg(long, value_name = "SOCKET")]
+    clamd: Option<String>,
+
+    /// Scan each file with a command before saving it ($DEADROP_PATH; exit 0 clean, 1 infected)
+    #[arg(long, value_name = "CMD")]
+    scan_cmd: Option<String>,
+
+    /// Where files wait to be scanned, and infected ones stay [default: OUTPUT/.deadrop-quarantine]
+    #[arg(long, value_name = "DIR", requires = "scanner")]
+    quarantine_dir: Option<PathBuf>,
+
+    /// Keep received files encrypted at rest to this age public key (age1… or a file); read them with `ded open`
+    #[arg(long, value_name = "KEY", conflicts_with = "scanner")]
+    vault: Option<String>,
 }
 
 /// Preprocess CLI args so `ded ./file` works without typing "send"
@@ -111,6 +314,8 @@ fn preprocess_args() -> Vec<String> {
         "s",
         "receive",
         "r",
+        "audit",
+        "open",
         "help",
         "--help",
         "-h",
@@ -125,6 +330,13 @@ fn preprocess_args() -> Vec<String> {
     args
 }
 
+/// `--drain-timeout` as a std duration
+fn parse_drain_timeout(s: &str) -> anyhow::Result<std::time::Duration> {
+    parse_duration(s)?
+        .to_std()
+        .map_err(|_| anyhow::anyhow!("--drain-timeout can't be negative"))
+}
+
 /// Parse a human-readable duration string (e.g. "30s", "10m", "1h", "7d")
 fn parse_duration(s: &str) -> anyhow::Result<chrono::Duration> {
     let s = s.trim();
@@ -160,7 +372,7 @@ async fn main() -> anyhow::Result<()> {
     match cli.command {
         Commands::Send(args) => {
             // ── Handle stdin/clipboard mode ──
-            let resolved_paths = if args.paths.len() == 1 && args.paths[0] == PathBuf::from("-") {
+            let resolved_paths = if args.paths.len() == 1 && args.paths[0].as_os_str() == "-" {
                 use std::io::Read;
                 let mut buffer = Vec::new();
                 std::io::stdin().read_to_end(&mut buffer)?;
@@ -210,7 +422,7 @@ async fn main() -> anyhow::Result<()> {
                 resolved_paths[0].clone()
             };
 
-            let drop_config = config::DropConfig::new(
+            let mut drop_config = config::DropConfig::new(
                 final_path,
                 args.port,
                 args.expire,
@@ -220,6 +432,45 @@ async fn main() -> anyhow::Result<()> {
                 args.no_qr,
                 args.recipients.clone(),
             )?;
+            if let (Some(decoy), Some(decoy_pw)) = (args.decoy, args.decoy_password) {
+                drop_config = drop_config.with_decoy(extend_path(decoy), decoy_pw)?;
+            }
+            drop_config.tls = args.tls;
+            drop_config.tui = args.tui;
+            drop_config.json = args.json;
+            drop_config.hooks = hooks::HookConfig {
+                on_download: args.on_download,
+                on_expire: args.on_expire,
+                webhook: args.webhook,
+                webhook_secret: args.webhook_secret,
+                ..Default::default()
+            }
+            .prepare()?;
+            drop_config.metrics = args.metrics;
+            drop_config.audit_log = args.audit_log;
+            drop_config.drain_timeout = parse_drain_timeout(&args.drain_timeout)?;
+            drop_config.chunk_size = crypto::check_chunk_size(args.chunk_size.as_u64())?;
+            // Validate now rather than after a long encryption pass
+            proxy::TrustedProxies::parse(&args.trust_proxy)?;
+            drop_config.trusted_proxies = args.trust_proxy;
+            drop_config.policy = policy::AccessPolicy {
+                allow: policy::parse_cidrs(&args.allow)?,
+                deny: policy::parse_cidrs(&args.deny)?,
+                allow_agents: policy::parse_agents(&args.allow_ua)?,
+                deny_agents: policy::parse_agents(&args.deny_ua)?,
+                not_before: args
+                    .not_before
+                    .as_deref()
+                    .map(policy::parse_not_before)
+                    .transpose()?,
+                between: args
+                    .between
+                    .as_deref()
+                    .map(policy::parse_window)
+                    .transpose()?,
+                max_ips: args.max_ips,
+                token: args.token.then(policy::generate_token),
+            };
 
             // ── Optional Tor hidden service ──
             let tor_service = if args.tor {
@@ -232,21 +483,67 @@ async fn main() -> anyhow::Result<()> {
             let tunnel_service = if args.no_tunnel {
                 None
             } else {
-                tunnel::try_start_tunnel(drop_config.port).await
+                tunnel::try_start_tunnel(drop_config.tls).await
             };
 
             server::start(drop_config, tor_service.as_ref(), tunnel_service.as_ref()).await?;
         }
 
         Commands::Receive(args) => {
-            let expiry_dur = parse_duration("1h")?;
+            let expiry_dur = parse_duration(&args.expire)?;
+            let to_stdout = args.output.as_os_str() == "-";
+            if to_stdout {
+                for (set, flag) in [
+                    (args.json, "--json"),
+                    (args.vault.is_some(), "--vault"),
+                    (args.clamd.is_some() || args.scan_cmd.is_some(), "a scanner"),
+                    (args.until_expiry, "--until-expiry"),
+                ] {
+                    if set {
+                        anyhow::bail!(
+                            "-o - streams one upload to stdout; it can't be combined with {}",
+                            flag
+                        );
+                    }
+                }
+            }
 
             let recv_config = config::ReceiveConfig {
                 port: args.port,
                 output_dir: args.output,
                 bind: args.bind,
                 no_qr: args.no_qr,
+                expire: args.expire,
                 expiry_duration: expiry_dur,
+                tls: args.tls,
+                json: args.json,
+                hooks: hooks::HookConfig {
+                    on_receive: args.on_receive,
+                    webhook: args.webhook,
+                    webhook_secret: args.webhook_secret,
+                    ..Default::default()
+                }
+                .prepare()?,
+                metrics: args.metrics,
+                audit_log: args.audit_log,
+                drain_timeout: parse_drain_timeout(&args.drain_timeout)?,
+                limits: receive::Limits {
+                    max_size: args.max_size.as_u64(),
+                    // stdout takes exactly one file
+                    max_files: if to_stdout { 1 } else { args.max_files },
+                    max_total_size: args.max_total_size.map(|size| size.as_u64()),
+                    until_expiry: args.until_expiry,
+                    min_free_disk: args.min_free_disk.map(|size| size.as_u64()),
+                },
+                on_conflict: args.on_conflict,
+                allow_dotfiles: args.allow_dotfiles,
+                scanner: match (args.clamd, args.scan_cmd) {
+                    (Some(address), _) => Some(scan::Scanner::Clamd(address)),
+                    (None, Some(command)) => Some(scan::Scanner::Command(command)),
+                    (None, None) => None,
+                },
+                quarantine_dir: args.quarantine_dir,
+                vault: args.vault.as_deref().map(vault::Vault::parse).transpose()?,
             };
 
             // ── Optional Tor hidden service ──
@@ -260,12 +557,70 @@ async fn main() -> anyhow::Result<()> {
             let tunnel_service = if args.no_tunnel {
                 None
             } else {
-                tunnel::try_start_tunnel(recv_config.port).await
+                tunnel::try_start_tunnel(recv_config.tls).await
             };
 
             server::start_receive(recv_config, tor_service.as_ref(), tunnel_service.as_ref())
                 .await?;
         }
+
+        Commands::Open(args) => {
+            let identities = vault::read_identities(&args.identity)?;
+            if args.output.as_deref() == Some(std::path::Path::new("-")) {
+                let mut stdout = std::io::stdout().lock();
+                for file in &args.files {
+                    let sealed = std::fs::File::open(file)
+                        .map_err(|e| anyhow::anyhow!("Cannot open {}: {}", file.display(), e))?;
+                    let mut plaintext =
+                        vault::open_reader(std::io::BufReader::new(sealed), &identities)
+                            .map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))?;
+                    std::io::copy(&mut plaintext, &mut stdout)?;
+                }
+                return Ok(());
+            }
+            for file in &args.files {
+                let dir = match &args.output {
+                    Some(dir) => dir.clone(),
+                    None => file
+                        .parent()
+                        .filter(|p| !p.as_os_str().is_empty())
+                        .map(PathBuf::from)
+                        .unwrap_or_else(|| PathBuf::from(".")),
+                };
+                std::fs::create_dir_all(&dir)?;
+                let opened = vault::open_file(file, &identities, &dir)?;
+                eprintln!(
+                    " {} {} → {}",
+                    console::style("🔓").bold(),
+                    file.display(),
+                    console::style(opened.display()).green()
+                );
+            }
+        }
+
+        Commands::Audit(AuditCommand::Verify { log }) => {
+            let summary = audit::verify(&log)
+                .map_err(|e| anyhow::anyhow!("Audit log {} FAILED: {}", log.display(), e))?;
+            eprintln!(
+                " {} Chain intact: {} entries across {} run(s)",
+                console::style("✓").green().bold(),
+                summary.entries,
+                summary.runs
+            );
+            if summary.unclosed > 0 {
+                eprintln!(
+                    " {} {} earlier run(s) ended without closing (process killed?)",
+                    console::style("⚠").yellow(),
+                    summary.unclosed
+                );
+            }
+            if !summary.sealed {
+                eprintln!(
+                    " {} Last run is not closed — still running, killed, or the end of the log was cut off",
+                    console::style("⚠").yellow()
+                );
+            }
+        }
     }
     Ok(())
 }
@@ -0,0 +1,235 @@
+//! Prometheus metrics (`--metrics <addr>`).
+//!
+//! Counters live in [`Metrics`] for the whole run; the `/metrics` endpoint
+//! is served on its own admin listener so it never shares an address (or
+//! the rate limiter) with the public drop routes.
+
+use crate::events::{Event, Record, Sink};
+use crate::policy::Denied;
+use crate::store::Transport;
+use axum::Router;
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Default)]
+pub struct Metrics {
+    bytes_blob: AtomicU64,
+    bytes_chunks: AtomicU64,
+    bytes_websocket: AtomicU64,
+    rate_limited: AtomicU64,
+    blocked: [AtomicU64; Denied::ALL.len()],
+    downloads_completed: AtomicU64,
+    downloads_aborted: AtomicU64,
+    receipts_verified: AtomicU64,
+    receipts_rejected: AtomicU64,
+    expired: AtomicU64,
+    burned_download_limit: AtomicU64,
+    burned_revoked: AtomicU64,
+    received: AtomicU64,
+    quarantined: AtomicU64,
+    decrypt_failures: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Ciphertext bytes sent to a recipient
+    pub fn bytes_served(&self, transport: Transport, bytes: u64) {
+        let counter = match transport {
+            Transport::Blob => &self.bytes_blob,
+            Transport::Chunks => &self.bytes_chunks,
+            Transport::WebSocket => &self.bytes_websocket,
+        };
+        counter.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// An upload in receive mode failed to decrypt
+    pub fn decrypt_failed(&self) {
+        self.decrypt_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self, active_drops: usize) -> String {
+        let get = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        let mut out = String::new();
+        let mut family = |name: &str, kind: &str, help: &str, samples: &[(&str, u64)]| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        };
+
+        family(
+            "deadrop_active_drops",
+            "gauge",
+            "Drops currently being served",
+            &[("", active_drops as u64)],
+        );
+        family(
+            "deadrop_bytes_served_total",
+            "counter",
+            "Ciphertext bytes sent to recipients, by transport",
+            &[
+                ("{transport=\"blob\"}", get(&self.bytes_blob)),
+                ("{transport=\"chunk\"}", get(&self.bytes_chunks)),
+                ("{transport=\"websocket\"}", get(&self.bytes_websocket)),
+            ],
+        );
+        family(
+            "deadrop_rate_limited_total",
+            "counter",
+            "Requests rejected by the rate limiter (HTTP 429)",
+            &[("", get(&self.rate_limited))],
+        );
+        let blocked: Vec<(String, u64)> = Denied::ALL
+            .iter()
+            .zip(&self.blocked)
+            .map(|(denied, c)| (format!("{{reason=\"{}\"}}", denied.code()), get(c)))
+            .collect();
+        let blocked: Vec<(&str, u64)> = blocked.iter().map(|(l, v)| (l.as_str(), *v)).collect();
+        family(
+            "deadrop_blocked_total",
+            "counter",
+            "Fetches refused by the access policy (ip_limit = IP pinning)",
+            &blocked,
+        );
+        family(
+            "deadrop_downloads_total",
+            "counter",
+            "Download sessions, by outcome",
+            &[
+                ("{outcome=\"completed\"}", get(&self.downloads_completed)),
+                ("{outcome=\"aborted\"}", get(&self.downloads_aborted)),
+            ],
+        );
+        family(
+            "deadrop_receipts_total",
+            "counter",
+            "Proof-of-download receipts, by outcome",
+            &[
+                ("{outcome=\"verified\"}", get(&self.receipts_verified)),
+                ("{outcome=\"rejected\"}", get(&self.receipts_rejected)),
+            ],
+        );
+        family(
+            "deadrop_expired_total",
+            "counter",
+            "Drops removed by expiry",
+            &[("", get(&self.expired))],
+        );
+        family(
+            "deadrop_burned_total",
+            "counter",
+            "Drops destroyed before expiry",
+            &[
+                (
+                    "{reason=\"download_limit\"}",
+                    get(&self.burned_download_limit),
+                ),
+                ("{reason=\"revoked\"}", get(&self.burned_revoked)),
+            ],
+        );
+        family(
+            "deadrop_received_total",
+            "counter",
+            "Files saved in receive mode",
+            &[("", get(&self.received))],
+        );
+        family(
+            "deadrop_quarantined_total",
+            "counter",
+            "Uploads held in quarantine by the scanner in receive mode",
+            &[("", get(&self.quarantined))],
+        );
+        family(
+            "deadrop_receive_decrypt_failures_total",
+            "counter",
+            "Uploads that failed to decrypt in receive mode",
+            &[("", get(&self.decrypt_failures))],
+        );
+        out
+    }
+}
+
+/// Lifecycle counters come off the event stream
+impl Sink for Metrics {
+    fn emit(&self, record: &Record) {
+        let counter = match &record.event {
+            Event::DownloadCompleted { .. } => &self.downloads_completed,
+            Event::DownloadAborted { .. } => &self.downloads_aborted,
+            Event::ReceiptVerified { .. } => &self.receipts_verified,
+            Event::ReceiptRejected { .. } => &self.receipts_rejected,
+            Event::Expired { .. } => &self.expired,
+            Event::Burned { reason, .. } if *reason == "revoked" => &self.burned_revoked,
+            Event::Burned { .. } => &self.burned_download_limit,
+            Event::Received { .. } => &self.received,
+            Event::Quarantined { .. } => &self.quarantined,
+            Event::Blocked { reason, .. } => {
+                match Denied::ALL.iter().position(|d| d.code() == *reason) {
+                    Some(i) => &self.blocked[i],
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Middleware around the rate-limited routes: count the 429s the
+/// governor answers with
+pub async fn count_rate_limited(
+    State(metrics): State<Arc<Metrics>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let resp = next.run(req).await;
+    if resp.status() == StatusCode::TOO_MANY_REQUESTS {
+        metrics.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+    resp
+}
+
+/// Bind the admin listener and serve `/metrics` in the background.
+/// `active_drops` is sampled on every scrape.
+pub async fn serve(
+    addr: &str,
+    metrics: Arc<Metrics>,
+    active_drops: impl Fn() -> usize + Send + Sync + 'static,
+) -> anyhow::Result<()> {
+    let active_drops = Arc::new(active_drops);
+    let app = Router::new().route(
+        "/metrics",
+        get(move || {
+            let body = metrics.render(active_drops());
+            async move {
+                (
+                    [("content-type", "text/plain; version=0.0.4; charset=utf-8")],
+                    body,
+                )
+                    .into_response()
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to bind metrics address {}: {}", addr, e))?;
+    eprintln!(
+        " {} Metrics: http://{}/metrics",
+        console::style("📈").bold(),
+        listener.local_addr()?
+    );
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, app).await;
+    });
+    Ok(())
+}
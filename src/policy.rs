@@ -0,0 +1,295 @@
+use chrono::{DateTime, Local, NaiveTime, Utc};
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// Per-drop access rules, checked on every fetch path
+/// (`/api/blob`, `/api/chunks`, `/api/chunk`, `/ws/blob`).
+#[derive(Clone, Debug)]
+pub struct AccessPolicy {
+    /// If non-empty, only these networks may fetch
+    pub allow: Vec<IpNet>,
+    /// Networks that may never fetch (wins over `allow`)
+    pub deny: Vec<IpNet>,
+    /// If non-empty, only clients whose User-Agent contains one of these
+    /// (lowercase) may fetch
+    pub allow_agents: Vec<String>,
+    /// Clients whose User-Agent contains one of these (lowercase) may never
+    /// fetch (wins over `allow_agents`)
+    pub deny_agents: Vec<String>,
+    /// Refuse fetches before this instant
+    pub not_before: Option<DateTime<Utc>>,
+    /// Daily local-time window `(start, end)`; wraps past midnight if start > end
+    pub between: Option<(NaiveTime, NaiveTime)>,
+    /// Distinct client IPs allowed (1 = classic IP pinning, 0 = unlimited)
+    pub max_ips: u32,
+    /// One-time access token expected as `?t=` — claimed by the first
+    /// device, for [`TOKEN_CLAIM_TTL`] or one download
+    pub token: Option<String>,
+}
+
+/// How long a claimed one-time token keeps admitting the device that
+/// claimed it, besides the downloads it already started
+pub const TOKEN_CLAIM_TTL: chrono::TimeDelta = chrono::TimeDelta::minutes(10);
+
+impl Default for AccessPolicy {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            allow_agents: Vec::new(),
+            deny_agents: Vec::new(),
+            not_before: None,
+            between: None,
+            max_ips: 1,
+            token: None,
+        }
+    }
+}
+
+/// Why a request was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denied {
+    Network,
+    UserAgent,
+    TooEarly,
+    OutsideWindow,
+    BadToken,
+    TokenClaimed,
+    IpLimit,
+}
+
+impl Denied {
+    pub const ALL: [Denied; 7] = [
+        Denied::Network,
+        Denied::UserAgent,
+        Denied::TooEarly,
+        Denied::OutsideWindow,
+        Denied::BadToken,
+        Denied::TokenClaimed,
+        Denied::IpLimit,
+    ];
+
+    /// Message returned to the client
+    pub fn message(&self) -> &'static str {
+        match self {
+            Denied::Network => "Access denied — your network is not allowed",
+            Denied::UserAgent => "Access denied — your browser is not allowed",
+            Denied::TooEarly => "Access denied — this drop is not available yet",
+            Denied::OutsideWindow => "Access denied — outside the allowed time window",
+            Denied::BadToken => "Access denied — missing or invalid access token",
+            Denied::TokenClaimed => {
+                "Access denied — this access token was already used or has expired"
+            }
+            Denied::IpLimit => "Access denied — this drop is locked to another device",
+        }
+    }
+
+    /// Short reason for the sender's log
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Denied::Network => "network not allowed",
+            Denied::UserAgent => "user agent not allowed",
+            Denied::TooEarly => "before not-before time",
+            Denied::OutsideWindow => "outside time window",
+            Denied::BadToken => "bad token",
+            Denied::TokenClaimed => "token already used or expired",
+            Denied::IpLimit => "pinned to different IP",
+        }
+    }
+
+    /// Stable identifier for machine-readable output
+    pub fn code(&self) -> &'static str {
+        match self {
+            Denied::Network => "network",
+            Denied::UserAgent => "user_agent",
+            Denied::TooEarly => "too_early",
+            Denied::OutsideWindow => "outside_window",
+            Denied::BadToken => "bad_token",
+            Denied::TokenClaimed => "token_claimed",
+            Denied::IpLimit => "ip_limit",
+        }
+    }
+}
+
+impl AccessPolicy {
+    /// Stateless checks: networks, user agents, time windows and token
+    /// presence
+    pub fn check(
+        &self,
+        ip: IpAddr,
+        user_agent: Option<&str>,
+        token: Option<&str>,
+        now: DateTime<Utc>,
+        local_time: NaiveTime,
+    ) -> Result<(), Denied> {
+        if self.deny.iter().any(|net| net.contains(&ip)) {
+            return Err(Denied::Network);
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|net| net.contains(&ip)) {
+            return Err(Denied::Network);
+        }
+        let agent = user_agent.unwrap_or("").to_lowercase();
+        if self.deny_agents.iter().any(|a| agent.contains(a.as_str())) {
+            return Err(Denied::UserAgent);
+        }
+        if !self.allow_agents.is_empty()
+            && !self.allow_agents.iter().any(|a| agent.contains(a.as_str()))
+        {
+            return Err(Denied::UserAgent);
+        }
+        if self.not_before.is_some_and(|t| now < t) {
+            return Err(Denied::TooEarly);
+        }
+        if let Some((start, end)) = self.between {
+            let inside = if start <= end {
+                local_time >= start && local_time < end
+            } else {
+                local_time >= start || local_time < end
+            };
+            if !inside {
+                return Err(Denied::OutsideWindow);
+            }
+        }
+        if let Some(expected) = &self.token {
+            // Compare digests so the comparison time says nothing about the token
+            use sha2::{Digest, Sha256};
+            let presented = Sha256::digest(token.unwrap_or("").as_bytes());
+            if presented != Sha256::digest(expected.as_bytes()) {
+                return Err(Denied::BadToken);
+            }
+        }
+        Ok(())
+    }
+
+    /// Human-readable summary of the non-default rules, for the banner
+    pub fn describe(&self) -> Vec<String> {
+        let join = |nets: &[IpNet]| {
+            nets.iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut rules = Vec::new();
+        if !self.allow.is_empty() {
+            rules.push(format!("Allowed networks: {}", join(&self.allow)));
+        }
+        if !self.deny.is_empty() {
+            rules.push(format!("Denied networks: {}", join(&self.deny)));
+        }
+        if !self.allow_agents.is_empty() {
+            rules.push(format!(
+                "Allowed user agents: {}",
+                self.allow_agents.join(", ")
+            ));
+        }
+        if !self.deny_agents.is_empty() {
+            rules.push(format!(
+                "Denied user agents: {}",
+                self.deny_agents.join(", ")
+            ));
+        }
+        if let Some(t) = self.not_before {
+            rules.push(format!(
+                "Not before: {}",
+                t.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            ));
+        }
+        if let Some((start, end)) = self.between {
+            rules.push(format!(
+                "Only between {} and {} (local time)",
+                start.format("%H:%M"),
+                end.format("%H:%M")
+            ));
+        }
+        match self.max_ips {
+            0 => rules.push("Any number of devices may download".to_string()),
+            1 => {}
+            n => rules.push(format!("Up to {} devices may download", n)),
+        }
+        if self.token.is_some() {
+            rules.push(format!(
+                "One-time access token required (included in the link): one download, \
+                 started within {} minutes of first use",
+                TOKEN_CLAIM_TTL.num_minutes()
+            ));
+        }
+        rules
+    }
+
+    /// `check` against the current clock
+    pub fn check_now(
+        &self,
+        ip: IpAddr,
+        user_agent: Option<&str>,
+        token: Option<&str>,
+    ) -> Result<(), Denied> {
+        self.check(ip, user_agent, token, Utc::now(), Local::now().time())
+    }
+}
+
+/// Parse `--allow` / `--deny` values: CIDRs or bare IPs
+pub fn parse_cidrs(entries: &[String]) -> anyhow::Result<Vec<IpNet>> {
+    entries
+        .iter()
+        .map(|entry| {
+            let entry = entry.trim();
+            entry
+                .parse::<IpNet>()
+                .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| anyhow::anyhow!("Invalid CIDR or IP: {}", entry))
+        })
+        .collect()
+}
+
+/// Parse `--allow-ua` / `--deny-ua` values: case-insensitive substrings
+/// of the User-Agent header
+pub fn parse_agents(entries: &[String]) -> anyhow::Result<Vec<String>> {
+    entries
+        .iter()
+        .map(|entry| {
+            let entry = entry.trim().to_lowercase();
+            if entry.is_empty() {
+                anyhow::bail!("Empty user agent pattern");
+            }
+            Ok(entry)
+        })
+        .collect()
+}
+
+/// Parse `--not-before`: an RFC 3339 timestamp or a delay from now (`10m`, `2h`)
+pub fn parse_not_before(s: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s.trim()) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    let delay = crate::config::parse_duration(s)
+        .map_err(|_| anyhow::anyhow!("Invalid --not-before '{}'. Use RFC 3339 or 10m/2h", s))?;
+    Ok(Utc::now() + delay)
+}
+
+/// Parse `--between HH:MM-HH:MM` (local time)
+pub fn parse_window(s: &str) -> anyhow::Result<(NaiveTime, NaiveTime)> {
+    let (start, end) = s
+        .split_once('-')
+        .ok_or_else(|| anyhow::anyhow!("Invalid --between '{}'. Use HH:MM-HH:MM", s))?;
+    let parse = |t: &str| {
+        NaiveTime::parse_from_str(t.trim(), "%H:%M")
+            .map_err(|_| anyhow::anyhow!("Invalid time '{}' in --between. Use HH:MM", t))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start == end {
+        anyhow::bail!(
+            "Invalid --between '{}'. The window is empty: start and end are the same",
+            s
+        );
+    }
+    Ok((start, end))
+}
+
+/// Random URL-safe one-time access token
+pub fn generate_token() -> String {
+    use base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD};
+    use rand::Rng;
+    let mut bytes = [0u8; 16];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
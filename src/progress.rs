@@ -1,6 +1,8 @@
 use console::style;
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Style presets for deadrop
 pub struct Styles;
@@ -16,7 +18,7 @@ impl Styles {
 
     pub fn download_bar() -> ProgressStyle {
         ProgressStyle::with_template(
-            "  {spinner:.magenta} Download    [{bar:40.magenta/dark_gray}] {bytes}/{total_bytes} ({bytes_per_sec})"
+            "  {spinner:.magenta} Download    [{bar:40.magenta/dark_gray}] {bytes}/{total_bytes} ({bytes_per_sec}) {msg:.dim}"
         )
         .unwrap()
         .progress_chars("━╸─")
@@ -32,6 +34,12 @@ pub struct ProgressManager {
     multi: MultiProgress,
 }
 
+impl Default for ProgressManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ProgressManager {
     pub fn new() -> Self {
         Self {
@@ -55,6 +63,11 @@ impl ProgressManager {
         pb
     }
 
+    /// Print a line above any live bars without tearing them
+    pub fn println(&self, line: String) {
+        self.multi.suspend(|| eprintln!("{}", line));
+    }
+
     /// Create a spinner for status messages
     pub fn create_spinner(&self, msg: &str) -> ProgressBar {
         let pb = self.multi.add(ProgressBar::new_spinner());
@@ -65,16 +78,136 @@ impl ProgressManager {
     }
 }
 
+/// Live sender-side view of every in-flight download: one bar per
+/// session with bytes, rate, remote IP and transport.
+#[derive(Clone, Default)]
+pub struct TransferBoard {
+    progress: Arc<ProgressManager>,
+    active: Arc<Mutex<HashMap<(String, u64), Transfer>>>,
+}
+
+struct Transfer {
+    bar: ProgressBar,
+    remote: String,
+    transport: &'static str,
+    started: Instant,
+}
+
+impl TransferBoard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A session opened: start its bar
+    pub fn started(
+        &self,
+        drop_id: &str,
+        session: u64,
+        remote: &str,
+        transport: &'static str,
+        total: u64,
+    ) {
+        let bar = self.progress.create_download_bar(total);
+        bar.set_message(format!("{} · {}", remote, transport));
+        self.active.lock().unwrap().insert(
+            (drop_id.to_string(), session),
+            Transfer {
+                bar,
+                remote: remote.to_string(),
+                transport,
+                started: Instant::now(),
+            },
+        );
+    }
+
+    /// Bytes left the server for this session
+    pub fn advance(&self, drop_id: &str, session: u64, bytes: u64) {
+        if let Some(t) = self
+            .active
+            .lock()
+            .unwrap()
+            .get(&(drop_id.to_string(), session))
+        {
+            t.bar.inc(bytes);
+        }
+    }
+
+    /// A drop's size became known (a folder archived as it sealed): size
+    /// the bars of its sessions that started before
+    pub fn sized(&self, drop_id: &str, total: u64) {
+        for ((id, _), t) in self.active.lock().unwrap().iter() {
+            if id == drop_id {
+                t.bar.set_length(total);
+            }
+        }
+    }
+
+    /// The session delivered everything
+    pub fn completed(&self, drop_id: &str, session: u64) {
+        let Some(t) = self.take(drop_id, session) else {
+            return;
+        };
+        let total = t.bar.length().unwrap_or(0);
+        let elapsed = t.started.elapsed();
+        let rate = total as f64 / elapsed.as_secs_f64().max(0.001);
+        t.bar.finish_and_clear();
+        self.progress.println(format!(
+            "  {} Download completed — {} to {} via {} in {:.1}s ({}/s)",
+            style("✓").green().bold(),
+            style(bytesize::ByteSize::b(total).to_string()).bold(),
+            style(&t.remote).dim(),
+            t.transport,
+            elapsed.as_secs_f64(),
+            bytesize::ByteSize::b(rate as u64)
+        ));
+    }
+
+    /// The session ended early
+    pub fn aborted(&self, drop_id: &str, session: u64) {
+        let Some(t) = self.take(drop_id, session) else {
+            return;
+        };
+        let total = t.bar.length().unwrap_or(0).max(1);
+        let percent = (t.bar.position() * 100 / total).min(100);
+        t.bar.finish_and_clear();
+        self.progress.println(format!(
+            "  {} Download aborted at {}% — {} via {} (not counted)",
+            style("✗").yellow().bold(),
+            percent,
+            style(&t.remote).dim(),
+            t.transport
+        ));
+    }
+
+    /// Encryption bar for a drop sealed while it is served, drawn with
+    /// the download bars
+    pub fn encrypting(&self, total: u64) -> ProgressBar {
+        self.progress.create_encrypt_bar(total)
+    }
+
+    /// Print a line above the live bars
+    pub fn println(&self, line: String) {
+        self.progress.println(line);
+    }
+
+    fn take(&self, drop_id: &str, session: u64) -> Option<Transfer> {
+        self.active
+            .lock()
+            .unwrap()
+            .remove(&(drop_id.to_string(), session))
+    }
+}
+
 /// Print the startup banner with drop info
 pub fn print_banner(
     url: &str,
     expire: &str,
     max_downloads: u32,
-    file_size: u64,
+    size: &str,
     filename: &str,
+    sha256: &str,
     has_password: bool,
 ) {
-    let size_str = bytesize::ByteSize::b(file_size).to_string();
     let downloads_str = if max_downloads == 0 {
         "unlimited".to_string()
     } else {
@@ -137,7 +270,8 @@ pub fn print_banner(
         style("├─ File").dim(),
         style(filename).white().bold()
     );
-    eprintln!("  {}  {}", style("├─ Size").dim(), style(&size_str).white());
+    eprintln!("  {}  {}", style("├─ Size").dim(), style(size).white());
+    eprintln!("  {}  {}", style("├─ SHA-256").dim(), style(sha256).dim());
     eprintln!(
         "  {}  {}",
         style("├─ Expires").dim(),
@@ -192,6 +326,36 @@ pub fn print_self_destruct() {
     eprintln!();
 }
 
+/// Print while a burned drop waits for the recipient's receipt
+pub fn print_awaiting_receipt(grace_secs: u64) {
+    eprintln!(
+        "  {} {}",
+        style("🧾").bold(),
+        style(format!(
+            "Waiting up to {}s for the recipient's receipt (Ctrl+C to skip)",
+            grace_secs
+        ))
+        .dim()
+    );
+}
+
+/// Print when a receipt verifies: the recipient decrypted the drop
+pub fn print_receipt(remote_addr: &str, decoy: bool) {
+    let what = if decoy {
+        style("Decoy decrypted and verified by recipient")
+            .yellow()
+            .bold()
+    } else {
+        style("Decrypted and verified by recipient").green().bold()
+    };
+    eprintln!(
+        "  {} {} — {}",
+        style("🧾").bold(),
+        what,
+        style(remote_addr).dim()
+    );
+}
+
 /// Print when a drop expires
 pub fn print_expired() {
     eprintln!();
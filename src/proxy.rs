@@ -0,0 +1,96 @@
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+
+/// Reverse proxies whose forwarding headers we believe.
+///
+/// `CF-Connecting-IP` and `X-Forwarded-For` are only honored when the TCP
+/// peer is inside one of these networks. Anyone else could simply forge them.
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies {
+    nets: Vec<IpNet>,
+}
+
+impl TrustedProxies {
+    /// Parse `--trust-proxy` values: CIDRs (`10.0.0.0/8`) or bare IPs (`10.0.0.1`)
+    pub fn parse(entries: &[String]) -> anyhow::Result<Self> {
+        let mut nets = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let entry = entry.trim();
+            let net = match entry.parse::<IpNet>() {
+                Ok(net) => net,
+                Err(_) => entry
+                    .parse::<IpAddr>()
+                    .map(IpNet::from)
+                    .map_err(|_| anyhow::anyhow!("Invalid --trust-proxy value: {}", entry))?,
+            };
+            nets.push(net);
+        }
+        Ok(Self { nets })
+    }
+
+    /// Trust loopback peers — only for the tunnel's own origin listener,
+    /// where the spawned cloudflared is the one connecting from there
+    pub fn with_loopback(mut self) -> Self {
+        self.nets.push("127.0.0.0/8".parse().unwrap());
+        self.nets.push("::1/128".parse().unwrap());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nets.is_empty()
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        let ip = canonical(ip);
+        self.nets.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Resolve the real client IP for a request.
+    ///
+    /// Untrusted peers are taken at face value. For trusted peers we prefer
+    /// `CF-Connecting-IP`, then walk `X-Forwarded-For` from the right and
+    /// return the first hop that is not itself a trusted proxy.
+    pub fn client_ip(&self, peer: &SocketAddr, headers: &HeaderMap) -> IpAddr {
+        let peer_ip = canonical(peer.ip());
+        if !self.is_trusted(peer_ip) {
+            return peer_ip;
+        }
+
+        if let Some(ip) = headers
+            .get("CF-Connecting-IP")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<IpAddr>().ok())
+        {
+            return canonical(ip);
+        }
+
+        let forwarded = headers
+            .get_all("X-Forwarded-For")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+            .map(canonical)
+            .collect::<Vec<_>>();
+
+        forwarded
+            .iter()
+            .rev()
+            .find(|ip| !self.is_trusted(**ip))
+            .or(forwarded.first())
+            .copied()
+            .unwrap_or(peer_ip)
+    }
+}
+
+/// Map IPv4-mapped IPv6 (`::ffff:a.b.c.d`) back to IPv4 so CIDRs match
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    }
+}
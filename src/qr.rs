@@ -1,22 +1,26 @@
 use console::style;
 use qrcode::{QrCode, render::unicode};
 
+/// Render a URL as a half-block QR code (light-on-dark, for terminals)
+pub fn render_qr(url: &str) -> anyhow::Result<String> {
+    let code = QrCode::new(url.as_bytes())?;
+    Ok(code
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
+
 pub fn print_qr(url: &str) {
-    let code = match QrCode::new(url.as_bytes()) {
-        Ok(c) => c,
+    let image = match render_qr(url) {
+        Ok(image) => image,
         Err(e) => {
             eprintln!("  {} QR generation failed: {}", style("⚠").yellow(), e);
             return;
         }
     };
 
-    let image = code
-        .render::<unicode::Dense1x2>()
-        .dark_color(unicode::Dense1x2::Light)
-        .light_color(unicode::Dense1x2::Dark)
-        .quiet_zone(true)
-        .build();
-
     eprintln!("{}", image);
     eprintln!("  {}", style("Scan to download on any device").dim());
     eprintln!();
@@ -0,0 +1,156 @@
+//! Proof-of-download receipts.
+//!
+//! After the download page decrypts and authenticates the last chunk it
+//! posts `{sha256, mac}` to `/api/receipt/{id}`: the SHA-256 of the
+//! plaintext it recovered, and an HMAC-SHA256 over the drop id and that
+//! hash. The MAC key is derived from the content key, so only someone who
+//! actually held the key — and decrypted the right bytes — can produce it.
+//!
+//! ```text
+//! receipt_key = HMAC-SHA256(content_key, "deadrop-receipt-v1")
+//! mac         = HMAC-SHA256(receipt_key, "deadrop-receipt-v1" || drop_id || sha256(plaintext))
+//! ```
+//!
+//! The WASM side (`receipt_for` in `wasm/src/lib.rs`) must match this.
+
+use dashmap::DashMap;
+use ring::hmac;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+const CONTEXT: &[u8] = b"deadrop-receipt-v1";
+
+/// One payload a receipt may be for: the real one, or the decoy
+pub struct Expected {
+    key: hmac::Key,
+    sha256: [u8; 32],
+    pub decoy: bool,
+}
+
+impl Expected {
+    /// Derive the receipt key now, so the content key need not be kept
+    pub fn new(content_key: &[u8; 32], plaintext_sha256: [u8; 32], decoy: bool) -> Self {
+        Self {
+            key: receipt_key(content_key),
+            sha256: plaintext_sha256,
+            decoy,
+        }
+    }
+}
+
+fn receipt_key(content_key: &[u8; 32]) -> hmac::Key {
+    let derived = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, content_key), CONTEXT);
+    hmac::Key::new(hmac::HMAC_SHA256, derived.as_ref())
+}
+
+fn message(drop_id: &str, plaintext_sha256: &[u8]) -> Vec<u8> {
+    [CONTEXT, drop_id.as_bytes(), plaintext_sha256].concat()
+}
+
+/// The MAC a recipient holding `content_key` sends (what the WASM computes)
+pub fn mac(content_key: &[u8; 32], drop_id: &str, plaintext_sha256: &[u8; 32]) -> Vec<u8> {
+    hmac::sign(
+        &receipt_key(content_key),
+        &message(drop_id, plaintext_sha256),
+    )
+    .as_ref()
+    .to_vec()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejected {
+    UnknownDrop,
+    Malformed,
+    /// The MAC checks out under no key this drop was sealed with
+    BadMac,
+    /// Valid, but this client's receipt was already counted
+    Repeated,
+}
+
+/// Receipt expectations for every drop this run has served. Outlives the
+/// drops themselves: the receipt arrives after the final byte, which is
+/// exactly when a one-shot drop burns.
+#[derive(Clone, Default)]
+pub struct Receipts {
+    expected: Arc<DashMap<String, Vec<Expected>>>,
+    /// Clients with a verified receipt, per drop. Each counts once, so a
+    /// replayed receipt can't stand in for another recipient's.
+    verified: Arc<DashMap<String, HashSet<IpAddr>>>,
+    on_verified: Arc<Notify>,
+}
+
+impl Receipts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn expect(&self, drop_id: &str, expected: Vec<Expected>) {
+        self.expected.insert(drop_id.to_string(), expected);
+    }
+
+    /// Check a receipt (hex `sha256` and `mac`) posted by `client`.
+    /// `Ok(decoy)` tells which payload the recipient opened.
+    pub fn verify(
+        &self,
+        drop_id: &str,
+        client: IpAddr,
+        sha256_hex: &str,
+        mac_hex: &str,
+    ) -> Result<bool, Rejected> {
+        let expected = self.expected.get(drop_id).ok_or(Rejected::UnknownDrop)?;
+        let sha256 = decode_hex(sha256_hex).ok_or(Rejected::Malformed)?;
+        let mac = decode_hex(mac_hex).ok_or(Rejected::Malformed)?;
+        let msg = message(drop_id, &sha256);
+
+        let opened = expected
+            .iter()
+            .find(|e| e.sha256[..] == sha256[..] && hmac::verify(&e.key, &msg, &mac).is_ok())
+            .ok_or(Rejected::BadMac)?;
+        let decoy = opened.decoy;
+        drop(expected);
+        if !self
+            .verified
+            .entry(drop_id.to_string())
+            .or_default()
+            .insert(client)
+        {
+            return Err(Rejected::Repeated);
+        }
+        self.on_verified.notify_waiters();
+        Ok(decoy)
+    }
+
+    /// Resolves once `count` receipts for the drop have verified
+    pub async fn wait_for(&self, drop_id: &str, count: u32) {
+        loop {
+            let notified = self.on_verified.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self
+                .verified
+                .get(drop_id)
+                .is_some_and(|clients| clients.len() >= count as usize)
+            {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// 32 bytes from 64 hex digits; anything else (non-ASCII included) is None
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() != 64 {
+        return None;
+    }
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| Some((hex_digit(pair[0])? << 4) | hex_digit(pair[1])?))
+        .collect()
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
@@ -0,0 +1,632 @@
+//! Receive-mode session bookkeeping.
+//!
+//! One receive link takes any number of files (and folders, as relative
+//! paths) until the page says it is done, a limit is reached, or the link
+//! expires (`--until-expiry` waits for that). [`Session`] admits uploads
+//! against `--max-size`, `--max-files`, `--max-total-size` and
+//! `--min-free-disk`, records what was saved and decides when to stop
+//! taking more.
+//!
+//! Uploads arrive in chunks so a dropped connection can pick up where it
+//! left off ([`Partials`]):
+//!
+//! ```text
+//! POST /api/upload/start        {path, size}  → {id, offset, chunk_size}
+//! PUT  /api/upload/{id}?offset=N  <bytes>     → {offset}   (the ack)
+//! GET  /api/upload/{id}                       → {offset, size}
+//! POST /api/upload/{id}/finish  {digest}      → {saved_as, size}
+//! ```
+//!
+//! `size` and offsets count encrypted bytes; `size` is reserved against the
+//! limits as soon as the upload starts. A chunk whose offset isn't where
+//! the server is gets 409 with the real offset; the page resumes from
+//! there. Chunks are rate limited on their own, looser limit (429, with
+//! Retry-After). Bytes land in a temp file under the output directory and
+//! are decrypted chunk by chunk on their way out of it once the whole
+//! upload is in — except with `-o -`, where they are decrypted as they
+//! arrive and never stored. The page encrypts as it reads the file, so
+//! neither side holds a whole upload in memory. An upload left idle for
+//! [`UPLOAD_IDLE_TIMEOUT`] is dropped and its reservation released.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Limits for one receive session
+#[derive(Clone, Debug)]
+pub struct Limits {
+    /// Largest single file accepted, in plaintext bytes
+    pub max_size: u64,
+    /// Most files accepted (0 = unlimited)
+    pub max_files: u32,
+    /// Most plaintext bytes accepted across all files
+    pub max_total_size: Option<u64>,
+    /// Keep accepting after the page finishes a batch, until the link expires
+    pub until_expiry: bool,
+    /// Refuse uploads that would leave less than this free on the output disk
+    pub min_free_disk: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_size: crate::config::DEFAULT_MAX_UPLOAD,
+            max_files: 0,
+            max_total_size: None,
+            until_expiry: false,
+            min_free_disk: None,
+        }
+    }
+}
+
+impl Limits {
+    /// Largest upload body allowed on the wire: `max_size` once encrypted
+    pub fn max_encrypted_size(&self) -> u64 {
+        crate::crypto::encrypted_len(self.max_size)
+    }
+}
+
+/// Why an upload was turned away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refused {
+    /// The session is over
+    Closed,
+    TooManyFiles,
+    /// Bigger than `--max-size`
+    FileTooLarge,
+    /// Would go past `--max-total-size`
+    TooLarge,
+    /// Would leave less than `--min-free-disk`
+    DiskFull,
+}
+
+impl std::fmt::Display for Refused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Refused::Closed => "This receive link is closed",
+            Refused::TooManyFiles => "File limit reached",
+            Refused::FileTooLarge => "File exceeds the size limit",
+            Refused::TooLarge => "Total size limit reached",
+            Refused::DiskFull => "Not enough free disk space on the receiver",
+        })
+    }
+}
+
+/// A file saved during the session, relative to the output directory
+#[derive(Clone, Debug)]
+pub struct Saved {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+#[derive(Default)]
+struct Tally {
+    /// Saved plus admitted-but-unfinished uploads
+    files: u32,
+    bytes: u64,
+    /// Admitted and not yet written out
+    pending: u64,
+    saved: Vec<Saved>,
+    refused: u32,
+    quarantined: u32,
+}
+
+pub struct Session {
+    limits: Limits,
+    output_dir: PathBuf,
+    closed: AtomicBool,
+    tally: Mutex<Tally>,
+    started: std::time::Instant,
+}
+
+impl Session {
+    pub fn new(limits: Limits, output_dir: PathBuf) -> Self {
+        Self {
+            limits,
+            output_dir,
+            closed: AtomicBool::new(false),
+            tally: Mutex::default(),
+            started: std::time::Instant::now(),
+        }
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Reserve room for an upload by its announced size (encrypted bytes)
+    /// before any of it is read, so uploads started side by side can't
+    /// each count on the same room. Once its header is in, bring the
+    /// reservation down to the plaintext size with [`Session::settle`];
+    /// give it back with [`Session::release`] if the upload is abandoned.
+    pub fn reserve_announced(&self, encrypted_size: u64) -> Result<(), Refused> {
+        let mut tally = self.tally.lock().unwrap();
+        let refused = if encrypted_size > self.limits.max_encrypted_size() {
+            Some(Refused::FileTooLarge)
+        } else {
+            self.refusal(&tally, encrypted_size)
+        };
+        if let Some(refused) = refused {
+            tally.refused += 1;
+            return Err(refused);
+        }
+        tally.files += 1;
+        tally.bytes += encrypted_size;
+        tally.pending += encrypted_size;
+        Ok(())
+    }
+
+    /// Turn a reservation of `reserved` encrypted bytes into one for the
+    /// `size` plaintext bytes its header announces
+    pub fn settle(&self, reserved: u64, size: u64) {
+        let mut tally = self.tally.lock().unwrap();
+        tally.bytes = tally.bytes.saturating_sub(reserved) + size;
+        tally.pending = tally.pending.saturating_sub(reserved) + size;
+    }
+
+    /// Reserve room for an upload of `size` plaintext bytes. Give it back
+    /// with [`Session::release`] if the upload then fails.
+    pub fn admit(&self, size: u64) -> Result<(), Refused> {
+        let mut tally = self.tally.lock().unwrap();
+        let refused = if size > self.limits.max_size {
+            Some(Refused::FileTooLarge)
+        } else {
+            self.refusal(&tally, size)
+        };
+        if let Some(refused) = refused {
+            tally.refused += 1;
+            return Err(refused);
+        }
+        tally.files += 1;
+        tally.bytes += size;
+        tally.pending += size;
+        Ok(())
+    }
+
+    fn refusal(&self, tally: &Tally, size: u64) -> Option<Refused> {
+        if self.is_closed() {
+            Some(Refused::Closed)
+        } else if self.limits.max_files > 0 && tally.files >= self.limits.max_files {
+            Some(Refused::TooManyFiles)
+        } else if self
+            .limits
+            .max_total_size
+            .is_some_and(|max| tally.bytes.saturating_add(size) > max)
+        {
+            Some(Refused::TooLarge)
+        } else if self.limits.min_free_disk.is_some_and(|min| {
+            // Reserved uploads still to be written will take their share
+            free_space(&self.output_dir)
+                .is_some_and(|free| free < min.saturating_add(tally.pending).saturating_add(size))
+        }) {
+            Some(Refused::DiskFull)
+        } else {
+            None
+        }
+    }
+
+    pub fn release(&self, size: u64) {
+        let mut tally = self.tally.lock().unwrap();
+        tally.files = tally.files.saturating_sub(1);
+        tally.bytes = tally.bytes.saturating_sub(size);
+        tally.pending = tally.pending.saturating_sub(size);
+    }
+
+    /// An upload the scanner held back: it frees its reservation and only
+    /// shows up in the summary
+    pub fn quarantined(&self, size: u64) {
+        self.release(size);
+        self.tally.lock().unwrap().quarantined += 1;
+    }
+
+    /// Record a saved upload. True when that used up a limit and the
+    /// session should close.
+    pub fn record(&self, path: PathBuf, size: u64) -> bool {
+        let mut tally = self.tally.lock().unwrap();
+        tally.pending = tally.pending.saturating_sub(size);
+        tally.saved.push(Saved { path, size });
+        let files_full = self.limits.max_files > 0 && tally.files >= self.limits.max_files;
+        let bytes_full = self
+            .limits
+            .max_total_size
+            .is_some_and(|max| tally.bytes >= max);
+        files_full || bytes_full
+    }
+
+    /// Stop accepting uploads. True for the call that actually closed it.
+    pub fn close(&self) -> bool {
+        !self.closed.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn saved(&self) -> Vec<Saved> {
+        self.tally.lock().unwrap().saved.clone()
+    }
+
+    /// Print what the session received
+    pub fn print_summary(&self) {
+        use console::style;
+
+        let tally = self.tally.lock().unwrap();
+        let bytes: u64 = tally.saved.iter().map(|s| s.size).sum();
+        eprintln!();
+        eprintln!(
+            " {} {} — {} file(s), {} in {}s → {}",
+            style("📦").bold(),
+            style("Session summary").bold(),
+            style(tally.saved.len()).green().bold(),
+            style(bytesize::ByteSize::b(bytes).to_string()).green(),
+            self.started.elapsed().as_secs(),
+            self.output_dir.display()
+        );
+        for saved in &tally.saved {
+            eprintln!(
+                "    {} {}",
+                saved.path.display(),
+                style(bytesize::ByteSize::b(saved.size).to_string()).dim()
+            );
+        }
+        if tally.refused > 0 {
+            eprintln!(
+                " {} {} upload(s) refused (session closed or limit reached)",
+                style("⚠").yellow(),
+                tally.refused
+            );
+        }
+        if tally.quarantined > 0 {
+            eprintln!(
+                " {} {} upload(s) held in quarantine",
+                style("☣").red(),
+                tally.quarantined
+            );
+        }
+    }
+}
+
+/// What to do when an upload's name is already taken
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OnConflict {
+    /// Save as `name (1).ext`, `name (2).ext`…
+    #[default]
+    Rename,
+    /// Keep the existing file and drop the upload
+    Skip,
+    /// Replace the existing file (never through a symlink)
+    Overwrite,
+}
+
+/// Refuse upload paths that are unsafe to create whatever their content:
+/// Windows device names anywhere in the path, and hidden files or folders
+/// unless `allow_dotfiles`
+pub fn check_path(relative: &Path, allow_dotfiles: bool) -> anyhow::Result<()> {
+    for part in relative.iter() {
+        let part = part.to_string_lossy();
+        if crate::crypto::is_reserved_name(&part) {
+            anyhow::bail!("{} is a reserved name", part);
+        }
+        if part.starts_with('.') && !allow_dotfiles {
+            anyhow::bail!("{} is a hidden file (allow with --allow-dotfiles)", part);
+        }
+    }
+    Ok(())
+}
+
+/// Most `name (n).ext` candidates tried before giving up
+const MAX_RENAMES: u32 = 1000;
+
+/// Write a received file under `output_dir`, creating its folders. Returns
+/// the relative path it was saved as — renamed on conflict — or None when
+/// skipped. Never writes through a symlink or outside `output_dir`.
+pub fn save_file(
+    output_dir: &Path,
+    relative: &Path,
+    data: &[u8],
+    on_conflict: OnConflict,
+) -> anyhow::Result<Option<PathBuf>> {
+    save_with(output_dir, relative, on_conflict, |file| {
+        std::io::Write::write_all(file, data)
+    })
+}
+
+/// [`save_file`], with the content written by `write`. If that fails the
+/// file is removed again.
+pub fn save_with(
+    output_dir: &Path,
+    relative: &Path,
+    on_conflict: OnConflict,
+    write: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>,
+) -> anyhow::Result<Option<PathBuf>> {
+    if let Some(parent) = output_dir.join(relative).parent() {
+        std::fs::create_dir_all(parent)?;
+        if !parent
+            .canonicalize()?
+            .starts_with(output_dir.canonicalize()?)
+        {
+            anyhow::bail!("{} leads outside the output directory", relative.display());
+        }
+    }
+
+    let name = relative
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+    for n in 0..=MAX_RENAMES {
+        let candidate = if n == 0 {
+            relative.to_path_buf()
+        } else {
+            relative.with_file_name(crate::crypto::numbered_filename(&name, n))
+        };
+        let path = output_dir.join(&candidate);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true);
+        let is_symlink = path.symlink_metadata().is_ok_and(|m| m.is_symlink());
+        if on_conflict == OnConflict::Overwrite && !is_symlink {
+            options.create(true).truncate(true);
+        } else {
+            // Fails on anything already there, symlinks included
+            options.create_new(true);
+        }
+        match options.open(&path) {
+            Ok(mut file) => {
+                if let Err(e) = write(&mut file) {
+                    // No half-written files left behind
+                    drop(file);
+                    let _ = std::fs::remove_file(&path);
+                    return Err(e.into());
+                }
+                return Ok(Some(candidate));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => match on_conflict {
+                OnConflict::Rename => continue,
+                OnConflict::Skip => return Ok(None),
+                OnConflict::Overwrite => {
+                    anyhow::bail!("{} is a symlink", candidate.display())
+                }
+            },
+            Err(e) => return Err(e.into()),
+        }
+    }
+    anyhow::bail!("{} exists {} times over", name, MAX_RENAMES)
+}
+
+/// Encrypted bytes per upload chunk the page sends
+pub const UPLOAD_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// An upload nothing has been sent to for this long is given up: its temp
+/// file is deleted and its reservation released. Longer than the page
+/// keeps retrying through a lost connection.
+pub const UPLOAD_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// One chunked upload in progress
+pub struct Partial {
+    pub relative: PathBuf,
+    /// Announced encrypted size
+    pub size: u64,
+    /// Bytes received so far
+    pub offset: u64,
+    /// The uploader's plaintext SHA-256, sealed ([`crate::crypto::seal_digest`])
+    pub digest: Option<String>,
+    /// None once finished and decrypted
+    spool: Option<Spool>,
+    /// Streamed plaintext not yet written out (`-o -`)
+    pub plaintext: Vec<u8>,
+    /// The reply to `finish`, kept so a retried finish gets it again
+    pub finished: Option<serde_json::Value>,
+    /// When a chunk last arrived
+    touched: std::time::Instant,
+}
+
+/// Where a chunked upload's bytes go
+enum Spool {
+    /// A temp file, decrypted once every byte is in
+    File(tempfile::NamedTempFile),
+    /// Straight through the decryptor into [`Partial::plaintext`]
+    Stream(crate::crypto::StreamDecryptor),
+}
+
+/// Why a chunk was not appended
+#[derive(Debug)]
+pub enum Append {
+    /// Not where the upload is at; carries the offset to resume from
+    WrongOffset(u64),
+    /// Runs past the announced size
+    TooLong,
+    Finished,
+    /// Writing the temp file failed (disk full?)
+    Io(std::io::Error),
+    /// A streamed chunk did not decrypt
+    Corrupt(anyhow::Error),
+}
+
+impl Partial {
+    /// Append a chunk written at `offset`; returns the new offset
+    pub fn append(&mut self, offset: u64, data: &[u8]) -> Result<u64, Append> {
+        use std::io::Write;
+
+        let Some(spool) = self.spool.as_mut() else {
+            return Err(Append::Finished);
+        };
+        if offset != self.offset {
+            return Err(Append::WrongOffset(self.offset));
+        }
+        if offset + data.len() as u64 > self.size {
+            return Err(Append::TooLong);
+        }
+        match spool {
+            Spool::File(file) => {
+                // A failed write is cut back off, so the offset stays true
+                if let Err(e) = file.write_all(data).and_then(|_| file.flush()) {
+                    let _ = file.as_file().set_len(self.offset);
+                    let _ = std::io::Seek::seek(file, std::io::SeekFrom::Start(self.offset));
+                    return Err(Append::Io(e));
+                }
+            }
+            Spool::Stream(decryptor) => {
+                let plaintext = decryptor.push(data).map_err(Append::Corrupt)?;
+                self.plaintext.extend_from_slice(&plaintext);
+            }
+        }
+        self.offset += data.len() as u64;
+        self.touched = std::time::Instant::now();
+        Ok(self.offset)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.offset == self.size
+    }
+
+    /// Hand over the temp file holding everything received, rewound to
+    /// the start. It is deleted when dropped.
+    pub fn take(&mut self) -> std::io::Result<tempfile::NamedTempFile> {
+        match self.spool.take() {
+            Some(Spool::File(mut file)) => {
+                std::io::Seek::rewind(&mut file)?;
+                Ok(file)
+            }
+            Some(stream) => {
+                self.spool = Some(stream);
+                Err(std::io::Error::other("upload was streamed"))
+            }
+            None => Err(std::io::Error::other("upload already finished")),
+        }
+    }
+
+    /// End a streamed upload; returns its plaintext size and SHA-256
+    pub fn finish_stream(&mut self) -> anyhow::Result<(u64, [u8; 32])> {
+        match self.spool.take() {
+            Some(Spool::Stream(decryptor)) => Ok((decryptor.finish()?, decryptor.sha256())),
+            _ => anyhow::bail!("upload was not streamed"),
+        }
+    }
+}
+
+/// Chunked uploads by id
+#[derive(Default)]
+pub struct Partials {
+    uploads: dashmap::DashMap<String, std::sync::Arc<tokio::sync::Mutex<Partial>>>,
+}
+
+impl Partials {
+    /// Open a temp file in `dir` for an upload of `size` encrypted bytes
+    pub fn start(
+        &self,
+        dir: &Path,
+        relative: PathBuf,
+        size: u64,
+        digest: Option<String>,
+    ) -> std::io::Result<String> {
+        let file = tempfile::Builder::new()
+            .prefix(".deadrop-")
+            .suffix(".part")
+            .tempfile_in(dir)?;
+        Ok(self.insert(relative, size, digest, Spool::File(file)))
+    }
+
+    /// An upload decrypted as it arrives instead of stored (`-o -`)
+    pub fn start_stream(
+        &self,
+        key: &crate::crypto::EncryptionKey,
+        relative: PathBuf,
+        size: u64,
+        digest: Option<String>,
+    ) -> anyhow::Result<String> {
+        let decryptor = crate::crypto::StreamDecryptor::new(key)?;
+        Ok(self.insert(relative, size, digest, Spool::Stream(decryptor)))
+    }
+
+    fn insert(&self, relative: PathBuf, size: u64, digest: Option<String>, spool: Spool) -> String {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        self.uploads.insert(
+            id.clone(),
+            std::sync::Arc::new(tokio::sync::Mutex::new(Partial {
+                relative,
+                size,
+                offset: 0,
+                digest,
+                spool: Some(spool),
+                plaintext: Vec::new(),
+                finished: None,
+                touched: std::time::Instant::now(),
+            })),
+        );
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<std::sync::Arc<tokio::sync::Mutex<Partial>>> {
+        self.uploads.get(id).map(|p| p.clone())
+    }
+
+    /// Uploads started and not yet finished
+    pub fn unfinished(&self) -> usize {
+        self.uploads
+            .iter()
+            .filter(|p| p.try_lock().map_or(true, |p| p.finished.is_none()))
+            .count()
+    }
+
+    pub fn remove(&self, id: &str) {
+        self.uploads.remove(id);
+    }
+
+    /// Give up on uploads nothing was sent to for `idle`, deleting their
+    /// temp files. Returns the announced sizes of the unfinished ones,
+    /// whose reservations are to be released; uploads busy finishing are
+    /// left alone.
+    pub fn reap_idle(&self, idle: std::time::Duration) -> Vec<u64> {
+        let mut reaped = Vec::new();
+        self.uploads.retain(|_, partial| match partial.try_lock() {
+            Ok(partial) if partial.touched.elapsed() >= idle => {
+                if partial.finished.is_none() {
+                    reaped.push(partial.size);
+                }
+                false
+            }
+            _ => true,
+        });
+        reaped
+    }
+
+    /// Forget every upload, deleting their temp files
+    pub fn clear(&self) {
+        self.uploads.clear();
+    }
+}
+
+/// Bytes available to us on the filesystem holding `path`; None if the
+/// filesystem can't be asked
+pub fn free_space(path: &Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut available = 0u64;
+        let ok = unsafe {
+            GetDiskFreeSpaceExW(
+                wide.as_ptr(),
+                &mut available,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        (ok != 0).then_some(available)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = path;
+        None
+    }
+}
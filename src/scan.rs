@@ -0,0 +1,318 @@
+//! Content scanning for receive mode (`--clamd`, `--scan-cmd`).
+//!
+//! With a scanner configured, decrypted uploads are first written to a
+//! quarantine directory and scanned there. Only a clean verdict moves the
+//! file into the output directory; anything else — a detection, or a
+//! scanner that could not give an answer — leaves it in quarantine.
+//!
+//! clamd is spoken to directly (`INSTREAM` over its unix socket or TCP
+//! port). Any other scanner can be plugged in as a command that follows
+//! the `clamscan` exit codes: 0 clean, 1 infected, anything else an error.
+
+use crate::receive::{self, OnConflict};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Default quarantine, inside the output directory
+pub const QUARANTINE_DIR: &str = ".deadrop-quarantine";
+/// A scan that takes longer than this counts as failed
+const SCAN_TIMEOUT: Duration = Duration::from_secs(120);
+/// Bytes per `INSTREAM` chunk
+const CLAMD_CHUNK: usize = 64 * 1024;
+
+/// Where verdicts come from
+#[derive(Clone, Debug)]
+pub enum Scanner {
+    /// clamd at a unix socket path or `host:port`
+    Clamd(String),
+    /// Shell command; the file is in `$DEADROP_PATH`
+    Command(String),
+}
+
+/// What the scanner said about a file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Clean,
+    /// Detected, with the threat name
+    Infected(String),
+    /// No verdict: the scanner failed, timed out or was unreachable
+    Failed(String),
+}
+
+impl Verdict {
+    pub fn is_clean(&self) -> bool {
+        matches!(self, Verdict::Clean)
+    }
+
+    /// Short name for replies and events
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::Clean => "clean",
+            Verdict::Infected(_) => "infected",
+            Verdict::Failed(_) => "scan_failed",
+        }
+    }
+
+    /// The threat name or error, if any
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            Verdict::Clean => None,
+            Verdict::Infected(detail) | Verdict::Failed(detail) => Some(detail),
+        }
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Clean => write!(f, "clean"),
+            Verdict::Infected(threat) => write!(f, "infected: {}", threat),
+            Verdict::Failed(error) => write!(f, "scan failed: {}", error),
+        }
+    }
+}
+
+impl Scanner {
+    /// Human description for the banner
+    pub fn describe(&self) -> String {
+        match self {
+            Scanner::Clamd(address) => format!("clamd at {}", address),
+            Scanner::Command(command) => format!("`{}`", command),
+        }
+    }
+
+    /// Make sure the scanner is there before the link goes out
+    pub async fn check(&self) -> anyhow::Result<()> {
+        match self {
+            Scanner::Clamd(address) => {
+                let reply = Stream::connect(address).await?.ping().await?;
+                if reply != "PONG" {
+                    anyhow::bail!("clamd at {} answered PING with '{}'", address, reply);
+                }
+                Ok(())
+            }
+            Scanner::Command(_) => Ok(()),
+        }
+    }
+
+    /// Scan one file. Never errors: a scanner problem is a [`Verdict::Failed`].
+    pub async fn scan(&self, path: &Path) -> Verdict {
+        let verdict = match self {
+            Scanner::Clamd(address) => {
+                tokio::time::timeout(SCAN_TIMEOUT, scan_clamd(address, path)).await
+            }
+            Scanner::Command(command) => {
+                tokio::time::timeout(SCAN_TIMEOUT, scan_command(command, path)).await
+            }
+        };
+        verdict.unwrap_or_else(|_| {
+            Verdict::Failed(format!("timed out after {}s", SCAN_TIMEOUT.as_secs()))
+        })
+    }
+}
+
+/// clamd's socket: unix when the address is a path, TCP otherwise
+enum Stream {
+    #[cfg(unix)]
+    Unix(tokio::net::UnixStream),
+    Tcp(tokio::net::TcpStream),
+}
+
+impl Stream {
+    async fn connect(address: &str) -> anyhow::Result<Self> {
+        let connected = async {
+            #[cfg(unix)]
+            if address.starts_with('/') || address.starts_with("unix:") {
+                let path = address.trim_start_matches("unix:");
+                return Ok(Stream::Unix(tokio::net::UnixStream::connect(path).await?));
+            }
+            let address = address.trim_start_matches("tcp://");
+            std::io::Result::Ok(Stream::Tcp(tokio::net::TcpStream::connect(address).await?))
+        };
+        connected
+            .await
+            .map_err(|e| anyhow::anyhow!("Cannot reach clamd at {}: {}", address, e))
+    }
+
+    async fn ping(&mut self) -> std::io::Result<String> {
+        match self {
+            #[cfg(unix)]
+            Stream::Unix(s) => command(s, b"zPING\0").await,
+            Stream::Tcp(s) => command(s, b"zPING\0").await,
+        }
+    }
+
+    async fn instream(&mut self, path: &Path) -> std::io::Result<String> {
+        match self {
+            #[cfg(unix)]
+            Stream::Unix(s) => instream(s, path).await,
+            Stream::Tcp(s) => instream(s, path).await,
+        }
+    }
+}
+
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    request: &[u8],
+) -> std::io::Result<String> {
+    stream.write_all(request).await?;
+    read_reply(stream).await
+}
+
+/// Read a NUL-terminated (`z`-command) reply
+async fn read_reply<S: AsyncRead + Unpin>(stream: &mut S) -> std::io::Result<String> {
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+    let end = reply.iter().position(|&b| b == 0).unwrap_or(reply.len());
+    Ok(String::from_utf8_lossy(&reply[..end]).trim().to_string())
+}
+
+/// Stream the file with `INSTREAM`: length-prefixed chunks, then a zero length
+async fn instream<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    path: &Path,
+) -> std::io::Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    stream.write_all(b"zINSTREAM\0").await?;
+    let mut buf = vec![0u8; CLAMD_CHUNK];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        stream.write_all(&(n as u32).to_be_bytes()).await?;
+        stream.write_all(&buf[..n]).await?;
+    }
+    stream.write_all(&0u32.to_be_bytes()).await?;
+    stream.flush().await?;
+    read_reply(stream).await
+}
+
+async fn scan_clamd(address: &str, path: &Path) -> Verdict {
+    let reply = match Stream::connect(address).await {
+        Ok(mut stream) => stream.instream(path).await,
+        Err(e) => return Verdict::Failed(e.to_string()),
+    };
+    match reply {
+        Ok(reply) => parse_clamd_reply(&reply),
+        Err(e) => Verdict::Failed(format!("clamd: {}", e)),
+    }
+}
+
+/// `stream: OK`, `stream: <threat> FOUND`, or an error line
+pub fn parse_clamd_reply(reply: &str) -> Verdict {
+    let result = reply.split_once(": ").map_or(reply, |(_, result)| result);
+    if result == "OK" {
+        Verdict::Clean
+    } else if let Some(threat) = result.strip_suffix(" FOUND") {
+        Verdict::Infected(threat.to_string())
+    } else {
+        Verdict::Failed(format!("clamd: {}", reply))
+    }
+}
+
+/// Run `--scan-cmd` on a file. Exit 0 is clean; exit 1 is infected, with
+/// the last line of stdout as the threat name.
+async fn scan_command(command: &str, path: &Path) -> Verdict {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut c = tokio::process::Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut c = tokio::process::Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    };
+    cmd.env("DEADROP_PATH", path)
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true);
+
+    let output = match cmd.output().await {
+        Ok(output) => output,
+        Err(e) => return Verdict::Failed(format!("scanner failed to start: {}", e)),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let last_line = stdout.lines().rev().find(|l| !l.trim().is_empty());
+    match output.status.code() {
+        Some(0) => Verdict::Clean,
+        Some(1) => Verdict::Infected(last_line.unwrap_or("detected").trim().to_string()),
+        _ => Verdict::Failed(format!("scanner exited with {}", output.status)),
+    }
+}
+
+/// What became of a scanned upload
+#[derive(Debug)]
+pub enum Outcome {
+    /// Clean and moved to the output directory (None: skipped on conflict)
+    Saved(Option<PathBuf>),
+    /// Held back; `path` is where it sits in quarantine
+    Quarantined { path: PathBuf, verdict: Verdict },
+}
+
+/// Scanner plus the directory uploads wait in while being scanned
+#[derive(Clone, Debug)]
+pub struct Quarantine {
+    pub dir: PathBuf,
+    pub scanner: Scanner,
+}
+
+impl Quarantine {
+    pub fn new(dir: PathBuf, scanner: Scanner) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow::anyhow!("Cannot create quarantine {}: {}", dir.display(), e))?;
+        Ok(Self { dir, scanner })
+    }
+
+    /// Write an upload to quarantine, scan it, and move it to `output_dir`
+    /// (under `relative`, honouring `on_conflict`) only if it is clean
+    pub async fn admit(
+        &self,
+        output_dir: &Path,
+        relative: &Path,
+        data: &[u8],
+        on_conflict: OnConflict,
+    ) -> anyhow::Result<Outcome> {
+        let held = self.hold(relative, data)?;
+        self.release(held, output_dir, relative, on_conflict).await
+    }
+
+    /// Write an upload to quarantine from whatever `data` reads; returns
+    /// where it is held. Nothing is left behind if reading fails.
+    pub fn hold(&self, relative: &Path, mut data: impl std::io::Read) -> anyhow::Result<PathBuf> {
+        receive::save_with(&self.dir, relative, OnConflict::Rename, |file| {
+            std::io::copy(&mut data, file).map(|_| ())
+        })?
+        .map(|p| self.dir.join(p))
+        .ok_or_else(|| anyhow::anyhow!("Cannot write {} to quarantine", relative.display()))
+    }
+
+    /// Scan an upload held in quarantine and move it to `output_dir` only
+    /// if it is clean
+    pub async fn release(
+        &self,
+        held: PathBuf,
+        output_dir: &Path,
+        relative: &Path,
+        on_conflict: OnConflict,
+    ) -> anyhow::Result<Outcome> {
+        let verdict = self.scanner.scan(&held).await;
+        if !verdict.is_clean() {
+            return Ok(Outcome::Quarantined {
+                path: held,
+                verdict,
+            });
+        }
+
+        // Move exactly the bytes that were scanned
+        let saved = receive::save_with(output_dir, relative, on_conflict, |file| {
+            std::io::copy(&mut std::fs::File::open(&held)?, file).map(|_| ())
+        })?;
+        std::fs::remove_file(&held)?;
+        Ok(Outcome::Saved(saved))
+    }
+}
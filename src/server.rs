@@ -1,10 +1,13 @@
 use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
 
 use base64::Engine;
 
 use crate::{
     config::{DropConfig, ReceiveConfig},
-    crypto, progress,
+    crypto,
+    events::{Event, Events},
+    progress,
     store::BlobStore,
 };
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
@@ -13,7 +16,7 @@ use axum::middleware;
 use axum::{
     Json, Router,
     body::Body,
-    extract::{ConnectInfo, Path, State},
+    extract::{ConnectInfo, DefaultBodyLimit, Path, Query, State},
     http::{HeaderMap, StatusCode, header},
     response::{Html, IntoResponse, Response},
     routing::{get, post},
@@ -36,17 +39,55 @@ struct StaticAssets;
 pub struct AppState {
     pub store: BlobStore,
     pub shutdown: Arc<Notify>,
+    pub proxies: crate::proxy::TrustedProxies,
+    pub transfers: progress::TransferBoard,
+    pub events: Events,
+    pub metrics: Arc<crate::metrics::Metrics>,
+    pub receipts: crate::receipt::Receipts,
 }
 
 pub struct ReceiveState {
     pub key: crypto::EncryptionKey,
     pub output_dir: std::path::PathBuf,
     pub shutdown: Arc<Notify>,
-    pub received: std::sync::atomic::AtomicBool,
+    pub session: crate::receive::Session,
+    pub on_conflict: crate::receive::OnConflict,
+    pub allow_dotfiles: bool,
+    /// Scan-before-save, when a scanner is configured
+    pub quarantine: Option<crate::scan::Quarantine>,
+    /// Seal files to this key instead of saving plaintext
+    pub vault: Option<crate::vault::Vault>,
+    /// `-o -`: the one upload goes to stdout, decrypted as it arrives
+    pub to_stdout: bool,
+    /// Chunked uploads, resumable by id
+    pub partials: crate::receive::Partials,
+    /// Uploads in progress
+    pub uploads: std::sync::atomic::AtomicUsize,
+    pub events: Events,
+    pub metrics: Arc<crate::metrics::Metrics>,
 }
 
 const DISK_THRESHOLD: u64 = 50 * 1024 * 1024;
 
+/// How long a finished one-shot drop stays up waiting for its receipt
+const RECEIPT_GRACE: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long shutdown waits for queued hooks and webhooks
+const EVENT_SETTLE: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Frame size for whole-blob transfers (WebSocket messages, HTTP body
+/// pieces). Independent of the container's chunk size: frames are cut
+/// from the raw ciphertext and the client reassembles it.
+const STREAM_FRAME: usize = 64 * 1024;
+
+/// Query string on fetch paths: `?t=` carries the one-time access token,
+/// `?session=` the chunked session `/api/chunks` issued
+#[derive(serde::Deserialize)]
+struct DropQuery {
+    t: Option<String>,
+    session: Option<String>,
+}
+
 async fn security_headers(
     request: axum::extract::Request,
     next: axum::middleware::Next,
@@ -98,29 +139,308 @@ async fn serve_favicon() -> Response {
 }
 
 // ===============================================================================
-// TUNNEL-AWARE IP RESOLUTION
+// PROXY-AWARE IP RESOLUTION
 // ===============================================================================
 
-/// Resolve the real client IP. When behind Cloudflare tunnel, ConnectInfo
-/// always shows 127.0.0.1 — use CF-Connecting-IP or X-Forwarded-For instead.
-fn resolve_client_ip(addr: &SocketAddr, headers: &HeaderMap) -> String {
-    if let Some(val) = headers
-        .get("CF-Connecting-IP")
-        .or_else(|| headers.get("X-Forwarded-For"))
-        .and_then(|v| v.to_str().ok())
-    {
-        // X-Forwarded-For can be "client, proxy1, proxy2" — take first
-        let real_ip = val.split(',').next().unwrap_or(val).trim();
-        if !real_ip.is_empty() {
-            return real_ip.to_string();
+/// Resolve the real client IP. Forwarding headers (CF-Connecting-IP,
+/// X-Forwarded-For) count only when the TCP peer is a trusted proxy —
+/// a `--trust-proxy` network. Tunnel requests arrive already resolved on
+/// their own listener (see `via_tunnel`).
+fn resolve_client_ip(
+    proxies: &crate::proxy::TrustedProxies,
+    addr: &SocketAddr,
+    headers: &HeaderMap,
+) -> String {
+    proxies.client_ip(addr, headers).to_string()
+}
+
+// ===============================================================================
+// ACCESS POLICY
+// ===============================================================================
+
+/// Apply the drop's access policy (networks, time windows, token, distinct
+/// IPs) to this request. Every fetch path goes through here; `Some` is the
+/// rejection to send back.
+fn enforce_access(
+    state: &AppState,
+    drop: &crate::store::Drop,
+    addr: &SocketAddr,
+    headers: &HeaderMap,
+    token: Option<&str>,
+) -> Option<Response> {
+    let client_ip = state.proxies.client_ip(addr, headers);
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok());
+    drop.admit(client_ip, user_agent, token)
+        .err()
+        .map(|denied| {
+            eprintln!(
+                " {} Blocked download attempt from {} [resolved: {}] ({})",
+                console::style("🛡").red(),
+                addr,
+                client_ip,
+                denied.reason()
+            );
+            state.events.emit(Event::Blocked {
+                drop_id: drop.id.clone(),
+                ip: client_ip.to_string(),
+                reason: denied.code(),
+            });
+            (StatusCode::FORBIDDEN, denied.message()).into_response()
+        })
+}
+
+// ===============================================================================
+// EVENTS
+// ===============================================================================
+
+/// Event sinks for this run: the metrics counters, plus the audit log,
+/// `--json` lines on stdout, hook commands and the webhook when configured
+fn event_bus(
+    json: bool,
+    hooks: &crate::hooks::HookConfig,
+    audit_log: Option<&std::path::Path>,
+    metrics: &Arc<crate::metrics::Metrics>,
+) -> anyhow::Result<Events> {
+    let mut events = Events::new().with_sink(metrics.clone());
+    if let Some(path) = audit_log {
+        events = events.with_sink(crate::audit::AuditLog::open(path)?);
+    }
+    if json {
+        events = events.with_sink(crate::events::JsonLines);
+    }
+    if !hooks.is_empty() {
+        events = events.with_sink(crate::hooks::Hooks::spawn(hooks.clone())?);
+    }
+    Ok(events)
+}
+
+// ===============================================================================
+// DOWNLOAD SESSIONS
+// ===============================================================================
+
+/// Count a finished session as one download, and burn the drop if that
+/// was its last allowed download.
+fn complete_download(state: &AppState, drop: &crate::store::Drop, session: u64) {
+    let Some((client_ip, transport, bytes)) = drop
+        .downloads
+        .sessions
+        .lock()
+        .unwrap()
+        .get(&session)
+        .map(|s| (s.client_ip.to_string(), s.transport, s.bytes_served))
+    else {
+        return;
+    };
+    let Some((count, should_delete)) = drop.complete_session(session) else {
+        return;
+    };
+
+    state.transfers.completed(&drop.id, session);
+    progress::print_download_event(count, drop.max_downloads, &client_ip);
+    state.events.emit(Event::DownloadCompleted {
+        drop_id: drop.id.clone(),
+        session,
+        ip: client_ip,
+        transport: transport.label(),
+        bytes,
+        count,
+        max_downloads: drop.max_downloads,
+    });
+
+    if should_delete {
+        state.store.remove(&drop.id);
+        progress::print_self_destruct();
+        state.events.emit(Event::Burned {
+            drop_id: drop.id.clone(),
+            reason: "download_limit",
+        });
+        if state.store.is_empty() {
+            // The receipt follows the last byte; give the page a moment to send it
+            progress::print_awaiting_receipt(RECEIPT_GRACE.as_secs());
+            let receipts = state.receipts.clone();
+            let shutdown = state.shutdown.clone();
+            let drop_id = drop.id.clone();
+            tokio::spawn(async move {
+                let _ =
+                    tokio::time::timeout(RECEIPT_GRACE, receipts.wait_for(&drop_id, count)).await;
+                shutdown.notify_one();
+            });
+        }
+    }
+}
+
+/// Open a streaming session and its progress bar. None when every
+/// download slot is taken.
+fn open_download(
+    state: &Arc<AppState>,
+    drop: &Arc<crate::store::Drop>,
+    client_ip: std::net::IpAddr,
+    transport: crate::store::Transport,
+) -> Option<SessionGuard> {
+    let session = drop.open_session(client_ip, transport)?;
+    state.transfers.started(
+        &drop.id,
+        session,
+        &client_ip.to_string(),
+        transport.label(),
+        drop.encrypted_size(),
+    );
+    download_started(state, drop, session, client_ip, transport);
+    Some(SessionGuard::new(state.clone(), drop.clone(), session))
+}
+
+/// Open a chunked session and its progress bar; returns the token that
+/// `/api/chunk` requests must carry. None when every download slot is taken.
+fn open_chunk_download(
+    state: &AppState,
+    drop: &crate::store::Drop,
+    client_ip: std::net::IpAddr,
+) -> Option<String> {
+    let (session, token) = drop.open_chunk_session(client_ip)?;
+    state.transfers.started(
+        &drop.id,
+        session,
+        &client_ip.to_string(),
+        crate::store::Transport::Chunks.label(),
+        drop.encrypted_size(),
+    );
+    download_started(
+        state,
+        drop,
+        session,
+        client_ip,
+        crate::store::Transport::Chunks,
+    );
+    Some(token)
+}
+
+fn download_started(
+    state: &AppState,
+    drop: &crate::store::Drop,
+    session: u64,
+    client_ip: std::net::IpAddr,
+    transport: crate::store::Transport,
+) {
+    state.events.emit(Event::DownloadStarted {
+        drop_id: drop.id.clone(),
+        session,
+        ip: client_ip.to_string(),
+        transport: transport.label(),
+    });
+}
+
+/// Close out a session that ended early: its bar, and the event
+fn download_aborted(
+    state: &AppState,
+    drop: &crate::store::Drop,
+    session: u64,
+    aborted: crate::store::DownloadSession,
+) {
+    state.transfers.aborted(&drop.id, session);
+    state.events.emit(Event::DownloadAborted {
+        drop_id: drop.id.clone(),
+        session,
+        ip: aborted.client_ip.to_string(),
+        transport: aborted.transport.label(),
+        bytes: aborted.bytes_served,
+    });
+}
+
+/// Note a delivered chunk; once every chunk is out the session completes
+fn chunk_delivered(
+    state: &AppState,
+    drop: &crate::store::Drop,
+    session: u64,
+    idx: u64,
+    bytes: u64,
+) {
+    state.transfers.advance(&drop.id, session, bytes);
+    state
+        .metrics
+        .bytes_served(crate::store::Transport::Chunks, bytes);
+    if drop.record_chunk(session, idx, bytes) {
+        complete_download(state, drop, session);
+    }
+}
+
+/// Abort chunked sessions that stopped fetching, so their slots come back
+fn spawn_session_reaper(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(tokio::time::Duration::from_secs(5));
+        loop {
+            tick.tick().await;
+            let now = chrono::Utc::now();
+            for drop in state.store.all() {
+                for (session, aborted) in drop.reap_idle_sessions(now) {
+                    download_aborted(&state, &drop, session, aborted);
+                }
+            }
+        }
+    });
+}
+
+/// Response when every download slot is taken by finished or in-flight sessions
+fn slots_exhausted(
+    state: &AppState,
+    drop: &crate::store::Drop,
+    client_ip: std::net::IpAddr,
+) -> Response {
+    state.events.emit(Event::Exhausted {
+        drop_id: drop.id.clone(),
+        ip: client_ip.to_string(),
+    });
+    (
+        StatusCode::GONE,
+        "Download limit reached — this drop is being or has been downloaded",
+    )
+        .into_response()
+}
+
+/// Owns a streaming session (blob or WebSocket). Completing it counts the
+/// download; dropping it early — client gone, send error — aborts it.
+struct SessionGuard {
+    state: Arc<AppState>,
+    drop: Arc<crate::store::Drop>,
+    session: u64,
+    done: bool,
+}
+
+impl SessionGuard {
+    fn new(state: Arc<AppState>, drop: Arc<crate::store::Drop>, session: u64) -> Self {
+        Self {
+            state,
+            drop,
+            session,
+            done: false,
         }
     }
-    addr.ip().to_string()
+
+    /// Account bytes that went out on this session
+    fn sent(&self, transport: crate::store::Transport, bytes: u64) {
+        self.drop.record_bytes(self.session, bytes);
+        self.state
+            .transfers
+            .advance(&self.drop.id, self.session, bytes);
+        self.state.metrics.bytes_served(transport, bytes);
+    }
+
+    fn complete(mut self) {
+        self.done = true;
+        complete_download(&self.state, &self.drop, self.session);
+    }
 }
 
-/// Check if a request is coming through Cloudflare tunnel
-fn is_tunnel_request(addr: &SocketAddr, headers: &HeaderMap) -> bool {
-    addr.ip().is_loopback() && headers.get("CF-Connecting-IP").is_some()
+impl std::ops::Drop for SessionGuard {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        if let Some(aborted) = self.drop.abort_session(self.session) {
+            download_aborted(&self.state, &self.drop, self.session, aborted);
+        }
+    }
 }
 
 // ===============================================================================
@@ -134,8 +454,19 @@ pub async fn start(
 ) -> anyhow::Result<()> {
     let shutdown = Arc::new(Notify::new());
     let shutdown_clone = shutdown.clone();
-    let store = BlobStore::new(move || {
+    let metrics = crate::metrics::Metrics::new();
+    let events = event_bus(
+        config.json,
+        &config.hooks,
+        config.audit_log.as_deref(),
+        &metrics,
+    )?;
+    let expire_events = events.clone();
+    let store = BlobStore::new(move |drop_id| {
         progress::print_expired();
+        expire_events.emit(Event::Expired {
+            drop_id: drop_id.to_string(),
+        });
     });
     store.spawn_reaper();
 
@@ -156,48 +487,95 @@ pub async fn start(
     let encrypted_path: Option<std::path::PathBuf>;
     let ciphertext: Option<Vec<u8>>;
     let encrypted_size: u64;
-    // Number of encrypted chunks (filled by encrypt helpers)
-    let mut total_chunks: u64 = 0;
-
-    if config.file.is_dir() {
-        let pm = progress::ProgressManager::new();
-        let archive_bar = pm.create_encrypt_bar(0);
-        archive_bar.set_style(
-            indicatif::ProgressStyle::with_template(
-                " {spinner:.green} Archiving [{bar:40.yellow/dark_gray}] {pos}/{len} files",
-            )
-            .unwrap()
-            .progress_chars("━╸─"),
+    // Slot layout of a deniable container (password drops only)
+    let mut layout: Option<crypto::DeniableLayout> = None;
+    // What a recipient's receipt may attest to: the real payload, and the decoy
+    let mut receipts = Vec::new();
+    // SHA-256 of the real payload, taken during encryption (None until
+    // a growing drop finishes sealing)
+    let real_sha256: Option<[u8; 32]>;
+    // One sealed digest per slot of a password drop sealed up front
+    let mut slot_digests: Option<String> = None;
+    // Chunks left to encrypt once a large drop is live
+    let mut growing: Option<Growing> = None;
+
+    if let Some(salt) = password_salt {
+        // Password drops always ship as a two-slot deniable container so a
+        // drop with a decoy looks exactly like one without.
+        let (file, decoy) = (
+            config.file.clone(),
+            config.decoy.clone().zip(config.decoy_password.clone()),
         );
+        let (mut real, mut decoy) =
+            tokio::task::spawn_blocking(move || open_deniable(&file, decoy, &salt)).await??;
+        let slots = crypto::deniable_layout(
+            &real.input(&key),
+            decoy.as_mut().map(|(d, k)| d.input(k)).as_ref(),
+            config.chunk_size,
+        );
+        (layout, encrypted_size) = (Some(slots), slots.size());
+        (file_size, filename) = (real.len, real.filename.clone());
+        if decoy
+            .as_mut()
+            .is_some_and(|(d, k)| slots.outgrows(&d.input(k)))
+        {
+            eprintln!(
+                " {} The decoy is much smaller than the file: its padding hints at a bigger slot",
+                console::style("⚠").yellow()
+            );
+        }
 
-        let (archive_bytes, archive_name): (Vec<u8>, String) =
-            crate::archive::compress_folder(&config.file, &archive_bar)?;
-
-        file_size = archive_bytes.len() as u64;
-        filename = archive_name;
-
-        let pm2 = progress::ProgressManager::new();
-        let encrypt_bar = pm2.create_encrypt_bar(file_size);
-
-        if file_size > DISK_THRESHOLD {
-            let mut cursor = std::io::Cursor::new(&archive_bytes);
-            let info = crypto::encrypt_file_to_disk(&mut cursor, &key, file_size, |bytes| {
-                encrypt_bar.set_position(bytes)
-            })?;
-            encrypted_size = info.total_size;
-            encrypted_path = Some(info.path);
-            ciphertext = None;
-            total_chunks = info.total_chunks;
+        if slots.plaintext_len() > DISK_THRESHOLD {
+            // Nothing is written yet: each slot starts with its own header
+            let temp_file = tempfile::NamedTempFile::new()?;
+            let (file, path) = temp_file
+                .keep()
+                .map_err(|e| anyhow::anyhow!("Failed to persist temp file: {}", e))?;
+            (encrypted_path, ciphertext, real_sha256) = (Some(path), None, None);
+            growing = Some(Growing {
+                file,
+                size: Some(slots.plaintext_len()),
+                payload: Payload::Deniable {
+                    real,
+                    decoy,
+                    layout: slots,
+                },
+            });
         } else {
-            let mut cursor = std::io::Cursor::new(&archive_bytes);
-            let ct = crypto::encrypt_file_streaming(&mut cursor, &key, file_size, |bytes| {
-                encrypt_bar.set_position(bytes)
-            })?;
-            encrypted_size = ct.len() as u64;
-            ciphertext = Some(ct);
-            encrypted_path = None;
+            let seal_key = key.clone();
+            let (ct, sealed) =
+                tokio::task::spawn_blocking(move || seal_deniable(real, decoy, &seal_key, slots))
+                    .await??;
+            (encrypted_path, ciphertext) = (None, Some(ct));
+            real_sha256 = Some(sealed.sha256);
+            (receipts, slot_digests) = (sealed.receipts, Some(sealed.digest));
         }
-        encrypt_bar.finish_and_clear();
+        if config.decoy.is_some() {
+            eprintln!(
+                " {} Decoy added — the duress password opens it instead",
+                console::style("🎭").bold()
+            );
+        }
+    } else if config.file.is_dir() {
+        // Archived as it is sealed, so a folder's link goes out as fast as
+        // a large file's. Nobody knows the archive's size before it ends:
+        // the container header leaves it open until then.
+        filename = crate::archive::archive_name(&config.file);
+        let temp_file = tempfile::NamedTempFile::new()?;
+        let (mut file, path) = temp_file
+            .keep()
+            .map_err(|e| anyhow::anyhow!("Failed to persist temp file: {}", e))?;
+        let container = crypto::GrowingContainer::start_open(&mut file, config.chunk_size)?;
+        (encrypted_path, ciphertext, encrypted_size) = (Some(path), None, 0);
+        (file_size, real_sha256) = (0, None);
+        growing = Some(Growing {
+            file,
+            size: None,
+            payload: Payload::Archive {
+                container,
+                folder: config.file.clone(),
+            },
+        });
     } else {
         file_size = std::fs::metadata(&config.file)?.len();
         filename = config
@@ -210,41 +588,66 @@ pub async fn start(
         let pm = progress::ProgressManager::new();
         let encrypt_bar = pm.create_encrypt_bar(file_size);
 
-        if file_size > DISK_THRESHOLD {
-            let mut file = std::fs::File::open(&config.file)?;
-            let info = crypto::encrypt_file_to_disk(&mut file, &key, file_size, |bytes| {
-                encrypt_bar.set_position(bytes)
-            })?;
-            encrypted_size = info.total_size;
-            encrypted_path = Some(info.path);
-            ciphertext = None;
-            total_chunks = info.total_chunks;
-        } else {
-            let mut file = std::fs::File::open(&config.file)?;
-            let ct = crypto::encrypt_file_streaming(&mut file, &key, file_size, |bytes| {
-                encrypt_bar.set_position(bytes)
-            })?;
-            // derive chunk count from header in-memory
-            if let Ok(header) =
-                crypto::EncryptedHeader::from_bytes(&ct[..crypto::EncryptedHeader::SIZE])
-            {
-                total_chunks = header.total_chunks;
-            }
-            encrypted_size = ct.len() as u64;
-            ciphertext = Some(ct);
-            encrypted_path = None;
-        }
+        let file = Box::new(std::fs::File::open(&config.file)?);
+        let encrypted = encrypt_payload(
+            file,
+            &key,
+            file_size,
+            config.chunk_size,
+            encrypt_bar.clone(),
+        )
+        .await?;
         encrypt_bar.finish_and_clear();
+        (encrypted_path, ciphertext, encrypted_size) =
+            (encrypted.path, encrypted.ciphertext, encrypted.size);
+        (real_sha256, growing) = (encrypted.sha256, encrypted.growing);
+    };
+    if password_salt.is_none()
+        && let Some(sha256) = real_sha256
+    {
+        receipts.push(crate::receipt::Expected::new(&key.0, sha256, false));
+    }
+
+    // Password drops seal one per slot, so the digest gives no decoy away
+    let digest = match (slot_digests, real_sha256) {
+        (Some(digests), _) => Some(digests),
+        (None, Some(sha256)) => Some(crypto::seal_digest(&key, &sha256)?),
+        (None, None) => None,
     };
 
-    let mime = mime_guess::from_path(&config.file)
-        .first_or_octet_stream()
-        .to_string();
+    // A folder still being archived has no size until it is sealed
+    let size_open = growing.as_ref().is_some_and(|g| g.size.is_none());
+
+    // Chunks per container (per slot for password drops — both slots match,
+    // and a growing one has no header on disk yet)
+    let total_chunks = match layout {
+        Some(slots) => slots.chunks,
+        None if size_open => 0,
+        None => read_header(encrypted_path.as_deref(), ciphertext.as_deref())?.total_chunks,
+    };
+    let slot_size = layout.map_or(0, |slots| slots.slot_size);
+
+    // Password drops keep the name inside the sealed slot; metadata stays generic
+    let (public_filename, mime, public_size) = if slot_size > 0 {
+        (
+            "sealed-drop".to_string(),
+            "application/octet-stream".to_string(),
+            slot_size,
+        )
+    } else {
+        (
+            filename.clone(),
+            mime_guess::from_path(&config.file)
+                .first_or_octet_stream()
+                .to_string(),
+            file_size,
+        )
+    };
 
     let drop_id = format!(
         "{}{}",
-        uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
-        uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+        &uuid::Uuid::new_v4().simple().to_string()[..8],
+        &uuid::Uuid::new_v4().simple().to_string()[..8],
     );
 
     // Build recipient envelopes if recipients were provided
@@ -336,49 +739,77 @@ pub async fn start(
         ciphertext,
         encrypted_size,
         total_chunks,
+        slot_size,
         recipient_envelopes,
-        filename: filename.clone(),
+        filename: public_filename,
         mime_type: mime,
-        file_size,
+        file_size: public_size,
         created_at: chrono::Utc::now(),
-        expires_at: chrono::Utc::now() + config.expiry_duration,
+        expires_at: std::sync::Mutex::new(chrono::Utc::now() + config.expiry_duration),
         max_downloads: config.max_downloads,
-        download_count: std::sync::atomic::AtomicU32::new(0),
         has_password: password_salt.is_some(),
-        pinned_ip: std::sync::Mutex::new(None),
+        digest,
+        policy: config.policy.clone(),
+        downloads: Default::default(),
+        sealing: growing.as_ref().map(Growing::sealing),
     };
 
+    // The audit log records what was served; hashing a large drop costs a
+    // pass over it, so only when someone is keeping that record. A growing
+    // drop is hashed once it is sealed (`Event::Sealed`).
+    let sha256 = match &config.audit_log {
+        Some(_) if drop.sealing.is_none() => Some(ciphertext_sha256(
+            drop.encrypted_path.as_deref(),
+            drop.ciphertext.as_deref(),
+        )?),
+        _ => None,
+    };
+    // What the link announces is the deadline the drop enforces
+    let expires_at = drop.expires_at();
+    events.emit(Event::Created {
+        drop_id: drop.id.clone(),
+        size: drop.file_size(),
+        encrypted_size: drop.encrypted_size(),
+        sha256,
+        expires_at,
+        max_downloads: drop.max_downloads,
+    });
+    let receipt_book = crate::receipt::Receipts::new();
+    receipt_book.expect(&drop.id, receipts);
     store.insert(drop);
 
+    let proxies = crate::proxy::TrustedProxies::parse(&config.trusted_proxies)?;
+
     let state = Arc::new(AppState {
         store,
         shutdown: shutdown_clone,
+        proxies,
+        transfers: progress::TransferBoard::new(),
+        events,
+        metrics,
+        receipts: receipt_book,
     });
+    spawn_session_reaper(state.clone());
+
+    if let (Some(growing), Some(drop)) = (growing, state.store.get(&drop_id)) {
+        // The TUI owns the screen and --json has the event stream
+        let banner = !config.tui && !config.json;
+        spawn_sealing(
+            state.clone(),
+            drop,
+            key.clone(),
+            growing,
+            banner,
+            config.audit_log.is_some(),
+        );
+    }
 
-    let governor_conf = Arc::new(
-        GovernorConfigBuilder::default()
-            .per_second(2)
-            .burst_size(5)
-            .finish()
-            .unwrap(),
-    );
-
-    let rate_limited = Router::new()
-        .route("/d/{id}", get(serve_download_page))
-        .route("/api/blob/{id}", get(serve_blob))
-        .route("/api/chunks/{id}", get(serve_chunks))
-        .route("/api/chunk/{id}/{idx}", get(serve_chunk))
-        .route("/api/meta/{id}", get(serve_meta))
-        .route("/ws/blob/{id}", get(ws_blob_handler))
-        .layer(GovernorLayer::new(governor_conf));
+    if let Some(addr) = &config.metrics {
+        let store = state.store.clone();
+        crate::metrics::serve(addr, state.metrics.clone(), move || store.drops().len()).await?;
+    }
 
-    let app = rate_limited
-        .route("/assets/{*path}", get(serve_web_asset))
-        .route("/download-worker.js", get(serve_root_worker))
-        .route("/wasm/{*path}", get(serve_wasm_asset))
-        .route("/favicon.ico", get(serve_favicon))
-        .layer(middleware::from_fn(security_headers))
-        .with_state(state.clone());
+    let app = send_router(state.clone());
 
     // Password drops: put salt in fragment. Normal drops: put key in fragment.
     let key_fragment = match &password_salt {
@@ -389,49 +820,133 @@ pub async fn start(
         None => key.to_url_safe(),
     };
 
+    // One-time token rides in the query string, next to the drop path
+    let drop_path = match &config.policy.token {
+        Some(token) => format!("/d/{}?t={}", drop_id, token),
+        None => format!("/d/{}", drop_id),
+    };
+
     let local_ip = local_ip_address::local_ip().unwrap_or("127.0.0.1".parse().unwrap());
-    let url = format!(
-        "http://{}:{}/d/{}#{}",
-        local_ip, config.port, drop_id, key_fragment
+    let tls = make_tls_identity(config.tls, local_ip)?;
+    let url = local_link(
+        format!(
+            "http://{}:{}{}#{}",
+            local_ip, config.port, drop_path, key_fragment
+        ),
+        tls.as_ref(),
     );
-    let localhost_url = format!(
-        "http://localhost:{}/d/{}#{}",
-        config.port, drop_id, key_fragment
+    let localhost_url = local_link(
+        format!(
+            "http://localhost:{}{}#{}",
+            config.port, drop_path, key_fragment
+        ),
+        tls.as_ref(),
     );
 
+    let onion_url =
+        tor_service.map(|tor| local_link(tor.onion_url(&drop_path, &key_fragment), tls.as_ref()));
+    let tunnel_url = tunnel_service.map(|tun| tun.tunnel_url(&drop_path, &key_fragment));
+
+    if config.json {
+        state.events.emit(Event::Ready {
+            mode: "send",
+            drop_id: Some(drop_id),
+            urls: crate::events::Urls {
+                lan: url,
+                localhost: localhost_url,
+                tunnel: tunnel_url,
+                onion: onion_url,
+            },
+            key_fragment,
+            expires_at,
+            max_downloads: Some(config.max_downloads),
+        });
+        let result = serve_app(
+            app,
+            &config.bind,
+            config.port,
+            tls.as_ref(),
+            tunnel_service,
+            shutdown,
+            send_drain(&state, config.drain_timeout, false),
+        )
+        .await;
+        state.events.finish(EVENT_SETTLE).await;
+        return result;
+    }
+
+    if config.tui {
+        let links = crate::tui::DropLinks {
+            lan: url,
+            localhost: localhost_url,
+            tunnel: tunnel_url,
+            onion: onion_url,
+        };
+        let status = crate::tui::Status {
+            tunnel: tunnel_service.is_some(),
+            tor: tor_service.is_some(),
+            tls: tls.as_ref().map(|t| t.display_fingerprint()),
+        };
+        let dashboard = crate::tui::Dashboard::spawn(
+            state.clone(),
+            [(drop_id, links)].into_iter().collect(),
+            status,
+        );
+        let result = serve_app(
+            app,
+            &config.bind,
+            config.port,
+            tls.as_ref(),
+            tunnel_service,
+            shutdown,
+            send_drain(&state, config.drain_timeout, false),
+        )
+        .await;
+        dashboard.stop();
+        state.events.finish(EVENT_SETTLE).await;
+        return result;
+    }
+
     progress::print_banner(
         &url,
         &config.expire,
         config.max_downloads,
-        file_size,
+        &match size_open {
+            true => "printed once archived".to_string(),
+            false => bytesize::ByteSize::b(file_size).to_string(),
+        },
         &filename,
+        &real_sha256.map_or_else(
+            || "printed once encrypted".to_string(),
+            |sha256| crypto::to_hex(&sha256),
+        ),
         config.password.is_some(),
     );
+    print_tls_fingerprint(tls.as_ref());
+    print_access_policy(&config.policy);
 
-    if let Some(tor) = tor_service {
-        let onion_url = tor.onion_url(&format!("/d/{}", drop_id), &key_fragment);
+    if let Some(onion_url) = &onion_url {
         eprintln!(
             " {} Tor: {}",
             console::style("🧅").bold(),
-            console::style(&onion_url).green()
+            console::style(onion_url).green()
         );
         eprintln!();
     }
 
-    if let Some(tun) = tunnel_service {
-        let tunnel_url = tun.tunnel_url(&format!("/d/{}", drop_id), &key_fragment);
+    if let Some(tunnel_url) = &tunnel_url {
         eprintln!(
             " {} Tunnel: {}",
             console::style("☁").bold(),
-            console::style(&tunnel_url).green()
+            console::style(tunnel_url).green()
         );
         if !config.no_qr {
-            crate::qr::print_qr(&tunnel_url);
+            crate::qr::print_qr(tunnel_url);
         }
         eprintln!();
     }
 
-    if !config.no_qr && tunnel_service.is_none() {
+    if !config.no_qr && tunnel_url.is_none() {
         crate::qr::print_qr(&url);
     }
 
@@ -446,48 +961,23 @@ pub async fn start(
     );
     eprintln!();
 
-    let listener =
-        tokio::net::TcpListener::bind(format!("{}:{}", config.bind, config.port)).await?;
-
-    let shutdown_signal = shutdown.clone();
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
+    let result = serve_app(
+        app,
+        &config.bind,
+        config.port,
+        tls.as_ref(),
+        tunnel_service,
+        shutdown,
+        send_drain(&state, config.drain_timeout, true),
     )
-    .with_graceful_shutdown(async move {
-        tokio::select! {
-            _ = shutdown_signal.notified() => {},
-            _ = tokio::signal::ctrl_c() => {
-                eprintln!("\n {} Shutting down...", console::style("🛑").bold());
-            }
-        }
-    })
-    .await?;
-
-    Ok(())
+    .await;
+    state.events.finish(EVENT_SETTLE).await;
+    result
 }
 
-// ===============================================================================
-// RECEIVE MODE
-// ===============================================================================
-
-pub async fn start_receive(
-    config: ReceiveConfig,
-    tor_service: Option<&crate::tor::TorHiddenService>,
-    tunnel_service: Option<&crate::tunnel::CloudflareTunnel>,
-) -> anyhow::Result<()> {
-    let shutdown = Arc::new(Notify::new());
-    let key = crypto::EncryptionKey::generate();
-
-    std::fs::create_dir_all(&config.output_dir)?;
-
-    let state = Arc::new(ReceiveState {
-        key: crypto::EncryptionKey(key.0),
-        output_dir: config.output_dir.clone(),
-        shutdown: shutdown.clone(),
-        received: std::sync::atomic::AtomicBool::new(false),
-    });
-
+/// Router for send mode: rate-limited drop routes plus static assets.
+/// Serve it with `into_make_service_with_connect_info::<SocketAddr>()`.
+pub fn send_router(state: Arc<AppState>) -> Router {
     let governor_conf = Arc::new(
         GovernorConfigBuilder::default()
             .per_second(2)
@@ -497,85 +987,1024 @@ pub async fn start_receive(
     );
 
     let rate_limited = Router::new()
-        .route("/api/upload", post(receive_upload))
-        .route("/ws/upload", get(ws_upload_handler))
-        .layer(GovernorLayer::new(governor_conf));
-
-    let app = rate_limited
-        .route("/", get(serve_upload_page))
-        .route("/assets/{*path}", get(serve_web_asset_receive))
+        .route("/d/{id}", get(serve_download_page))
+        .route("/api/blob/{id}", get(serve_blob))
+        .route("/api/chunks/{id}", get(serve_chunks))
+        .route("/api/chunk/{id}/{idx}", get(serve_chunk))
+        .route("/api/meta/{id}", get(serve_meta))
+        .route("/ws/blob/{id}", get(ws_blob_handler))
+        .route("/api/receipt/{id}", post(receive_receipt))
+        .layer(GovernorLayer::new(governor_conf))
+        .layer(middleware::from_fn_with_state(
+            state.metrics.clone(),
+            crate::metrics::count_rate_limited,
+        ));
+
+    rate_limited
+        .route("/assets/{*path}", get(serve_web_asset))
+        .route("/download-worker.js", get(serve_root_worker))
         .route("/wasm/{*path}", get(serve_wasm_asset))
         .route("/favicon.ico", get(serve_favicon))
         .layer(middleware::from_fn(security_headers))
-        .with_state(state.clone());
-
-    let key_fragment = key.to_url_safe();
-    let local_ip = local_ip_address::local_ip().unwrap_or("127.0.0.1".parse().unwrap());
-    let url = format!("http://{}:{}/#{}", local_ip, config.port, key_fragment);
-    let localhost_url = format!("http://localhost:{}/#{}", config.port, key_fragment);
-
-    print_receive_banner(&url, &config.output_dir);
+        .with_state(state)
+}
 
-    if let Some(tor) = tor_service {
-        let onion_url = tor.onion_url("/", &key_fragment);
-        eprintln!(
-            " {} Tor: {}",
-            console::style("🧅").bold(),
-            console::style(&onion_url).green()
-        );
-        eprintln!();
+/// Lowercase hex SHA-256 of a drop's ciphertext, in memory or on disk
+fn ciphertext_sha256(
+    encrypted_path: Option<&std::path::Path>,
+    ciphertext: Option<&[u8]>,
+) -> anyhow::Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    match (encrypted_path, ciphertext) {
+        (_, Some(ct)) => hasher.update(ct),
+        (Some(path), None) => {
+            let mut file = std::fs::File::open(path)?;
+            let mut buf = vec![0u8; crypto::CHUNK_SIZE];
+            loop {
+                let n = std::io::Read::read(&mut file, &mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+            }
+        }
+        (None, None) => anyhow::bail!("Drop has no ciphertext"),
     }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
 
-    if let Some(tun) = tunnel_service {
-        let tunnel_url = tun.tunnel_url("/", &key_fragment);
-        eprintln!(
-            " {} Tunnel: {}",
-            console::style("☁").bold(),
-            console::style(&tunnel_url).green()
-        );
-        if !config.no_qr {
-            crate::qr::print_qr(&tunnel_url);
+/// Read the container header at the start of the ciphertext
+fn read_header(
+    encrypted_path: Option<&std::path::Path>,
+    ciphertext: Option<&[u8]>,
+) -> anyhow::Result<crypto::EncryptedHeader> {
+    match (encrypted_path, ciphertext) {
+        (Some(path), _) => {
+            use std::io::Read;
+            let mut buf = Vec::with_capacity(crypto::EncryptedHeader::SIZED);
+            std::fs::File::open(path)?
+                .take(crypto::EncryptedHeader::SIZED as u64)
+                .read_to_end(&mut buf)?;
+            crypto::EncryptedHeader::from_bytes(&buf)
         }
-        eprintln!();
+        (None, Some(data)) => crypto::EncryptedHeader::from_bytes(data),
+        _ => anyhow::bail!("No encrypted data available"),
     }
+}
 
-    if !config.no_qr && tunnel_service.is_none() {
-        crate::qr::print_qr(&url);
-    }
+/// Read the container header at the file's current position, leaving it
+/// just past the header
+async fn read_header_async(file: &mut tokio::fs::File) -> anyhow::Result<crypto::EncryptedHeader> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+    let mut buf = Vec::with_capacity(crypto::EncryptedHeader::SIZED);
+    (&mut *file)
+        .take(crypto::EncryptedHeader::SIZED as u64)
+        .read_to_end(&mut buf)
+        .await?;
+    let header = crypto::EncryptedHeader::from_bytes(&buf)?;
+    let overread = buf.len() - header.encoded_len();
+    file.seek(std::io::SeekFrom::Current(-(overread as i64)))
+        .await?;
+    Ok(header)
+}
 
-    eprintln!(
-        " {} Also available at: {}",
-        console::style("ℹ").blue(),
-        console::style(&localhost_url).dim()
-    );
-    eprintln!(
-        " {} Waiting for upload... (Ctrl+C to abort)",
-        console::style("⏳").dim()
-    );
-    eprintln!();
+/// A payload in the standard container: in memory up to `DISK_THRESHOLD`;
+/// past it, a temp file holding just the header so far — the chunks
+/// follow in [`spawn_sealing`] while the drop is already served
+struct Encrypted {
+    path: Option<std::path::PathBuf>,
+    ciphertext: Option<Vec<u8>>,
+    size: u64,
+    /// Of the plaintext, taken on the way through (None while growing)
+    sha256: Option<[u8; 32]>,
+    growing: Option<Growing>,
+}
 
-    let listener =
-        tokio::net::TcpListener::bind(format!("{}:{}", config.bind, config.port)).await?;
+/// Encryption still to run for a drop that is served while it seals
+struct Growing {
+    file: std::fs::File,
+    size: Option<u64>, // Plaintext bytes to seal (None = left open)
+    payload: Payload,
+}
 
-    let shutdown_signal = shutdown.clone();
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .with_graceful_shutdown(async move {
-        tokio::select! {
-            _ = shutdown_signal.notified() => {},
-            _ = tokio::signal::ctrl_c() => {
-                eprintln!("\n {} Shutting down...", console::style("🛑").bold());
-            }
-        }
-    })
-    .await?;
+/// What sealing a payload yields besides the ciphertext
+struct SealedPayload {
+    size: u64,        // Plaintext bytes sealed
+    sha256: [u8; 32], // Of the real payload
+    receipts: Vec<crate::receipt::Expected>,
+    digest: String, // For `/api/meta` (crypto::seal_digest, seal_slot_digests)
+}
 
-    Ok(())
+/// What a [`Growing`] drop seals into its file
+enum Payload {
+    /// A standard container, header already written
+    Container {
+        container: crypto::GrowingContainer,
+        reader: Box<dyn std::io::Read + Send>,
+    },
+    /// A folder archived as it is sealed, behind an open-size header
+    Archive {
+        container: crypto::GrowingContainer,
+        folder: std::path::PathBuf,
+    },
+    /// Both slots of a password drop, nothing written yet
+    Deniable {
+        real: SlotSource,
+        decoy: Option<(SlotSource, crypto::EncryptionKey)>,
+        layout: crypto::DeniableLayout,
+    },
 }
 
-fn print_receive_banner(url: &str, output_dir: &std::path::Path) {
+impl Growing {
+    /// Progress tracking that maps sealed plaintext onto the file
+    fn sealing(&self) -> crate::store::Sealing {
+        match &self.payload {
+            Payload::Container { container, .. } => {
+                crate::store::Sealing::new(container.header().chunk_size)
+            }
+            Payload::Archive { container, .. } => {
+                crate::store::Sealing::open(container.header().chunk_size)
+            }
+            Payload::Deniable { layout, .. } => crate::store::Sealing::deniable(*layout),
+        }
+    }
+
+    /// Seal everything into the file. Blocking. `progress` gets the
+    /// plaintext bytes sealed so far; a folder's archiving shows on `bar`.
+    fn seal(
+        self,
+        key: &crypto::EncryptionKey,
+        bar: &indicatif::ProgressBar,
+        progress: impl Fn(u64),
+    ) -> anyhow::Result<SealedPayload> {
+        let Growing {
+            mut file, payload, ..
+        } = self;
+        let sealed = |sha256: [u8; 32], header: crypto::EncryptedHeader| {
+            Ok(SealedPayload {
+                size: header.original_size,
+                sha256,
+                receipts: vec![crate::receipt::Expected::new(&key.0, sha256, false)],
+                digest: crypto::seal_digest(key, &sha256)?,
+            })
+        };
+        match payload {
+            Payload::Container { container, reader } => {
+                let mut reader = crypto::HashingReader::new(reader);
+                let header = container.seal(&mut reader, key, &mut file, progress)?;
+                sealed(reader.finish(), header)
+            }
+            Payload::Archive { container, folder } => {
+                archive_bar_style(bar);
+                let archive = crate::archive::stream_folder(&folder, bar.clone())?;
+                let mut reader = crypto::HashingReader::new(archive);
+                let header = container.seal_open(&mut reader, key, &mut file, progress)?;
+                sealed(reader.finish(), header)
+            }
+            Payload::Deniable {
+                real,
+                decoy,
+                layout,
+            } => seal_slots(&mut file, real, decoy, key, layout.chunk_size, progress),
+        }
+    }
+}
+
+/// Encrypt a small payload in memory on the blocking pool, or start a
+/// large one's container so the link can go out before it is sealed
+async fn encrypt_payload(
+    reader: Box<dyn std::io::Read + Send>,
+    key: &crypto::EncryptionKey,
+    size: u64,
+    chunk_size: usize,
+    bar: indicatif::ProgressBar,
+) -> anyhow::Result<Encrypted> {
+    if size > DISK_THRESHOLD {
+        let temp_file = tempfile::NamedTempFile::new()?;
+        let (mut file, path) = temp_file
+            .keep()
+            .map_err(|e| anyhow::anyhow!("Failed to persist temp file: {}", e))?;
+        let container = crypto::GrowingContainer::start(&mut file, size, chunk_size)?;
+        return Ok(Encrypted {
+            path: Some(path),
+            ciphertext: None,
+            size: crypto::encrypted_len_with(size, chunk_size),
+            sha256: None,
+            growing: Some(Growing {
+                file,
+                size: Some(size),
+                payload: Payload::Container { container, reader },
+            }),
+        });
+    }
+    let key = key.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut reader = crypto::HashingReader::new(reader);
+        let pipeline = crypto::Pipeline {
+            chunk_size,
+            ..Default::default()
+        };
+        let ct =
+            pipeline.encrypt_to_vec(&mut reader, &key, size, |bytes| bar.set_position(bytes))?;
+        Ok(Encrypted {
+            path: None,
+            size: ct.len() as u64,
+            ciphertext: Some(ct),
+            sha256: Some(reader.finish()),
+            growing: None,
+        })
+    })
+    .await?
+}
+
+/// Encrypt a growing drop's chunks on the blocking pool while it is
+/// served. The last chunk is held back until the digest and receipt are
+/// in place, so no recipient can finish before them (a drop whose size is
+/// left open holds back its latest chunk itself). If encryption fails
+/// the drop is taken down.
+fn spawn_sealing(
+    state: Arc<AppState>,
+    drop: Arc<crate::store::Drop>,
+    key: crypto::EncryptionKey,
+    growing: Growing,
+    banner: bool,
+    hash_ciphertext: bool,
+) {
+    let bar = match banner {
+        true => state.transfers.encrypting(growing.size.unwrap_or(0)),
+        false => indicatif::ProgressBar::hidden(),
+    };
+    tokio::spawn(async move {
+        let (sealer, seal_key, seal_bar) = (drop.clone(), key.clone(), bar.clone());
+        let size = growing.size;
+        let sealed = tokio::task::spawn_blocking(move || {
+            growing.seal(&seal_key, &seal_bar, |bytes| {
+                // An archive's bar counts files, not bytes
+                if size.is_some() {
+                    seal_bar.set_position(bytes);
+                }
+                if let Some(sealing) = &sealer.sealing
+                    && size.is_none_or(|size| bytes < size)
+                {
+                    sealing.advance(bytes);
+                }
+            })
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|sealed| sealed);
+        bar.finish_and_clear();
+        let Some(sealing) = &drop.sealing else {
+            return;
+        };
+
+        let SealedPayload {
+            size: sealed_size,
+            sha256,
+            receipts,
+            digest,
+        } = match sealed {
+            Ok(sealed) => sealed,
+            Err(e) => {
+                sealing.fail();
+                state.transfers.println(format!(
+                    " {} Encryption failed, drop taken down: {}",
+                    console::style("⚠").yellow(),
+                    e
+                ));
+                state.store.remove(&drop.id);
+                if state.store.is_empty() {
+                    state.shutdown.notify_one();
+                }
+                return;
+            }
+        };
+        state.receipts.expect(&drop.id, receipts);
+        sealing.finish(sealed_size, Some(digest));
+        if size.is_none() {
+            state.transfers.sized(&drop.id, drop.encrypted_size());
+        }
+
+        let ciphertext_sha256 = match hash_ciphertext {
+            true => ciphertext_sha256(drop.encrypted_path.as_deref(), None).ok(),
+            false => None,
+        };
+        if banner {
+            // A folder's size was left open until now
+            let archived = match size {
+                None => format!("{}, ", bytesize::ByteSize::b(sealed_size)),
+                Some(_) => String::new(),
+            };
+            state.transfers.println(format!(
+                " {} Encryption finished — {}SHA-256 {}",
+                console::style("🔒").bold(),
+                archived,
+                console::style(crypto::to_hex(&sha256)).dim()
+            ));
+        }
+        state.events.emit(Event::Sealed {
+            drop_id: drop.id.clone(),
+            size: drop.file_size(),
+            encrypted_size: drop.encrypted_size(),
+            sha256: ciphertext_sha256,
+        });
+    });
+}
+
+/// One payload of a password drop, opened but not yet sealed
+struct SlotSource {
+    reader: Box<dyn std::io::Read + Send>,
+    len: u64,
+    filename: String,
+}
+
+impl SlotSource {
+    fn input<'a>(&'a mut self, key: &'a crypto::EncryptionKey) -> crypto::SlotInput<'a> {
+        crypto::SlotInput {
+            reader: &mut self.reader,
+            len: self.len,
+            filename: self.filename.clone(),
+            key,
+        }
+    }
+}
+
+/// Open `file` and the optional decoy (path, password) for a deniable
+/// container. Blocking: archives folders and derives the decoy key, so
+/// run it on the blocking pool.
+fn open_deniable(
+    file: &std::path::Path,
+    decoy: Option<(std::path::PathBuf, String)>,
+    salt: &[u8; 16],
+) -> anyhow::Result<(SlotSource, Option<(SlotSource, crypto::EncryptionKey)>)> {
+    let open = |path: &std::path::Path| -> anyhow::Result<SlotSource> {
+        let (reader, filename, len) = open_payload(path)?;
+        Ok(SlotSource {
+            reader,
+            len,
+            filename,
+        })
+    };
+    let decoy = match decoy {
+        Some((path, pw)) => Some((
+            open(&path)?,
+            crypto::EncryptionKey::from_password(&pw, salt)?,
+        )),
+        None => None,
+    };
+    Ok((open(file)?, decoy))
+}
+
+/// Seal both slots of a password drop into `writer` in `chunk_size`
+/// chunks, hashing each payload on the way through
+fn seal_slots(
+    writer: &mut impl std::io::Write,
+    real: SlotSource,
+    decoy: Option<(SlotSource, crypto::EncryptionKey)>,
+    key: &crypto::EncryptionKey,
+    chunk_size: usize,
+    progress: impl Fn(u64),
+) -> anyhow::Result<SealedPayload> {
+    let mut real_reader = crypto::HashingReader::new(real.reader);
+    let mut decoy = decoy.map(|(source, decoy_key)| {
+        let reader = crypto::HashingReader::new(source.reader);
+        (reader, source.len, source.filename, decoy_key)
+    });
+
+    let layout = crypto::encrypt_deniable(
+        writer,
+        crypto::SlotInput {
+            reader: &mut real_reader,
+            len: real.len,
+            filename: real.filename,
+            key,
+        },
+        decoy
+            .as_mut()
+            .map(|(reader, len, filename, decoy_key)| crypto::SlotInput {
+                reader,
+                len: *len,
+                filename: filename.clone(),
+                key: decoy_key,
+            }),
+        chunk_size,
+        progress,
+    )?;
+
+    let sha256 = real_reader.finish();
+    let decoy = decoy.map(|(reader, _, _, decoy_key)| (decoy_key, reader.finish()));
+    let mut receipts = vec![crate::receipt::Expected::new(&key.0, sha256, false)];
+    if let Some((decoy_key, decoy_sha256)) = &decoy {
+        receipts.push(crate::receipt::Expected::new(
+            &decoy_key.0,
+            *decoy_sha256,
+            true,
+        ));
+    }
+    let digest = crypto::seal_slot_digests(
+        (key, &sha256),
+        decoy
+            .as_ref()
+            .map(|(decoy_key, sha256)| (decoy_key, sha256)),
+    )?;
+    Ok(SealedPayload {
+        size: layout.plaintext_len(),
+        sha256,
+        receipts,
+        digest,
+    })
+}
+
+/// Seal a password drop that fits in memory. Blocking.
+fn seal_deniable(
+    real: SlotSource,
+    decoy: Option<(SlotSource, crypto::EncryptionKey)>,
+    key: &crypto::EncryptionKey,
+    layout: crypto::DeniableLayout,
+) -> anyhow::Result<(Vec<u8>, SealedPayload)> {
+    let pm = progress::ProgressManager::new();
+    let encrypt_bar = pm.create_encrypt_bar(layout.plaintext_len());
+
+    let mut ciphertext = Vec::new();
+    let sealed = seal_slots(
+        &mut ciphertext,
+        real,
+        decoy,
+        key,
+        layout.chunk_size,
+        |bytes| encrypt_bar.set_position(bytes),
+    )?;
+    encrypt_bar.finish_and_clear();
+    Ok((ciphertext, sealed))
+}
+
+/// Open a file, or archive a folder into a temp file, as a plaintext reader.
+/// Returns (reader, display filename, plaintext length).
+fn open_payload(
+    path: &std::path::Path,
+) -> anyhow::Result<(Box<dyn std::io::Read + Send>, String, u64)> {
+    if path.is_dir() {
+        let pm = progress::ProgressManager::new();
+        let archive_bar = pm.create_encrypt_bar(0);
+        archive_bar_style(&archive_bar);
+        let (archive, archive_name) = crate::archive::compress_folder(path, &archive_bar)?;
+        archive_bar.finish_and_clear();
+        let len = archive.metadata()?.len();
+        Ok((
+            Box::new(archive),
+            crypto::safe_filename(&archive_name, true),
+            len,
+        ))
+    } else {
+        let file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok((
+            Box::new(file),
+            crypto::safe_filename_from_path(path, false),
+            len,
+        ))
+    }
+}
+
+/// Show a folder's archiving on `bar`, in files
+fn archive_bar_style(bar: &indicatif::ProgressBar) {
+    bar.set_style(
+        indicatif::ProgressStyle::with_template(
+            " {spinner:.green} Archiving [{bar:40.yellow/dark_gray}] {pos}/{len} files",
+        )
+        .unwrap()
+        .progress_chars("━╸─"),
+    );
+}
+
+/// Send mode drains downloads, removed drops' included
+fn send_drain(state: &Arc<AppState>, timeout: std::time::Duration, ask: bool) -> Drain {
+    let store = state.store.clone();
+    Drain::new(move || store.in_flight(), timeout, ask)
+}
+
+fn receive_drain(state: &Arc<ReceiveState>, timeout: std::time::Duration, ask: bool) -> Drain {
+    let state = state.clone();
+    Drain::new(
+        // Chunked uploads count until finished, even between chunks
+        move || {
+            state.uploads.load(std::sync::atomic::Ordering::SeqCst) + state.partials.unfinished()
+        },
+        timeout,
+        ask,
+    )
+}
+
+/// What shutdown waits for before the listener closes: transfers still
+/// running, for at most `timeout`
+struct Drain {
+    in_flight: Box<dyn Fn() -> usize + Send>,
+    timeout: std::time::Duration,
+    /// On Ctrl+C, ask whether to wait (interactive terminal sessions only)
+    ask: bool,
+}
+
+impl Drain {
+    fn new(
+        in_flight: impl Fn() -> usize + Send + 'static,
+        timeout: std::time::Duration,
+        ask: bool,
+    ) -> Self {
+        Self {
+            in_flight: Box::new(in_flight),
+            timeout,
+            ask: ask && std::io::IsTerminal::is_terminal(&std::io::stdin()),
+        }
+    }
+
+    /// Keep serving until in-flight transfers finish, the timeout passes
+    /// or Ctrl+C is pressed (again)
+    async fn run(self, interrupted: bool) {
+        let active = (self.in_flight)();
+        if active == 0 || self.timeout.is_zero() {
+            return;
+        }
+        if interrupted && self.ask && !ask_to_wait(active).await {
+            return;
+        }
+
+        eprintln!(
+            " {} Waiting for {} transfer(s) to finish (up to {}s, Ctrl+C to stop now)",
+            console::style("⏳").dim(),
+            active,
+            self.timeout.as_secs()
+        );
+        let deadline = tokio::time::sleep(self.timeout);
+        tokio::pin!(deadline);
+        let mut tick = tokio::time::interval(std::time::Duration::from_millis(250));
+        loop {
+            tokio::select! {
+                _ = &mut deadline => {
+                    eprintln!(
+                        " {} Drain timeout — stopping with {} transfer(s) still running",
+                        console::style("⚠").yellow(),
+                        (self.in_flight)()
+                    );
+                    return;
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    eprintln!("\n {} Stopping now", console::style("🛑").bold());
+                    return;
+                }
+                _ = tick.tick() => {
+                    if (self.in_flight)() == 0 {
+                        eprintln!(
+                            " {} All transfers finished",
+                            console::style("✓").green()
+                        );
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Ask on the terminal whether to let running transfers finish. A second
+/// Ctrl+C answers no.
+async fn ask_to_wait(active: usize) -> bool {
+    eprint!(
+        " {} {} transfer(s) still running. Wait for them to finish? [Y/n] ",
+        console::style("?").cyan().bold(),
+        active
+    );
+    // A plain thread, not spawn_blocking: an unanswered read must not hold
+    // up the runtime's exit
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        let _ = tx.send(std::io::stdin().read_line(&mut line).map(|_| line));
+    });
+    tokio::select! {
+        answer = rx => match answer {
+            Ok(Ok(line)) => !line.trim().eq_ignore_ascii_case("n"),
+            _ => false,
+        },
+        _ = tokio::signal::ctrl_c() => {
+            eprintln!();
+            false
+        }
+    }
+}
+
+/// Bind and serve the router over plain HTTP or, with `--tls`, HTTPS/WSS —
+/// and on the tunnel's origin listener when there is one. Returns once
+/// `shutdown` is notified or Ctrl+C is pressed and `drain` has let running
+/// transfers finish.
+async fn serve_app(
+    app: Router,
+    bind: &str,
+    port: u16,
+    tls: Option<&crate::tls::TlsIdentity>,
+    tunnel: Option<&crate::tunnel::CloudflareTunnel>,
+    shutdown: Arc<Notify>,
+    drain: Drain,
+) -> anyhow::Result<()> {
+    let shutdown_signal = async move {
+        let interrupted = tokio::select! {
+            _ = shutdown.notified() => false,
+            _ = tokio::signal::ctrl_c() => {
+                eprintln!("\n {} Shutting down...", console::style("🛑").bold());
+                true
+            }
+        };
+        drain.run(interrupted).await;
+    };
+    let origin = tunnel.map(|tun| tun.origin()).transpose()?;
+    let tunnel_app = app.clone().layer(middleware::from_fn(via_tunnel));
+
+    let Some(tls) = tls else {
+        let (stop, stopped) = tokio::sync::watch::channel(false);
+        tokio::spawn(async move {
+            shutdown_signal.await;
+            let _ = stop.send(true);
+        });
+        let until_stopped = |mut stopped: tokio::sync::watch::Receiver<bool>| async move {
+            let _ = stopped.wait_for(|stop| *stop).await;
+        };
+
+        let listener = tokio::net::TcpListener::bind(format!("{}:{}", bind, port)).await?;
+        let main = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(until_stopped(stopped.clone()))
+        .into_future();
+        let tunneled = async {
+            let Some(origin) = origin else { return Ok(()) };
+            axum::serve(
+                tokio::net::TcpListener::from_std(origin)?,
+                tunnel_app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(until_stopped(stopped))
+            .await
+        };
+        tokio::try_join!(main, tunneled)?;
+        return Ok(());
+    };
+
+    let addr: SocketAddr = tokio::net::lookup_host(format!("{}:{}", bind, port))
+        .await?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Could not resolve bind address {}", bind))?;
+    let rustls_config = tls.rustls_config().await?;
+
+    let handle = axum_server::Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown_signal.await;
+        shutdown_handle.graceful_shutdown(None);
+    });
+
+    let main = axum_server::bind_rustls(addr, rustls_config.clone())
+        .handle(handle.clone())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>());
+    let tunneled = async {
+        let Some(origin) = origin else { return Ok(()) };
+        axum_server::from_tcp_rustls(origin, rustls_config)?
+            .handle(handle)
+            .serve(tunnel_app.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+    };
+    tokio::try_join!(main, tunneled)?;
+    Ok(())
+}
+
+/// Requests that came in on the tunnel's origin listener: cloudflared's
+/// `CF-Connecting-IP` becomes the peer address, so handlers see the real
+/// client without loopback being trusted on the main port
+async fn via_tunnel(mut request: axum::extract::Request, next: middleware::Next) -> Response {
+    if let Some(ConnectInfo(peer)) = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .copied()
+    {
+        let client = crate::proxy::TrustedProxies::default()
+            .with_loopback()
+            .client_ip(&peer, request.headers());
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(client, peer.port())));
+    }
+    // Resolved; nothing further down may read them again
+    request.headers_mut().remove("CF-Connecting-IP");
+    request.headers_mut().remove("X-Forwarded-For");
+    next.run(request).await
+}
+
+/// Generate the ephemeral `--tls` certificate for the LAN address and localhost
+fn make_tls_identity(
+    enabled: bool,
+    local_ip: std::net::IpAddr,
+) -> anyhow::Result<Option<crate::tls::TlsIdentity>> {
+    if !enabled {
+        return Ok(None);
+    }
+    let hosts = vec![
+        local_ip.to_string(),
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+    ];
+    Ok(Some(crate::tls::TlsIdentity::generate(hosts)?))
+}
+
+/// Apply the scheme and certificate pin for a locally served link
+fn local_link(url: String, tls: Option<&crate::tls::TlsIdentity>) -> String {
+    match tls {
+        Some(id) => crate::tls::pin_url(&url.replacen("http://", "https://", 1), &id.fingerprint),
+        None => url,
+    }
+}
+
+fn print_tls_fingerprint(tls: Option<&crate::tls::TlsIdentity>) {
+    let Some(id) = tls else { return };
+    eprintln!(
+        " {} TLS certificate (SHA-256): {}",
+        console::style("🔐").bold(),
+        console::style(id.display_fingerprint()).cyan()
+    );
+    eprintln!(
+        " {} Self-signed — the browser will warn. Check that the fingerprint it shows matches the one above before continuing.",
+        console::style("⚠").yellow()
+    );
+    eprintln!();
+}
+
+fn print_access_policy(policy: &crate::policy::AccessPolicy) {
+    let rules = policy.describe();
+    if rules.is_empty() {
+        return;
+    }
+    for rule in rules {
+        eprintln!(" {} {}", console::style("🛡").cyan(), rule);
+    }
+    eprintln!();
+}
+
+// ===============================================================================
+// RECEIVE MODE
+// ===============================================================================
+
+pub async fn start_receive(
+    config: ReceiveConfig,
+    tor_service: Option<&crate::tor::TorHiddenService>,
+    tunnel_service: Option<&crate::tunnel::CloudflareTunnel>,
+) -> anyhow::Result<()> {
+    let shutdown = Arc::new(Notify::new());
+    let key = crypto::EncryptionKey::generate();
+
+    if !config.to_stdout() {
+        std::fs::create_dir_all(&config.output_dir)?;
+        // A floor the filesystem can't be asked about would never hold
+        if config.limits.min_free_disk.is_some()
+            && crate::receive::free_space(&config.output_dir).is_none()
+        {
+            anyhow::bail!(
+                "--min-free-disk: can't read the free space of {}",
+                config.output_dir.display()
+            );
+        }
+    }
+
+    let quarantine = match &config.scanner {
+        Some(scanner) => {
+            scanner.check().await?;
+            Some(crate::scan::Quarantine::new(
+                config.quarantine_path(),
+                scanner.clone(),
+            )?)
+        }
+        None => None,
+    };
+
+    let metrics = crate::metrics::Metrics::new();
+    let state = Arc::new(ReceiveState {
+        key: crypto::EncryptionKey(key.0),
+        output_dir: config.output_dir.clone(),
+        shutdown: shutdown.clone(),
+        session: crate::receive::Session::new(
+            config.limits.clone(),
+            if config.to_stdout() {
+                "stdout".into()
+            } else {
+                config.output_dir.clone()
+            },
+        ),
+        on_conflict: config.on_conflict,
+        allow_dotfiles: config.allow_dotfiles,
+        quarantine,
+        vault: config.vault.clone(),
+        to_stdout: config.to_stdout(),
+        partials: Default::default(),
+        uploads: Default::default(),
+        events: event_bus(
+            config.json,
+            &config.hooks,
+            config.audit_log.as_deref(),
+            &metrics,
+        )?,
+        metrics,
+    });
+
+    // The link dies at expiry whatever else is going on. The deadline is
+    // fixed here, once: the timer and the `Ready` event both use it.
+    let expiring = state.clone();
+    let expires_at = chrono::Utc::now() + config.expiry_duration;
+    tokio::spawn(async move {
+        let left = (expires_at - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default();
+        tokio::time::sleep(left).await;
+        if !expiring.session.is_closed() {
+            eprintln!("\n {} Receive link expired.", console::style("⏰").bold());
+        }
+        close_session(&expiring, "expired");
+    });
+
+    if let Some(addr) = &config.metrics {
+        let waiting = state.clone();
+        crate::metrics::serve(addr, state.metrics.clone(), move || {
+            // The upload page is the one "drop" while the session is open
+            usize::from(!waiting.session.is_closed())
+        })
+        .await?;
+    }
+
+    let governor_conf = Arc::new(
+        GovernorConfigBuilder::default()
+            .per_second(2)
+            .burst_size(5)
+            .finish()
+            .unwrap(),
+    );
+
+    let rate_limited = Router::new()
+        .route("/api/upload", post(receive_upload))
+        .route("/api/upload/done", post(receive_upload_done))
+        .route("/api/upload/start", post(start_chunked_upload))
+        .route("/ws/upload", get(ws_upload_handler))
+        // Bodies past --max-size (encrypted) are cut off with a 413, by
+        // Content-Length up front or as they stream in
+        .layer(DefaultBodyLimit::max(
+            usize::try_from(config.limits.max_encrypted_size()).unwrap_or(usize::MAX),
+        ))
+        .layer(GovernorLayer::new(governor_conf))
+        .layer(middleware::from_fn_with_state(
+            state.metrics.clone(),
+            crate::metrics::count_rate_limited,
+        ));
+
+    // Chunks of an upload already started get a limit of their own: a
+    // large file is hundreds of requests, but not thousands a second
+    let chunk_governor_conf = Arc::new(
+        GovernorConfigBuilder::default()
+            .per_millisecond(20)
+            .burst_size(50)
+            .finish()
+            .unwrap(),
+    );
+    let chunks = Router::new()
+        .route(
+            "/api/upload/{id}",
+            get(chunked_upload_status).put(receive_upload_chunk),
+        )
+        .route("/api/upload/{id}/finish", post(finish_chunked_upload))
+        .layer(GovernorLayer::new(chunk_governor_conf))
+        .layer(middleware::from_fn_with_state(
+            state.metrics.clone(),
+            crate::metrics::count_rate_limited,
+        ));
+
+    spawn_partial_reaper(state.clone());
+
+    let app = rate_limited
+        .merge(chunks)
+        .route("/", get(serve_upload_page))
+        .route("/assets/{*path}", get(serve_web_asset_receive))
+        .route("/wasm/{*path}", get(serve_wasm_asset))
+        .route("/favicon.ico", get(serve_favicon))
+        .layer(middleware::from_fn(security_headers))
+        .with_state(state.clone());
+
+    let key_fragment = key.to_url_safe();
+    let local_ip = local_ip_address::local_ip().unwrap_or("127.0.0.1".parse().unwrap());
+    let tls = make_tls_identity(config.tls, local_ip)?;
+    let url = local_link(
+        format!("http://{}:{}/#{}", local_ip, config.port, key_fragment),
+        tls.as_ref(),
+    );
+    let localhost_url = local_link(
+        format!("http://localhost:{}/#{}", config.port, key_fragment),
+        tls.as_ref(),
+    );
+
+    if config.json {
+        state.events.emit(Event::Ready {
+            mode: "receive",
+            drop_id: None,
+            urls: crate::events::Urls {
+                lan: url,
+                localhost: localhost_url,
+                tunnel: tunnel_service.map(|tun| tun.tunnel_url("/", &key_fragment)),
+                onion: tor_service
+                    .map(|tor| local_link(tor.onion_url("/", &key_fragment), tls.as_ref())),
+            },
+            key_fragment,
+            expires_at,
+            max_downloads: None,
+        });
+        let result = serve_app(
+            app,
+            &config.bind,
+            config.port,
+            tls.as_ref(),
+            tunnel_service,
+            shutdown,
+            receive_drain(&state, config.drain_timeout, false),
+        )
+        .await;
+        close_session(&state, "interrupted");
+        state.events.finish(EVENT_SETTLE).await;
+        return result.and_then(|()| streamed(&state));
+    }
+
+    print_receive_banner(&url, &config);
+    print_tls_fingerprint(tls.as_ref());
+
+    if let Some(tor) = tor_service {
+        let onion_url = local_link(tor.onion_url("/", &key_fragment), tls.as_ref());
+        eprintln!(
+            " {} Tor: {}",
+            console::style("🧅").bold(),
+            console::style(&onion_url).green()
+        );
+        eprintln!();
+    }
+
+    if let Some(tun) = tunnel_service {
+        let tunnel_url = tun.tunnel_url("/", &key_fragment);
+        eprintln!(
+            " {} Tunnel: {}",
+            console::style("☁").bold(),
+            console::style(&tunnel_url).green()
+        );
+        if !config.no_qr {
+            crate::qr::print_qr(&tunnel_url);
+        }
+        eprintln!();
+    }
+
+    if !config.no_qr && tunnel_service.is_none() {
+        crate::qr::print_qr(&url);
+    }
+
+    eprintln!(
+        " {} Also available at: {}",
+        console::style("ℹ").blue(),
+        console::style(&localhost_url).dim()
+    );
+    eprintln!(
+        " {} Waiting for upload... (Ctrl+C to abort)",
+        console::style("⏳").dim()
+    );
+    eprintln!();
+
+    let result = serve_app(
+        app,
+        &config.bind,
+        config.port,
+        tls.as_ref(),
+        tunnel_service,
+        shutdown,
+        receive_drain(&state, config.drain_timeout, true),
+    )
+    .await;
+    close_session(&state, "interrupted");
+    state.events.finish(EVENT_SETTLE).await;
+    result.and_then(|()| streamed(&state))
+}
+
+/// With `-o -`, a run that ends without the whole upload on stdout is a
+/// failure — whatever reads the pipe got nothing, or a truncated stream
+fn streamed(state: &ReceiveState) -> anyhow::Result<()> {
+    if state.to_stdout && state.session.saved().is_empty() {
+        anyhow::bail!("No complete upload was written to stdout");
+    }
+    Ok(())
+}
+
+fn print_receive_banner(url: &str, config: &ReceiveConfig) {
+    let limits = &config.limits;
     eprintln!();
     eprintln!(
         "{}",
@@ -627,20 +2056,67 @@ fn print_receive_banner(url: &str, output_dir: &std::path::Path) {
         console::style(url).green()
     );
     eprintln!(" │");
+    eprintln!(
+        " │ ├─ {} Receive (phone → PC)",
+        console::style("Mode").dim()
+    );
+    if config.to_stdout() {
+        eprintln!(
+            " │ ├─ {} stdout (one upload, decrypted as it arrives)",
+            console::style("Save to").dim()
+        );
+    } else {
+        eprintln!(
+            " │ ├─ {} {}",
+            console::style("Save to").dim(),
+            config.output_dir.display()
+        );
+    }
     eprintln!(
         " │ ├─ {} {}",
-        console::style("Mode").dim(),
-        "Receive (phone → PC)"
+        console::style("Expires").dim(),
+        console::style(&config.expire).yellow()
     );
+    let mut accept = vec![
+        if limits.until_expiry {
+            "until expiry".to_string()
+        } else {
+            "one batch".to_string()
+        },
+        format!("≤ {} per file", bytesize::ByteSize::b(limits.max_size)),
+    ];
+    if limits.max_files > 0 {
+        accept.push(format!("max {} files", limits.max_files));
+    }
+    if let Some(max) = limits.max_total_size {
+        accept.push(format!("max {}", bytesize::ByteSize::b(max)));
+    }
+    if let Some(min) = limits.min_free_disk {
+        accept.push(format!("keep {} free", bytesize::ByteSize::b(min)));
+    }
     eprintln!(
         " │ ├─ {} {}",
-        console::style("Save to").dim(),
-        output_dir.display()
+        console::style("Accept").dim(),
+        accept.join(" · ")
     );
+    if let Some(vault) = &config.vault {
+        eprintln!(
+            " │ ├─ {} sealed to {} (open with `ded open`)",
+            console::style("Vault").dim(),
+            vault.recipient()
+        );
+    }
+    if let Some(scanner) = &config.scanner {
+        eprintln!(
+            " │ ├─ {} {} · quarantine {}",
+            console::style("Scan").dim(),
+            scanner.describe(),
+            config.quarantine_path().display()
+        );
+    }
     eprintln!(
-        " │ └─ {} {}",
-        console::style("Crypto").dim(),
-        "XChaCha20-Poly1305"
+        " │ └─ {} XChaCha20-Poly1305",
+        console::style("Crypto").dim()
     );
     eprintln!(" └──────────────────────────────────────────────────┘");
     eprintln!();
@@ -660,158 +2136,752 @@ async fn receive_upload(
     headers: HeaderMap,
     body: axum::body::Bytes,
 ) -> Response {
-    if state.received.load(std::sync::atomic::Ordering::SeqCst) {
+    if state.session.is_closed() {
         return (
             StatusCode::GONE,
-            "Already received a file — server is shutting down",
+            crate::receive::Refused::Closed.to_string(),
         )
             .into_response();
     }
+    let upload = UploadGuard::new(&state);
 
-    let filename = headers
+    // X-Filename carries the relative path for files inside a folder
+    let relative = headers
         .get("X-Filename")
         .and_then(|v| v.to_str().ok())
         .and_then(|v| urlencoding::decode(v).ok())
-        .map(|v| v.to_string())
-        .unwrap_or_else(|| "received_file".to_string());
-
-    let safe_filename: String = filename
-        .replace("..", "")
-        .replace('/', "")
-        .replace('\\', "");
-
-    let safe_filename = if safe_filename.is_empty() {
-        "received_file".to_string()
-    } else {
-        safe_filename
-    };
+        .map(|v| crypto::safe_relative_path(&v))
+        .unwrap_or_else(|| "received_file".into());
+    // The sealed plaintext SHA-256, when the uploader sends one
+    let digest = headers.get("X-Digest").and_then(|v| v.to_str().ok());
 
     eprintln!(
         " {} Receiving encrypted upload: {} ({} bytes)",
         console::style("📥").bold(),
-        safe_filename,
+        relative.display(),
         body.len()
     );
 
-    match decrypt_uploaded_blob(&body, &state.key) {
-        Ok(plaintext) => {
-            let output_path = state.output_dir.join(&safe_filename);
-            match std::fs::write(&output_path, &plaintext) {
-                Ok(_) => {
-                    state
-                        .received
-                        .store(true, std::sync::atomic::Ordering::SeqCst);
-                    eprintln!(
-                        " {} Saved: {} ({})",
-                        console::style("✅").bold(),
-                        console::style(&safe_filename).green(),
-                        console::style(bytesize::ByteSize::b(plaintext.len() as u64).to_string())
-                            .dim()
-                    );
-                    eprintln!(
-                        " {} File saved to: {}",
-                        console::style("📁").bold(),
-                        console::style(output_path.display()).green()
-                    );
+    match accept_upload(&state, &relative, std::io::Cursor::new(body), digest, None).await {
+        Ok(accepted) => {
+            drop(upload);
+            if accepted.limit_reached {
+                close_session(&state, "limit");
+            }
+            Json(accepted.reply()).into_response()
+        }
+        Err((status, message)) => (status, message).into_response(),
+    }
+}
 
-                    let shutdown = state.shutdown.clone();
-                    tokio::spawn(async move {
-                        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                        eprintln!(
-                            "\n {} Transfer complete — self-destructing.",
-                            console::style("💥").bold()
-                        );
-                        shutdown.notify_one();
-                    });
+/// The page finished sending its batch
+async fn receive_upload_done(State(state): State<Arc<ReceiveState>>) -> Response {
+    if state.session.limits().until_expiry && !state.session.is_closed() {
+        eprintln!(
+            " {} Batch complete — still accepting uploads until the link expires",
+            console::style("⏳").dim()
+        );
+        return Json(serde_json::json!({ "status": "open" })).into_response();
+    }
+    close_session(&state, "done");
+    Json(serde_json::json!({ "status": "closed" })).into_response()
+}
 
-                    Json(serde_json::json!({
-                        "status": "ok",
-                        "saved_as": safe_filename,
-                        "size": plaintext.len()
-                    }))
-                    .into_response()
-                }
-                Err(e) => {
-                    eprintln!(
-                        " {} Failed to save file: {}",
-                        console::style("❌").bold(),
-                        e
-                    );
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Failed to save: {}", e),
-                    )
-                        .into_response()
-                }
+fn refused_status(refused: crate::receive::Refused) -> StatusCode {
+    use crate::receive::Refused;
+    match refused {
+        Refused::Closed | Refused::TooManyFiles => StatusCode::GONE,
+        Refused::FileTooLarge | Refused::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+        Refused::DiskFull => StatusCode::INSUFFICIENT_STORAGE,
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct StartUpload {
+    path: String,
+    /// Encrypted size
+    size: u64,
+    /// Plaintext SHA-256 sealed under the session key, checked on finish
+    #[serde(default)]
+    digest: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ChunkQuery {
+    offset: u64,
+}
+
+async fn start_chunked_upload(
+    State(state): State<Arc<ReceiveState>>,
+    Json(start): Json<StartUpload>,
+) -> Response {
+    let relative = crypto::safe_relative_path(&start.path);
+    if let Err(refused) = check_upload_path(&state, &relative) {
+        return refused.into_response();
+    }
+    // No point taking the bytes only to drop them
+    if already_exists(&state, &relative) {
+        print_skipped(&relative);
+        return Json(serde_json::json!({
+            "status": "skipped",
+            "saved_as": relative.display().to_string(),
+        }))
+        .into_response();
+    }
+    if let Err(refused) = state.session.reserve_announced(start.size) {
+        eprintln!(
+            " {} Refused {}: {}",
+            console::style("⚠").yellow(),
+            relative.display(),
+            refused
+        );
+        return (refused_status(refused), refused.to_string()).into_response();
+    }
+    let started = if state.to_stdout {
+        state
+            .partials
+            .start_stream(&state.key, relative.clone(), start.size, start.digest)
+    } else {
+        state
+            .partials
+            .start(
+                &state.output_dir,
+                relative.clone(),
+                start.size,
+                start.digest,
+            )
+            .map_err(Into::into)
+    };
+    if started.is_err() {
+        state.session.release(start.size);
+    }
+    match started {
+        Ok(id) => {
+            eprintln!(
+                " {} Receiving encrypted upload: {} ({})",
+                console::style("📥").bold(),
+                relative.display(),
+                bytesize::ByteSize::b(start.size)
+            );
+            (
+                StatusCode::CREATED,
+                Json(serde_json::json!({
+                    "id": id,
+                    "offset": 0,
+                    "chunk_size": crate::receive::UPLOAD_CHUNK_SIZE,
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to start upload: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+/// Where an upload is at — what the page asks after reconnecting
+async fn chunked_upload_status(
+    Path(id): Path<String>,
+    State(state): State<Arc<ReceiveState>>,
+) -> Response {
+    let Some(partial) = state.partials.get(&id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let partial = partial.lock().await;
+    Json(serde_json::json!({
+        "offset": partial.offset,
+        "size": partial.size,
+        "finished": partial.finished.is_some(),
+    }))
+    .into_response()
+}
+
+async fn receive_upload_chunk(
+    Path(id): Path<String>,
+    Query(query): Query<ChunkQuery>,
+    State(state): State<Arc<ReceiveState>>,
+    body: axum::body::Bytes,
+) -> Response {
+    use crate::receive::Append;
+
+    if state.session.is_closed() {
+        return (
+            StatusCode::GONE,
+            crate::receive::Refused::Closed.to_string(),
+        )
+            .into_response();
+    }
+    let Some(partial) = state.partials.get(&id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let _upload = UploadGuard::new(&state);
+    let mut partial = partial.lock().await;
+    match partial.append(query.offset, &body) {
+        Ok(offset) if state.to_stdout => {
+            let plaintext = std::mem::take(&mut partial.plaintext);
+            if let Err(e) = write_stdout(&plaintext).await {
+                drop(partial);
+                return stream_failed(&state, format!("Writing to stdout failed: {}", e));
             }
+            Json(serde_json::json!({ "offset": offset })).into_response()
         }
-        Err(e) => {
-            eprintln!(" {} Decryption failed: {}", console::style("❌").bold(), e);
-            (StatusCode::BAD_REQUEST, format!("Decryption failed: {}", e)).into_response()
+        Ok(offset) => Json(serde_json::json!({ "offset": offset })).into_response(),
+        Err(Append::WrongOffset(offset)) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "offset": offset })),
+        )
+            .into_response(),
+        Err(Append::TooLong) => (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Chunk runs past the announced size",
+        )
+            .into_response(),
+        Err(Append::Finished) => (StatusCode::CONFLICT, "Upload already finished").into_response(),
+        Err(Append::Io(e)) => (
+            StatusCode::INSUFFICIENT_STORAGE,
+            format!("Failed to store chunk: {}", e),
+        )
+            .into_response(),
+        Err(Append::Corrupt(e)) => {
+            drop(partial);
+            state.metrics.decrypt_failed();
+            stream_failed(&state, format!("Decryption failed: {}", e))
         }
     }
 }
 
-fn decrypt_uploaded_blob(data: &[u8], key: &crypto::EncryptionKey) -> anyhow::Result<Vec<u8>> {
-    use chacha20poly1305::{
-        XChaCha20Poly1305,
-        aead::{Aead, KeyInit},
+async fn write_stdout(data: &[u8]) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+    let mut stdout = tokio::io::stdout();
+    stdout.write_all(data).await?;
+    stdout.flush().await
+}
+
+/// Part of the upload is already on stdout and the rest can't follow:
+/// give up on the session
+fn stream_failed(state: &ReceiveState, message: String) -> Response {
+    eprintln!(" {} {}", console::style("❌").bold(), message);
+    close_session(state, "failed");
+    (StatusCode::BAD_REQUEST, message).into_response()
+}
+
+#[derive(Default, serde::Deserialize)]
+struct FinishUpload {
+    /// Plaintext SHA-256 sealed under the session key: known only once the
+    /// page has read the whole file
+    #[serde(default)]
+    digest: Option<String>,
+}
+
+/// Every byte is in: decrypt and save. Safe to retry.
+async fn finish_chunked_upload(
+    Path(id): Path<String>,
+    State(state): State<Arc<ReceiveState>>,
+    body: axum::body::Bytes,
+) -> Response {
+    let finish: FinishUpload = if body.is_empty() {
+        FinishUpload::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(finish) => finish,
+            Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        }
+    };
+    let Some(partial) = state.partials.get(&id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let upload = UploadGuard::new(&state);
+    let mut partial = partial.lock().await;
+    if let Some(finished) = &partial.finished {
+        return Json(finished.clone()).into_response();
+    }
+    if !partial.is_complete() {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "offset": partial.offset })),
+        )
+            .into_response();
+    }
+    if finish.digest.is_some() {
+        partial.digest = finish.digest;
+    }
+    if state.to_stdout {
+        let (size, sha256) = match partial.finish_stream() {
+            Ok(finished) => finished,
+            Err(e) => {
+                drop(partial);
+                return stream_failed(&state, format!("Upload incomplete: {}", e));
+            }
+        };
+        // Too late to take the bytes back, but not to fail the pipeline
+        if let Some(digest) = &partial.digest
+            && let Err(e) = crypto::verify_digest(&state.key, digest, &sha256)
+        {
+            drop(partial);
+            return stream_failed(&state, format!("Integrity check failed: {}", e));
+        }
+        let relative = partial.relative.clone();
+        let verified = partial.digest.is_some();
+        state.session.settle(partial.size, size);
+        let accepted = streamed_upload(&state, relative, size, sha256, verified);
+        let reply = accepted.reply();
+        partial.finished = Some(reply.clone());
+        drop(partial);
+        drop(upload);
+        close_session(&state, "limit");
+        return Json(reply).into_response();
+    }
+    let ciphertext = match partial.take() {
+        Ok(ciphertext) => ciphertext,
+        Err(e) => {
+            state.session.release(partial.size);
+            drop(partial);
+            state.partials.remove(&id);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to read upload: {}", e),
+            )
+                .into_response();
+        }
     };
-    const HEADER_SIZE: usize = 40;
+    let relative = partial.relative.clone();
+    let digest = partial.digest.clone();
+    let reserved = Some(partial.size);
+    match accept_upload(&state, &relative, ciphertext, digest.as_deref(), reserved).await {
+        Ok(accepted) => {
+            let reply = accepted.reply();
+            partial.finished = Some(reply.clone());
+            drop(partial);
+            drop(upload);
+            if accepted.limit_reached {
+                close_session(&state, "limit");
+            }
+            Json(reply).into_response()
+        }
+        Err((status, message)) => {
+            // Nothing left to resume: the bytes are gone either way
+            drop(partial);
+            state.partials.remove(&id);
+            (status, message).into_response()
+        }
+    }
+}
+
+struct Accepted {
+    /// Where it was saved (renamed on conflict), or the name it would have had
+    relative: std::path::PathBuf,
+    size: u64,
+    /// This upload used up `--max-files` or `--max-total-size`
+    limit_reached: bool,
+    /// `--on-conflict skip` and the name was taken
+    skipped: bool,
+    /// What the scanner said, when one is configured. Anything but clean
+    /// means the file is in quarantine, not the output directory.
+    verdict: Option<crate::scan::Verdict>,
+    /// Plaintext SHA-256
+    sha256: [u8; 32],
+}
 
-    if data.len() < HEADER_SIZE {
-        anyhow::bail!("Data too short for header");
+impl Accepted {
+    fn reply(&self) -> serde_json::Value {
+        let quarantined = self.verdict.as_ref().is_some_and(|v| !v.is_clean());
+        let mut reply = serde_json::json!({
+            "status": if quarantined {
+                "quarantined"
+            } else if self.skipped {
+                "skipped"
+            } else {
+                "ok"
+            },
+            "saved_as": self.relative.display().to_string(),
+            "size": self.size,
+            "sha256": crypto::to_hex(&self.sha256),
+        });
+        if let Some(verdict) = &self.verdict {
+            reply["verdict"] = verdict.label().into();
+            if let Some(detail) = verdict.detail() {
+                reply["threat"] = detail.into();
+            }
+        }
+        reply
     }
+}
+
+/// Refuse a name before any of its bytes are taken
+fn check_upload_path(
+    state: &ReceiveState,
+    relative: &std::path::Path,
+) -> Result<(), (StatusCode, String)> {
+    // Names only matter for what is written to disk
+    if state.to_stdout {
+        return Ok(());
+    }
+    let checked = crate::receive::check_path(relative, state.allow_dotfiles).and_then(|()| {
+        match &state.quarantine {
+            Some(q) if state.output_dir.join(relative).starts_with(&q.dir) => {
+                anyhow::bail!("the quarantine folder is off limits")
+            }
+            _ => Ok(()),
+        }
+    });
+    checked.map_err(|e| {
+        eprintln!(
+            " {} Refused {}: {}",
+            console::style("⚠").yellow(),
+            relative.display(),
+            e
+        );
+        (StatusCode::BAD_REQUEST, e.to_string())
+    })
+}
 
-    let mut nonce_bytes = [0u8; 24];
-    nonce_bytes.copy_from_slice(&data[..24]);
-    let total_chunks = u64::from_le_bytes(data[24..32].try_into()?);
-    let original_size = u64::from_le_bytes(data[32..40].try_into()?);
+/// `--on-conflict skip` and the name is already taken
+fn already_exists(state: &ReceiveState, relative: &std::path::Path) -> bool {
+    let stored = match state.vault {
+        Some(_) => crate::vault::sealed_name(relative),
+        None => relative.to_path_buf(),
+    };
+    state.on_conflict == crate::receive::OnConflict::Skip
+        && state.output_dir.join(stored).symlink_metadata().is_ok()
+}
 
-    let cipher = XChaCha20Poly1305::new_from_slice(&key.0)
-        .map_err(|_| anyhow::anyhow!("Failed to init cipher"))?;
+fn print_skipped(relative: &std::path::Path) {
+    eprintln!(
+        " {} Skipped {} — already exists",
+        console::style("⏭").dim(),
+        relative.display()
+    );
+}
 
-    let chunk_data = &data[HEADER_SIZE..];
-    let mut plaintext = Vec::with_capacity(original_size as usize);
-    let mut offset = 0;
+/// Admit, decrypt, verify against the uploader's sealed `digest` (when
+/// sent), scan (when configured) and save one encrypted upload under the
+/// output directory. The ciphertext is decrypted a chunk at a time on its
+/// way to disk. `reserved` is the room already reserved by its announced
+/// size; without it the upload is admitted by its header's.
+async fn accept_upload(
+    state: &ReceiveState,
+    relative: &std::path::Path,
+    ciphertext: impl std::io::Read + Send + 'static,
+    digest: Option<&str>,
+    reserved: Option<u64>,
+) -> Result<Accepted, (StatusCode, String)> {
+    let give_back = || {
+        if let Some(reserved) = reserved {
+            state.session.release(reserved);
+        }
+    };
+    let expected = match digest.map(|digest| crypto::open_digest(&state.key, digest)) {
+        None => None,
+        Some(Ok(sha256)) => Some(sha256),
+        Some(Err(e)) => {
+            give_back();
+            eprintln!(
+                " {} Integrity check failed for {}: {}",
+                console::style("❌").bold(),
+                relative.display(),
+                e
+            );
+            state.metrics.decrypt_failed();
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Integrity check failed: {}", e),
+            ));
+        }
+    };
+    let reader = match crypto::DecryptingReader::new(ciphertext, &state.key, expected) {
+        Ok(reader) => reader,
+        Err(e) => {
+            give_back();
+            return Err((StatusCode::BAD_REQUEST, e.to_string()));
+        }
+    };
+    let size = reader.original_size();
+    if let Err(refused) = check_upload_path(state, relative) {
+        give_back();
+        return Err(refused);
+    }
+    match reserved {
+        None => {
+            if let Err(refused) = state.session.admit(size) {
+                eprintln!(
+                    " {} Refused {}: {}",
+                    console::style("⚠").yellow(),
+                    relative.display(),
+                    refused
+                );
+                return Err((refused_status(refused), refused.to_string()));
+            }
+        }
+        // A header claiming more than was sent can't be the truth
+        Some(reserved) if size > reserved => {
+            give_back();
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Size does not match the header".into(),
+            ));
+        }
+        Some(reserved) => state.session.settle(reserved, size),
+    }
+    let verified = expected.is_some();
 
-    for chunk_index in 0..total_chunks {
-        if offset + 4 > chunk_data.len() {
-            anyhow::bail!("Truncated chunk length at chunk {}", chunk_index);
+    // Any chunk that does not decrypt, a missing one or a digest that
+    // doesn't match fails the read, and whatever was written is removed
+    let failed = |reader: &crypto::DecryptingReader<_>, error: String| {
+        state.session.release(size);
+        if let Some(failure) = reader.failure() {
+            eprintln!(
+                " {} {}: {}",
+                console::style("❌").bold(),
+                relative.display(),
+                failure
+            );
+            state.metrics.decrypt_failed();
+            return (StatusCode::BAD_REQUEST, failure.to_string());
         }
+        eprintln!(" {} {}", console::style("❌").bold(), error);
+        (StatusCode::INTERNAL_SERVER_ERROR, error)
+    };
 
-        let chunk_len = u32::from_le_bytes(chunk_data[offset..offset + 4].try_into()?) as usize;
-        offset += 4;
+    if state.to_stdout {
+        let read = tokio::task::spawn_blocking(move || {
+            let mut reader = reader;
+            let mut plaintext = Vec::new();
+            let read = std::io::Read::read_to_end(&mut reader, &mut plaintext);
+            (read.map(|_| plaintext), reader)
+        })
+        .await;
+        let (plaintext, reader) = match read {
+            Ok(read) => read,
+            Err(e) => {
+                state.session.release(size);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+            }
+        };
+        let plaintext = match plaintext {
+            Ok(plaintext) => plaintext,
+            Err(e) => return Err(failed(&reader, format!("Failed to read upload: {}", e))),
+        };
+        if let Err(e) = write_stdout(&plaintext).await {
+            return Err(failed(&reader, format!("Writing to stdout failed: {}", e)));
+        }
+        return Ok(streamed_upload(
+            state,
+            relative.to_path_buf(),
+            size,
+            reader.sha256(),
+            verified,
+        ));
+    }
 
-        if offset + chunk_len > chunk_data.len() {
-            anyhow::bail!("Truncated chunk data at chunk {}", chunk_index);
+    let output_dir = state.output_dir.clone();
+    let vault = state.vault.clone();
+    let quarantine = state.quarantine.clone();
+    let on_conflict = state.on_conflict;
+    let target = relative.to_path_buf();
+    let stored = tokio::task::spawn_blocking(move || {
+        let mut reader = reader;
+        let stored = match (&quarantine, &vault) {
+            (Some(quarantine), _) => quarantine.hold(&target, &mut reader).map(Stored::Held),
+            (None, Some(vault)) => vault
+                .save_from(&output_dir, &target, &mut reader, on_conflict)
+                .map(Stored::Saved),
+            (None, None) => crate::receive::save_with(&output_dir, &target, on_conflict, |file| {
+                std::io::copy(&mut reader, file).map(|_| ())
+            })
+            .map(Stored::Saved),
+        };
+        (stored, reader)
+    })
+    .await;
+    let (stored, reader) = match stored {
+        Ok(stored) => stored,
+        Err(e) => {
+            state.session.release(size);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+        }
+    };
+    let sha256 = reader.sha256();
+    let (saved, verdict) = match stored {
+        Err(e) => return Err(failed(&reader, format!("Failed to save: {}", e))),
+        Ok(Stored::Saved(saved)) => (Ok(saved), None),
+        Ok(Stored::Held(held)) => match state
+            .quarantine
+            .as_ref()
+            .expect("held in quarantine")
+            .release(held, &state.output_dir, relative, state.on_conflict)
+            .await
+        {
+            Ok(crate::scan::Outcome::Saved(saved)) => {
+                (Ok(saved), Some(crate::scan::Verdict::Clean))
+            }
+            Ok(crate::scan::Outcome::Quarantined { path, verdict }) => {
+                state.session.quarantined(size);
+                eprintln!(
+                    " {} Quarantined {}: {} → {}",
+                    console::style("☣").red().bold(),
+                    relative.display(),
+                    console::style(&verdict).red(),
+                    path.display()
+                );
+                state.events.emit(Event::Quarantined {
+                    filename: relative.display().to_string(),
+                    path: path.display().to_string(),
+                    verdict: verdict.label(),
+                    threat: verdict.detail().map(str::to_string),
+                });
+                return Ok(Accepted {
+                    relative: relative.to_path_buf(),
+                    size,
+                    limit_reached: false,
+                    skipped: false,
+                    verdict: Some(verdict),
+                    sha256,
+                });
+            }
+            Err(e) => (Err(e), None),
+        },
+    };
+    let relative = match saved {
+        Ok(Some(saved)) => saved,
+        Ok(None) => {
+            state.session.release(size);
+            print_skipped(relative);
+            return Ok(Accepted {
+                relative: relative.to_path_buf(),
+                size,
+                limit_reached: false,
+                skipped: true,
+                verdict,
+                sha256,
+            });
         }
+        Err(e) => {
+            state.session.release(size);
+            eprintln!(
+                " {} Failed to save file: {}",
+                console::style("❌").bold(),
+                e
+            );
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to save: {}", e),
+            ));
+        }
+    };
 
-        let encrypted_chunk = &chunk_data[offset..offset + chunk_len];
-        offset += chunk_len;
+    eprintln!(
+        " {} Saved: {} ({}{})",
+        console::style("✅").bold(),
+        console::style(relative.display()).green(),
+        console::style(bytesize::ByteSize::b(size).to_string()).dim(),
+        console::style(if verdict.is_some() {
+            ", scanned clean"
+        } else if state.vault.is_some() {
+            ", sealed to vault"
+        } else {
+            ""
+        })
+        .dim()
+    );
+    print_sha256(&sha256, verified);
+    state.events.emit(Event::Received {
+        filename: relative.display().to_string(),
+        path: state.output_dir.join(&relative).display().to_string(),
+        size,
+        sha256: crypto::to_hex(&sha256),
+        verified,
+    });
+    Ok(Accepted {
+        limit_reached: state.session.record(relative.clone(), size),
+        relative,
+        size,
+        skipped: false,
+        verdict,
+        sha256,
+    })
+}
+
+/// Where an upload's plaintext went on its way through
+enum Stored {
+    /// Written to quarantine, still to be scanned
+    Held(std::path::PathBuf),
+    /// Written to the output directory (None: skipped on conflict)
+    Saved(Option<std::path::PathBuf>),
+}
+
+/// The upload is all on stdout: report and record it
+fn streamed_upload(
+    state: &ReceiveState,
+    relative: std::path::PathBuf,
+    size: u64,
+    sha256: [u8; 32],
+    verified: bool,
+) -> Accepted {
+    eprintln!(
+        " {} Streamed: {} ({}) → stdout",
+        console::style("✅").bold(),
+        console::style(relative.display()).green(),
+        console::style(bytesize::ByteSize::b(size).to_string()).dim()
+    );
+    print_sha256(&sha256, verified);
+    state.events.emit(Event::Received {
+        filename: relative.display().to_string(),
+        path: "-".to_string(),
+        size,
+        sha256: crypto::to_hex(&sha256),
+        verified,
+    });
+    Accepted {
+        limit_reached: state.session.record(relative.clone(), size),
+        relative,
+        size,
+        skipped: false,
+        verdict: None,
+        sha256,
+    }
+}
 
-        let mut chunk_nonce = nonce_bytes;
-        let idx_bytes = chunk_index.to_le_bytes();
-        for i in 0..8 {
-            chunk_nonce[i] ^= idx_bytes[i];
+/// The digest under a saved upload, to read back to the sender
+fn print_sha256(sha256: &[u8; 32], verified: bool) {
+    eprintln!(
+        "    {} {}{}",
+        console::style("SHA-256").dim(),
+        crypto::to_hex(sha256),
+        if verified {
+            console::style(" ✓ matches the sender's").green()
+        } else {
+            console::style(" (the uploader sent none)").dim()
         }
+    );
+}
 
-        let decrypted = cipher
-            .decrypt(
-                &chacha20poly1305::XNonce::from(chunk_nonce),
-                encrypted_chunk,
-            )
-            .map_err(|_| {
-                anyhow::anyhow!(
-                    "Decryption failed at chunk {} — wrong key or corrupted",
-                    chunk_index
-                )
-            })?;
-
-        plaintext.extend_from_slice(&decrypted);
+/// Stop taking uploads, print what arrived and shut down. `reason` is
+/// "done", "limit", "expired", "failed" (`-o -` stream broken) or
+/// "interrupted" (Ctrl+C, already stopping).
+fn close_session(state: &ReceiveState, reason: &'static str) {
+    if !state.session.close() {
+        return;
+    }
+    // Uploads that never finished go with their temp files
+    state.partials.clear();
+    let saved = state.session.saved();
+    state.session.print_summary();
+    state.events.emit(Event::ReceiveClosed {
+        reason,
+        files: saved.len() as u32,
+        size: saved.iter().map(|s| s.size).sum(),
+    });
+    if matches!(reason, "done" | "limit") {
+        eprintln!(
+            "\n {} Transfer complete — self-destructing.",
+            console::style("💥").bold()
+        );
+    }
+    if reason != "interrupted" {
+        state.shutdown.notify_one();
     }
-
-    Ok(plaintext)
 }
 
 async fn serve_web_asset_receive(Path(path): Path<String>) -> Response {
@@ -846,6 +2916,7 @@ async fn serve_web_asset_receive(Path(path): Path<String>) -> Response {
 
 async fn ws_blob_handler(
     Path(id): Path<String>,
+    Query(query): Query<DropQuery>,
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -855,24 +2926,15 @@ async fn ws_blob_handler(
         return (StatusCode::NOT_FOUND, "Drop not found").into_response();
     };
 
-    // Tunnel-aware IP pinning (uses shared helpers)
-    let tunnel = is_tunnel_request(&addr, &headers);
-    let client_ip = resolve_client_ip(&addr, &headers);
-
-    {
-        let mut pinned = drop.pinned_ip.lock().unwrap();
-        match pinned.as_ref() {
-            None => *pinned = Some(client_ip.clone()),
-            Some(ip) if ip == &client_ip => {}
-            Some(_) if tunnel => {}
-            Some(_) => {
-                return (StatusCode::FORBIDDEN, "Access denied").into_response();
-            }
-        }
+    // Access policy — strict on every path, tunnel included
+    if let Some(denied) = enforce_access(&state, &drop, &addr, &headers, query.t.as_deref()) {
+        return denied;
     }
 
-    let Some((count, should_delete)) = state.store.record_download(&id) else {
-        return (StatusCode::NOT_FOUND, "Drop not found").into_response();
+    let client_ip = state.proxies.client_ip(&addr, &headers);
+    let Some(guard) = open_download(&state, &drop, client_ip, crate::store::Transport::WebSocket)
+    else {
+        return slots_exhausted(&state, &drop, client_ip);
     };
 
     eprintln!(
@@ -880,30 +2942,17 @@ async fn ws_blob_handler(
         console::style("⚡").cyan(),
         console::style(&addr.to_string()).dim()
     );
-    progress::print_download_event(count, drop.max_downloads, &addr.to_string());
-
-    let encrypted_size = drop.encrypted_size;
-    let encrypted_path = drop.encrypted_path.clone();
-    let ciphertext = drop.ciphertext.clone();
-    let store = state.store.clone();
-    let shutdown = state.shutdown.clone();
-    let id_clone = id.clone();
 
     ws.on_upgrade(move |socket| async move {
-        if let Err(e) = stream_blob_ws(socket, encrypted_size, encrypted_path, ciphertext).await {
-            eprintln!(
-                " {} WebSocket stream error: {}",
-                console::style("⚠").yellow(),
-                e
-            );
-        }
-
-        if should_delete {
-            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-            store.remove(&id_clone);
-            progress::print_self_destruct();
-            if store.is_empty() {
-                shutdown.notify_one();
+        let sent = |bytes: u64| guard.sent(crate::store::Transport::WebSocket, bytes);
+        match stream_blob_ws(socket, &drop, sent).await {
+            Ok(()) => guard.complete(),
+            Err(e) => {
+                eprintln!(
+                    " {} WebSocket stream error: {}",
+                    console::style("⚠").yellow(),
+                    e
+                );
             }
         }
     })
@@ -911,42 +2960,47 @@ async fn ws_blob_handler(
 
 async fn stream_blob_ws(
     mut socket: WebSocket,
-    encrypted_size: u64,
-    encrypted_path: Option<std::path::PathBuf>,
-    ciphertext: Option<Vec<u8>>,
+    drop: &crate::store::Drop,
+    mut on_sent: impl FnMut(u64),
 ) -> anyhow::Result<()> {
+    // A folder still being archived has no size to announce yet
+    if drop.size_open() {
+        drop.bytes_ready(0).await?;
+    }
+
     // Send start message
     let start_msg = serde_json::json!({
         "type": "start",
-        "encrypted_size": encrypted_size,
+        "encrypted_size": drop.encrypted_size(),
     });
     socket
         .send(Message::from(start_msg.to_string()))
         .await
         .map_err(|e| anyhow::anyhow!("WS send error: {}", e))?;
 
-    const CHUNK_SIZE: usize = 64 * 1024; // 64KB frames
-
-    if let Some(ref path) = encrypted_path {
-        use tokio::io::AsyncReadExt;
+    if let Some(ref path) = drop.encrypted_path {
         let mut file = tokio::fs::File::open(path).await?;
-        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut buf = vec![0u8; STREAM_FRAME];
+        let mut offset: u64 = 0;
         loop {
-            let n = file.read(&mut buf).await?;
+            let n = read_growing(drop, &mut file, offset, &mut buf).await?;
             if n == 0 {
                 break;
             }
+            offset += n as u64;
             socket
                 .send(Message::from(buf[..n].to_vec()))
                 .await
                 .map_err(|e| anyhow::anyhow!("WS send error: {}", e))?;
+            on_sent(n as u64);
         }
-    } else if let Some(ref data) = ciphertext {
-        for chunk in data.chunks(CHUNK_SIZE) {
+    } else if let Some(ref data) = drop.ciphertext {
+        for chunk in data.chunks(STREAM_FRAME) {
             socket
                 .send(Message::from(chunk.to_vec()))
                 .await
                 .map_err(|e| anyhow::anyhow!("WS send error: {}", e))?;
+            on_sent(chunk.len() as u64);
         }
     } else {
         anyhow::bail!("No encrypted data available");
@@ -962,12 +3016,53 @@ async fn stream_blob_ws(
     Ok(())
 }
 
+/// Read the container file from `offset` into `buf`. A drop still
+/// encrypting waits for bytes that are not on disk yet; 0 at the end.
+async fn read_growing(
+    drop: &crate::store::Drop,
+    file: &mut tokio::fs::File,
+    offset: u64,
+    buf: &mut [u8],
+) -> anyhow::Result<usize> {
+    use tokio::io::AsyncReadExt;
+    let available = drop.bytes_ready(offset).await?;
+    let want = buf.len().min(available.saturating_sub(offset) as usize);
+    if want == 0 {
+        return Ok(0);
+    }
+    Ok(file.read(&mut buf[..want]).await?)
+}
+
+/// Blob body for a drop still encrypting: follows the file as it grows
+fn growing_stream(
+    drop: Arc<crate::store::Drop>,
+    file: tokio::fs::File,
+) -> futures_util::stream::BoxStream<'static, std::io::Result<axum::body::Bytes>> {
+    futures_util::stream::try_unfold((drop, file, 0u64), |(drop, mut file, offset)| async move {
+        let mut buf = vec![0u8; STREAM_FRAME];
+        let n = read_growing(&drop, &mut file, offset, &mut buf)
+            .await
+            .map_err(std::io::Error::other)?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.truncate(n);
+        let next = (drop, file, offset + n as u64);
+        Ok(Some((axum::body::Bytes::from(buf), next)))
+    })
+    .boxed()
+}
+
 async fn ws_upload_handler(
     State(state): State<Arc<ReceiveState>>,
     ws: WebSocketUpgrade,
 ) -> Response {
-    if state.received.load(std::sync::atomic::Ordering::SeqCst) {
-        return (StatusCode::GONE, "Already received").into_response();
+    if state.session.is_closed() {
+        return (
+            StatusCode::GONE,
+            crate::receive::Refused::Closed.to_string(),
+        )
+            .into_response();
     }
 
     ws.on_upgrade(move |socket| async move {
@@ -981,10 +3076,100 @@ async fn ws_upload_handler(
     })
 }
 
+/// Counts an upload as in flight for as long as its handler runs
+struct UploadGuard(Arc<ReceiveState>);
+
+impl UploadGuard {
+    fn new(state: &Arc<ReceiveState>) -> Self {
+        state
+            .uploads
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Self(state.clone())
+    }
+}
+
+impl std::ops::Drop for UploadGuard {
+    fn drop(&mut self) {
+        self.0
+            .uploads
+            .fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// Every so often, give up on chunked uploads left idle past
+/// [`crate::receive::UPLOAD_IDLE_TIMEOUT`] and release their reservations
+fn spawn_partial_reaper(state: Arc<ReceiveState>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        while !state.session.is_closed() {
+            interval.tick().await;
+            let reaped = state
+                .partials
+                .reap_idle(crate::receive::UPLOAD_IDLE_TIMEOUT);
+            if reaped.is_empty() {
+                continue;
+            }
+            for size in &reaped {
+                state.session.release(*size);
+            }
+            eprintln!(
+                " {} Gave up on {} idle upload{}",
+                console::style("⌛").dim(),
+                reaped.len(),
+                if reaped.len() == 1 { "" } else { "s" }
+            );
+            // Part of it is already on stdout and the rest isn't coming
+            if state.to_stdout {
+                close_session(&state, "failed");
+            }
+        }
+    });
+}
+
+/// Room a WebSocket upload reserved by its announced size, given back if
+/// the upload ends before it is handed on
+struct Reservation<'a> {
+    session: &'a crate::receive::Session,
+    size: Option<u64>,
+}
+
+impl std::ops::Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if let Some(size) = self.size.take() {
+            self.session.release(size);
+        }
+    }
+}
+
+/// Tell the page why its upload was turned away, and hang up
+async fn refuse_ws_upload(
+    mut socket: WebSocket,
+    relative: &std::path::Path,
+    refused: crate::receive::Refused,
+) -> anyhow::Result<()> {
+    eprintln!(
+        " {} Refused {}: {}",
+        console::style("⚠").yellow(),
+        relative.display(),
+        refused
+    );
+    let resp = serde_json::json!({ "type": "error", "message": refused.to_string() });
+    let _ = socket.send(Message::from(resp.to_string())).await;
+    let _ = socket.close().await;
+    Ok(())
+}
+
 async fn handle_ws_upload(mut socket: WebSocket, state: Arc<ReceiveState>) -> anyhow::Result<()> {
-    let mut filename = "received_file".to_string();
-    let mut encrypted_data: Vec<u8> = Vec::new();
-    let mut started = false;
+    let upload = UploadGuard::new(&state);
+    let mut reservation = Reservation {
+        session: &state.session,
+        size: None,
+    };
+    let mut relative = std::path::PathBuf::from("received_file");
+    // Spooled to disk like a chunked upload, never held in memory
+    let mut spool: Option<tempfile::NamedTempFile> = None;
+    let mut received: u64 = 0;
+    let mut digest: Option<String> = None;
 
     while let Some(msg) = socket.recv().await {
         let msg: Message = msg.map_err(|e| anyhow::anyhow!("WS recv error: {}", e))?;
@@ -993,20 +3178,41 @@ async fn handle_ws_upload(mut socket: WebSocket, state: Arc<ReceiveState>) -> an
                 let json: serde_json::Value = serde_json::from_str(&text)?;
                 match json["type"].as_str() {
                     Some("start") => {
-                        if let Some(name) = json["filename"].as_str() {
-                            filename = name.replace("..", "").replace('/', "").replace('\\', "");
-                            if filename.is_empty() {
-                                filename = "received_file".to_string();
-                            }
+                        // `path` for files inside a folder, else `filename`
+                        if let Some(name) = json["path"].as_str().or(json["filename"].as_str()) {
+                            relative = crypto::safe_relative_path(name);
+                        }
+                        if let Err((_, message)) = check_upload_path(&state, &relative) {
+                            let resp = serde_json::json!({ "type": "error", "message": message });
+                            let _ = socket.send(Message::from(resp.to_string())).await;
+                            let _ = socket.close().await;
+                            return Ok(());
                         }
+                        digest = json["digest"].as_str().map(str::to_string);
                         if let Some(size) = json["size"].as_u64() {
-                            encrypted_data.reserve(size as usize);
+                            if let Some(size) = reservation.size.take() {
+                                state.session.release(size);
+                            }
+                            if let Err(refused) = state.session.reserve_announced(size) {
+                                return refuse_ws_upload(socket, &relative, refused).await;
+                            }
+                            reservation.size = Some(size);
+                        }
+                        if spool.is_none() {
+                            let file = if state.to_stdout {
+                                tempfile::NamedTempFile::new()
+                            } else {
+                                tempfile::Builder::new()
+                                    .prefix(".deadrop-")
+                                    .suffix(".part")
+                                    .tempfile_in(&state.output_dir)
+                            };
+                            spool = Some(file?);
                         }
-                        started = true;
                         eprintln!(
                             " {} WebSocket upload started: {}",
                             console::style("⚡").cyan(),
-                            filename
+                            relative.display()
                         );
                     }
                     Some("done") => break,
@@ -1014,60 +3220,55 @@ async fn handle_ws_upload(mut socket: WebSocket, state: Arc<ReceiveState>) -> an
                 }
             }
             Message::Binary(data) => {
-                if started {
-                    encrypted_data.extend_from_slice(&data);
+                let Some(file) = spool.as_mut() else { continue };
+                received += data.len() as u64;
+                // The announced size may have been a lie: stop at it
+                let limit = reservation
+                    .size
+                    .unwrap_or_else(|| state.session.limits().max_encrypted_size());
+                if received > limit {
+                    return refuse_ws_upload(
+                        socket,
+                        &relative,
+                        crate::receive::Refused::FileTooLarge,
+                    )
+                    .await;
                 }
+                std::io::Write::write_all(file, &data)?;
             }
             Message::Close(_) => break,
             _ => {}
         }
     }
 
-    if encrypted_data.is_empty() {
+    let Some(mut file) = spool.filter(|_| received > 0) else {
         anyhow::bail!("No data received via WebSocket");
-    }
+    };
+    std::io::Seek::rewind(&mut file)?;
 
     eprintln!(
         " {} Received {} via P2P, decrypting...",
         console::style("📥").bold(),
-        bytesize::ByteSize::b(encrypted_data.len() as u64)
-    );
-
-    let plaintext = decrypt_uploaded_blob(&encrypted_data, &state.key)?;
-
-    let output_path = state.output_dir.join(&filename);
-    std::fs::write(&output_path, &plaintext)?;
-
-    state
-        .received
-        .store(true, std::sync::atomic::Ordering::SeqCst);
-
-    eprintln!(
-        " {} Saved: {} ({})",
-        console::style("✅").bold(),
-        console::style(&filename).green(),
-        console::style(bytesize::ByteSize::b(plaintext.len() as u64).to_string()).dim()
+        bytesize::ByteSize::b(received)
     );
 
-    let resp = serde_json::json!({
-        "type": "ok",
-        "saved_as": filename,
-        "size": plaintext.len()
-    });
-
+    let reserved = reservation.size.take();
+    let resp = match accept_upload(&state, &relative, file, digest.as_deref(), reserved).await {
+        Ok(accepted) => {
+            let mut resp = accepted.reply();
+            resp["type"] = resp["status"].take();
+            let _ = socket.send(Message::from(resp.to_string())).await;
+            let _ = socket.close().await;
+            drop(upload);
+            if accepted.limit_reached {
+                close_session(&state, "limit");
+            }
+            return Ok(());
+        }
+        Err((_, message)) => serde_json::json!({ "type": "error", "message": message }),
+    };
     let _ = socket.send(Message::from(resp.to_string())).await;
     let _ = socket.close().await;
-
-    let shutdown = state.shutdown.clone();
-    tokio::spawn(async move {
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-        eprintln!(
-            "\n {} Transfer complete — self-destructing.",
-            console::style("💥").bold()
-        );
-        shutdown.notify_one();
-    });
-
     Ok(())
 }
 
@@ -1086,6 +3287,7 @@ async fn serve_download_page() -> impl IntoResponse {
 
 async fn serve_blob(
     Path(id): Path<String>,
+    Query(query): Query<DropQuery>,
     State(state): State<Arc<AppState>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
@@ -1096,74 +3298,80 @@ async fn serve_blob(
         return (StatusCode::NOT_FOUND, "Drop not found or already destroyed").into_response();
     };
 
-    // ── Tunnel-aware IP pinning ──
+    // ── Access policy (IP pinning, networks, time windows, token) ──
     // Through Cloudflare tunnel, all connections arrive from 127.0.0.1.
-    // Use CF-Connecting-IP / X-Forwarded-For to resolve the real client IP.
-    let client_ip = resolve_client_ip(&addr, &headers);
-    let tunnel = is_tunnel_request(&addr, &headers);
+    // Forwarding headers are only believed from trusted proxies.
+    if let Some(denied) = enforce_access(&state, &drop, &addr, &headers, query.t.as_deref()) {
+        return denied;
+    }
 
+    // A folder still being archived is sized once it is sealed
+    if drop.size_open()
+        && let Err(e) = drop.bytes_ready(0).await
     {
-        let mut pinned = drop.pinned_ip.lock().unwrap();
-        match pinned.as_ref() {
-            None => *pinned = Some(client_ip.clone()),
-            Some(ip) if ip == &client_ip => {}
-            Some(_) if tunnel => {} // Allow tunnel requests through
-            Some(_) => {
-                eprintln!(
-                    " {} Blocked download attempt from {} [resolved: {}] (pinned to different IP)",
-                    console::style("🛡").red(),
-                    addr,
-                    client_ip
-                );
-                return (
-                    StatusCode::FORBIDDEN,
-                    "Access denied — this drop is locked to another device",
-                )
-                    .into_response();
-            }
-        }
+        return (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response();
     }
+    let encrypted_size = drop.encrypted_size();
 
-    let Some((count, should_delete)) = state.store.record_download(&id) else {
-        return StatusCode::NOT_FOUND.into_response();
-    };
-
-    progress::print_download_event(count, drop.max_downloads, &addr.to_string());
-
-    let encrypted_size = drop.encrypted_size;
-
-    let body = if let Some(ref path) = drop.encrypted_path {
-        match tokio::fs::File::open(path).await {
-            Ok(file) => Body::from_stream(ReaderStream::new(file)),
-            Err(e) => {
-                eprintln!(
-                    " {} Failed to open encrypted file {}: {}",
-                    console::style("⚠").yellow(),
-                    path.display(),
-                    e
-                );
-                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    let stream: futures_util::stream::BoxStream<'static, std::io::Result<axum::body::Bytes>> =
+        if let Some(ref path) = drop.encrypted_path {
+            match tokio::fs::File::open(path).await {
+                Ok(file) if drop.sealing.is_some() => growing_stream(drop.clone(), file),
+                Ok(file) => ReaderStream::new(file).boxed(),
+                Err(e) => {
+                    eprintln!(
+                        " {} Failed to open encrypted file {}: {}",
+                        console::style("⚠").yellow(),
+                        path.display(),
+                        e
+                    );
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
             }
-        }
-    } else if let Some(ref data) = drop.ciphertext {
-        Body::from(data.clone())
-    } else {
-        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        } else if let Some(ref data) = drop.ciphertext {
+            // Frame like the file stream so an early disconnect is noticed
+            let data = axum::body::Bytes::from(data.clone());
+            let frames = (0..data.len())
+                .step_by(STREAM_FRAME)
+                .map(move |start| Ok(data.slice(start..(start + STREAM_FRAME).min(data.len()))))
+                .collect::<Vec<_>>();
+            futures_util::stream::iter(frames).boxed()
+        } else {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        };
+
+    // The session completes when the last byte leaves the stream (hyper
+    // stops polling at Content-Length); if the client disconnects first,
+    // the guard is dropped with the stream and aborts it.
+    let client_ip = state.proxies.client_ip(&addr, &headers);
+    // A page falling back from chunks hands its chunked session back first
+    if let Some(session) = query
+        .session
+        .as_deref()
+        .and_then(|token| drop.chunk_session(client_ip, token))
+        && let Some(aborted) = drop.abort_session(session)
+    {
+        download_aborted(&state, &drop, session, aborted);
+    }
+    let Some(guard) = open_download(&state, &drop, client_ip, crate::store::Transport::Blob) else {
+        return slots_exhausted(&state, &drop, client_ip);
     };
-
-    if should_delete {
-        let store = state.store.clone();
-        let shutdown = state.shutdown.clone();
-        let id_clone = id.clone();
-        tokio::spawn(async move {
-            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-            store.remove(&id_clone);
-            progress::print_self_destruct();
-            if store.is_empty() {
-                shutdown.notify_one();
+    let mut guard = Some(guard);
+    let mut sent: u64 = 0;
+    let stream = stream.inspect(move |chunk| {
+        if let Ok(bytes) = chunk {
+            sent += bytes.len() as u64;
+            if let Some(guard) = guard.as_ref() {
+                guard.sent(crate::store::Transport::Blob, bytes.len() as u64);
             }
-        });
-    }
+            if sent >= encrypted_size
+                && let Some(guard) = guard.take()
+            {
+                guard.complete();
+            }
+        }
+    });
+    let body = Body::from_stream(stream);
 
     (
         StatusCode::OK,
@@ -1179,10 +3387,14 @@ async fn serve_blob(
 
 async fn serve_meta(Path(id): Path<String>, State(state): State<Arc<AppState>>) -> Response {
     if state.store.is_burned(&id) {
+        let burned = serde_json::json!({
+            "burned": true,
+            "digest": state.store.burned_digest(&id),
+        });
         return (
             StatusCode::GONE,
             [(header::CONTENT_TYPE, "application/json")],
-            r#"{"burned":true}"#,
+            burned.to_string(),
         )
             .into_response();
     }
@@ -1195,19 +3407,22 @@ async fn serve_meta(Path(id): Path<String>, State(state): State<Arc<AppState>>)
 
     let meta = serde_json::json!({
         "filename": drop.filename,
-        "size": bytesize::ByteSize::b(drop.file_size).to_string(),
-        "size_bytes": drop.file_size,
+        // A folder still being archived is sized once it is sealed
+        "size": match drop.size_open() {
+            true => "still archiving".to_string(),
+            false => bytesize::ByteSize::b(drop.file_size()).to_string(),
+        },
+        "size_bytes": (!drop.size_open()).then(|| drop.file_size()),
         "mime": drop.mime_type,
-        "expires_at": drop.expires_at.to_rfc3339(),
-        "downloads_remaining": if drop.max_downloads == 0 {
-            "unlimited".to_string()
-        } else {
-            let remaining = drop.max_downloads.saturating_sub(
-                drop.download_count.load(std::sync::atomic::Ordering::SeqCst)
-            );
-            remaining.to_string()
+        "expires_at": drop.expires_at().to_rfc3339(),
+        "downloads_remaining": match drop.remaining_downloads() {
+            None => "unlimited".to_string(),
+            Some(remaining) => remaining.to_string(),
         },
         "has_password": drop.has_password,
+        "digest": drop.sealed_digest(),
+        // Still encrypting: the digest comes once the last chunk is out
+        "sealing": drop.sealing.as_ref().is_some_and(|s| !s.progress().done),
     });
 
     (
@@ -1218,44 +3433,146 @@ async fn serve_meta(Path(id): Path<String>, State(state): State<Arc<AppState>>)
         .into_response()
 }
 
+#[derive(serde::Deserialize)]
+struct ReceiptBody {
+    sha256: String,
+    mac: String,
+}
+
+/// Proof of download: the page posts this after the final chunk decrypted
+/// and authenticated. Drops that already burned still take receipts.
+async fn receive_receipt(
+    Path(id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    axum::Json(body): axum::Json<ReceiptBody>,
+) -> Response {
+    let client = state.proxies.client_ip(&addr, &headers);
+    let client_ip = client.to_string();
+    match state.receipts.verify(&id, client, &body.sha256, &body.mac) {
+        Ok(decoy) => {
+            progress::print_receipt(&client_ip, decoy);
+            state.events.emit(Event::ReceiptVerified {
+                drop_id: id,
+                ip: client_ip,
+                decoy,
+            });
+            StatusCode::NO_CONTENT.into_response()
+        }
+        // Already counted; a retry from the page is fine, it just adds nothing
+        Err(crate::receipt::Rejected::Repeated) => StatusCode::NO_CONTENT.into_response(),
+        Err(crate::receipt::Rejected::UnknownDrop) => {
+            let delay = 50 + rand::random::<u64>() % 150;
+            tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
+            (StatusCode::NOT_FOUND, "Drop not found").into_response()
+        }
+        Err(_) => {
+            eprintln!(
+                " {} Receipt from {} did not verify",
+                console::style("⚠").yellow(),
+                client_ip
+            );
+            state.events.emit(Event::ReceiptRejected {
+                drop_id: id,
+                ip: client_ip,
+            });
+            (StatusCode::BAD_REQUEST, "Receipt did not verify").into_response()
+        }
+    }
+}
+
 // Return header metadata (nonce, total_chunks, original_size, encrypted_size)
+// and the session token the chunk requests go out under. A folder still
+// being archived has no sizes yet: its chunks carry X-Total-Chunks once known.
 async fn serve_chunks(
     Path(id): Path<String>,
+    Query(query): Query<DropQuery>,
     State(state): State<Arc<AppState>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
 ) -> Response {
-    let Some(drop) = state.store.get(&id) else {
+    // A client picking its session back up may be draining a revoked drop
+    let drop = match query.session {
+        Some(_) => state.store.get_for_session(&id),
+        None => state.store.get(&id),
+    };
+    let Some(drop) = drop else {
         return (StatusCode::NOT_FOUND, "Drop not found").into_response();
     };
 
-    const HEADER_SIZE: usize = crypto::EncryptedHeader::SIZE;
+    if let Some(denied) = enforce_access(&state, &drop, &addr, &headers, query.t.as_deref()) {
+        return denied;
+    }
+
+    // Carry on with the session the client already holds, or issue one
+    let client_ip = state.proxies.client_ip(&addr, &headers);
+    let session = match query
+        .session
+        .filter(|token| drop.chunk_session(client_ip, token).is_some())
+    {
+        Some(token) => token,
+        None => match open_chunk_download(&state, &drop, client_ip) {
+            Some(token) => token,
+            None => return slots_exhausted(&state, &drop, client_ip),
+        },
+    };
+
+    // A drop still encrypting has written only some of its chunks; the
+    // rest are served as they land
+    let (available_chunks, sealed) = match &drop.sealing {
+        Some(sealing) => {
+            let progress = sealing.progress();
+            (progress.chunks, progress.done)
+        }
+        None => (drop.served_chunks(), true),
+    };
+    let reply = |meta: serde_json::Value| {
+        eprintln!(
+            " {} /api/chunks/{} requested from {}",
+            console::style("→").dim(),
+            id,
+            resolve_client_ip(&state.proxies, &addr, &headers)
+        );
+        (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            serde_json::to_string(&meta).unwrap(),
+        )
+            .into_response()
+    };
+
+    // A password drop goes out slot after slot, every chunk of both
+    // whichever one the password opens, so the fetches can't tell them
+    // apart. A slot's header may not be written yet: each chunk carries
+    // its slot's nonce instead.
+    if drop.slot_size > 0 {
+        return reply(serde_json::json!({
+            "session": session,
+            "slots": crypto::DENIABLE_SLOTS,
+            "slot_chunks": drop.total_chunks(),
+            "total_chunks": drop.served_chunks(),
+            "available_chunks": available_chunks,
+            "sealed": sealed,
+            "encrypted_size": drop.encrypted_size(),
+        }));
+    }
 
+    // Taken before the header is read: once sealed, the header on disk is final
+    let size_open = drop.size_open();
     // Read header from disk or memory
     let header = if let Some(ref path) = drop.encrypted_path {
         match tokio::fs::File::open(path).await {
-            Ok(mut f) => {
-                let mut buf = vec![0u8; HEADER_SIZE];
-                use tokio::io::AsyncReadExt;
-                if let Err(e) = f.read_exact(&mut buf).await {
-                    eprintln!(
-                        " {} Failed to read header: {}",
-                        console::style("⚠").yellow(),
-                        e
-                    );
-                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-                }
-                match crypto::EncryptedHeader::from_bytes(&buf) {
-                    Ok(h) => h,
-                    Err(e) => {
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            format!("Bad header: {}", e),
-                        )
-                            .into_response();
-                    }
+            Ok(mut f) => match read_header_async(&mut f).await {
+                Ok(h) => h,
+                Err(e) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Bad header: {}", e),
+                    )
+                        .into_response();
                 }
-            }
+            },
             Err(e) => {
                 eprintln!(
                     " {} Failed to open encrypted file: {}",
@@ -1266,7 +3583,7 @@ async fn serve_chunks(
             }
         }
     } else if let Some(ref data) = drop.ciphertext {
-        match crypto::EncryptedHeader::from_bytes(&data[..HEADER_SIZE]) {
+        match crypto::EncryptedHeader::from_bytes(data) {
             Ok(h) => h,
             Err(e) => {
                 return (
@@ -1281,56 +3598,77 @@ async fn serve_chunks(
     };
 
     use base64::engine::general_purpose::URL_SAFE_NO_PAD;
-    let nonce_b64 = URL_SAFE_NO_PAD.encode(&header.nonce);
+    let nonce_b64 = URL_SAFE_NO_PAD.encode(header.nonce);
 
-    let meta = serde_json::json!({
+    let sized = |n: u64| (!size_open).then_some(n);
+    reply(serde_json::json!({
+        "session": session,
         "nonce": nonce_b64,
-        "total_chunks": header.total_chunks,
-        "original_size": header.original_size,
-        "encrypted_size": drop.encrypted_size,
+        "total_chunks": sized(header.total_chunks),
+        "chunk_size": header.chunk_size,
+        "available_chunks": available_chunks,
+        "sealed": sealed,
+        "original_size": sized(header.original_size),
+        "encrypted_size": sized(drop.encrypted_size()),
         "recipient_envelopes": drop.recipient_envelopes.iter().map(|e| serde_json::json!({
             "recipient_id": e.recipient_id,
             "ephemeral_pub_b64": e.ephemeral_pub_b64,
             "encrypted_cek_b64": e.encrypted_cek_b64,
         })).collect::<Vec<_>>(),
-    });
-
-    eprintln!(
-        " {} /api/chunks/{} requested from {}",
-        console::style("→").dim(),
-        id,
-        resolve_client_ip(&addr, &headers)
-    );
-
-    (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, "application/json")],
-        serde_json::to_string(&meta).unwrap(),
-    )
-        .into_response()
+    }))
 }
 
 // Return the raw encrypted bytes for a single chunk index
 async fn serve_chunk(
     Path((id, idx)): Path<(String, u64)>,
+    Query(query): Query<DropQuery>,
     State(state): State<Arc<AppState>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
 ) -> Response {
-    let Some(drop) = state.store.get(&id) else {
+    let Some(drop) = state.store.get_for_session(&id) else {
         return (StatusCode::NOT_FOUND, "Drop not found").into_response();
     };
 
-    const HEADER_SIZE: usize = crypto::EncryptedHeader::SIZE;
+    if let Some(denied) = enforce_access(&state, &drop, &addr, &headers, query.t.as_deref()) {
+        return denied;
+    }
+
+    // Only the client `/api/chunks` issued the session to may spend it
+    let client_ip = state.proxies.client_ip(&addr, &headers);
+    let Some(session) = query
+        .session
+        .as_deref()
+        .and_then(|token| drop.chunk_session(client_ip, token))
+    else {
+        return (StatusCode::FORBIDDEN, "Unknown or expired download session").into_response();
+    };
 
-    // Validate requested index against stored total_chunks if available
-    if drop.total_chunks > 0 && idx >= drop.total_chunks {
+    // A folder still being archived has its chunk count once sealed, so
+    // its range is checked again when the chunk is ready
+    let out_of_range = || !drop.size_open() && idx >= drop.served_chunks();
+    if out_of_range() {
+        return (StatusCode::NOT_FOUND, "Chunk index out of range").into_response();
+    }
+    if let Err(e) = drop.chunk_ready(idx).await {
+        return (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response();
+    }
+    if out_of_range() {
         return (StatusCode::NOT_FOUND, "Chunk index out of range").into_response();
     }
 
-    // Helper to extract chunk bytes from a byte slice starting at HEADER_SIZE
+    // A password drop's chunks run through one slot, then the other
+    let (slot_start, slot_index) = match drop.slot_size {
+        0 => (0, idx),
+        slot_size => (
+            idx / drop.total_chunks() * slot_size,
+            idx % drop.total_chunks(),
+        ),
+    };
+
+    // Helper to extract chunk bytes from a byte slice starting at the header
     let extract_from_slice = |data: &[u8], target: u64| -> anyhow::Result<Vec<u8>> {
-        let mut offset = HEADER_SIZE;
+        let mut offset = crypto::EncryptedHeader::len_of(data);
         for i in 0.. {
             if offset + 4 > data.len() {
                 anyhow::bail!("Truncated chunk length at {}", i);
@@ -1349,22 +3687,21 @@ async fn serve_chunk(
     };
 
     if let Some(ref data) = drop.ciphertext {
-        match extract_from_slice(data, idx) {
-            Ok(bytes) => {
+        let data = &data[slot_start as usize..];
+        let chunk = crypto::EncryptedHeader::from_bytes(data)
+            .and_then(|container| Ok((container, extract_from_slice(data, slot_index)?)));
+        match chunk {
+            Ok((container, bytes)) => {
                 eprintln!(
                     " {} /api/chunk/{}/{} -> {} bytes (in-memory) from {}",
                     console::style("→").dim(),
                     id,
                     idx,
                     bytes.len(),
-                    resolve_client_ip(&addr, &headers)
+                    resolve_client_ip(&state.proxies, &addr, &headers)
                 );
-                return (
-                    StatusCode::OK,
-                    [(header::CONTENT_TYPE, "application/octet-stream")],
-                    bytes,
-                )
-                    .into_response();
+                chunk_delivered(&state, &drop, session, idx, bytes.len() as u64);
+                return chunk_reply(&drop, &container, bytes);
             }
             Err(e) => {
                 return (
@@ -1381,16 +3718,22 @@ async fn serve_chunk(
         use tokio::io::{AsyncReadExt, AsyncSeekExt};
         match tokio::fs::File::open(path).await {
             Ok(mut f) => {
-                // Read header first
-                let mut hdr = vec![0u8; HEADER_SIZE];
-                if let Err(e) = f.read_exact(&mut hdr).await {
-                    eprintln!(
-                        " {} Failed to read header: {}",
-                        console::style("⚠").yellow(),
-                        e
-                    );
-                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-                }
+                // Read header first (the slot's, for a password drop)
+                let header = match f.seek(SeekFrom::Start(slot_start)).await {
+                    Ok(_) => read_header_async(&mut f).await,
+                    Err(e) => Err(e.into()),
+                };
+                let container = match header {
+                    Ok(container) => container,
+                    Err(e) => {
+                        eprintln!(
+                            " {} Failed to read header: {}",
+                            console::style("⚠").yellow(),
+                            e
+                        );
+                        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                    }
+                };
 
                 // Now iterate chunk-by-chunk until target
                 let mut index: u64 = 0;
@@ -1407,7 +3750,7 @@ async fn serve_chunk(
                         }
                     }
                     let chunk_len = u32::from_le_bytes(len_buf) as usize;
-                    if index == idx {
+                    if index == slot_index {
                         let mut buf = vec![0u8; chunk_len];
                         if let Err(e) = f.read_exact(&mut buf).await {
                             return (
@@ -1422,14 +3765,10 @@ async fn serve_chunk(
                             id,
                             idx,
                             buf.len(),
-                            resolve_client_ip(&addr, &headers)
+                            resolve_client_ip(&state.proxies, &addr, &headers)
                         );
-                        return (
-                            StatusCode::OK,
-                            [(header::CONTENT_TYPE, "application/octet-stream")],
-                            buf,
-                        )
-                            .into_response();
+                        chunk_delivered(&state, &drop, session, idx, buf.len() as u64);
+                        return chunk_reply(&drop, &container, buf);
                     } else {
                         // Seek forward by chunk_len bytes
                         if let Err(e) = f.seek(SeekFrom::Current(chunk_len as i64)).await {
@@ -1457,6 +3796,32 @@ async fn serve_chunk(
     StatusCode::INTERNAL_SERVER_ERROR.into_response()
 }
 
+/// A chunk's bytes, and what the client can't take from `/api/chunks`:
+/// the chunk count once it is known (a folder archived as it seals has
+/// none there yet) and, for a password drop, the nonce of the chunk's slot
+fn chunk_reply(
+    drop: &crate::store::Drop,
+    container: &crypto::EncryptedHeader,
+    bytes: Vec<u8>,
+) -> Response {
+    let mut response = (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/octet-stream")],
+        bytes,
+    )
+        .into_response();
+    let headers = response.headers_mut();
+    if !drop.size_open() {
+        headers.insert("x-total-chunks", drop.served_chunks().into());
+    }
+    if drop.slot_size > 0 {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let nonce = URL_SAFE_NO_PAD.encode(container.nonce);
+        headers.insert("x-slot-nonce", HeaderValue::try_from(nonce).unwrap());
+    }
+    response
+}
+
 async fn serve_web_asset(Path(path): Path<String>) -> Response {
     match WebAssets::get(&path) {
         Some(content) => {
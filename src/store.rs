@@ -1,8 +1,11 @@
+use crate::policy::{AccessPolicy, Denied};
 use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::sync::Mutex;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
 use tokio::time::{Duration, interval};
 
 pub struct Drop {
@@ -11,18 +14,218 @@ pub struct Drop {
     pub ciphertext: Option<Vec<u8>>,     // Some = in-memory, None = disk-backed
     pub encrypted_size: u64,             // Total size of encrypted data
     pub total_chunks: u64,               // Number of encrypted chunks (for streaming/resume)
+    pub slot_size: u64,                  // Bytes per deniable slot (0 = single container)
     pub recipient_envelopes: Vec<RecipientEnvelope>, // per-recipient CEK envelopes (base64)
     pub filename: String,
     pub mime_type: String,
     pub file_size: u64,
     pub created_at: chrono::DateTime<chrono::Utc>,
-    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: Mutex<chrono::DateTime<chrono::Utc>>, // Extendable from the dashboard
     pub max_downloads: u32,
-    pub download_count: AtomicU32,
     pub has_password: bool,
-    pub pinned_ip: Mutex<Option<String>>, // IP pinning: first downloader gets locked
+    pub digest: Option<String>, // Plaintext SHA-256 sealed under the key (crypto::seal_digest)
+    pub policy: AccessPolicy,
+    pub downloads: DownloadState,
+    pub sealing: Option<Sealing>, // Some = still being encrypted while served
 }
 
+/// Encryption of a drop that is already being served. Its container file
+/// grows chunk by chunk; fetch paths wait here for bytes not written yet.
+pub struct Sealing {
+    progress: tokio::sync::watch::Sender<Sealed>,
+    digest: OnceLock<String>,
+    chunk_size: usize,
+    slots: Option<crate::crypto::DeniableLayout>, // Some = two-slot password container
+    open: bool, // Size left open until sealed (a folder archived as it seals)
+}
+
+/// How far a [`Sealing`] drop has got
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sealed {
+    pub chunks: u64,    // Chunks on disk
+    pub bytes: u64,     // Container bytes on disk, header included
+    pub plaintext: u64, // Plaintext sealed, once done
+    pub done: bool,
+    pub failed: bool,
+}
+
+impl Default for Sealing {
+    fn default() -> Self {
+        Self::new(crate::crypto::CHUNK_SIZE)
+    }
+}
+
+impl Sealing {
+    /// A container cut into `chunk_size` chunks, header written
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            progress: tokio::sync::watch::Sender::new(Sealed {
+                bytes: crate::crypto::EncryptedHeader::len_for(chunk_size) as u64,
+                ..Default::default()
+            }),
+            digest: OnceLock::new(),
+            chunk_size,
+            slots: None,
+            open: false,
+        }
+    }
+
+    /// A container whose size is left open until sealing finishes. Its
+    /// header is written again then, so no byte of the stream is ready
+    /// before; chunks go out as they are sealed.
+    pub fn open(chunk_size: usize) -> Self {
+        Self {
+            progress: tokio::sync::watch::Sender::new(Sealed::default()),
+            open: true,
+            ..Self::new(chunk_size)
+        }
+    }
+
+    /// A deniable container laid out as `layout`, nothing written yet:
+    /// each slot writes its own header when it starts
+    pub fn deniable(layout: crate::crypto::DeniableLayout) -> Self {
+        Self {
+            progress: tokio::sync::watch::Sender::new(Sealed::default()),
+            digest: OnceLock::new(),
+            chunk_size: layout.chunk_size,
+            slots: Some(layout),
+            open: false,
+        }
+    }
+
+    pub fn progress(&self) -> Sealed {
+        *self.progress.borrow()
+    }
+
+    /// Chunks and container bytes on disk once `plaintext` bytes are sealed
+    fn on_disk(&self, plaintext: u64) -> (u64, u64) {
+        let sealed = |plaintext: u64| {
+            (
+                plaintext.div_ceil(self.chunk_size as u64),
+                crate::crypto::encrypted_len_with(plaintext, self.chunk_size),
+            )
+        };
+        let Some(layout) = self.slots else {
+            return sealed(plaintext);
+        };
+        // Finished slots, then the one in progress. At a slot boundary the
+        // next header may not be out yet, so it isn't counted.
+        let (slot, within) = (plaintext / layout.padded_len, plaintext % layout.padded_len);
+        let (chunks, bytes) = match within {
+            0 => (0, 0),
+            _ => sealed(within),
+        };
+        (
+            slot * layout.chunks + chunks,
+            slot * layout.slot_size + bytes,
+        )
+    }
+
+    /// The first `plaintext` bytes are encrypted and written
+    pub fn advance(&self, plaintext: u64) {
+        let (mut chunks, mut bytes) = self.on_disk(plaintext);
+        if self.open {
+            // Any chunk may turn out to be the last, and the header is not
+            // final yet: neither goes out before `finish`
+            (chunks, bytes) = (chunks.saturating_sub(1), 0);
+        }
+        self.progress.send_modify(|p| {
+            p.chunks = chunks;
+            p.bytes = bytes;
+        });
+    }
+
+    /// All `plaintext` bytes are on disk; `digest` is their sealed SHA-256
+    pub fn finish(&self, plaintext: u64, digest: Option<String>) {
+        if let Some(digest) = digest {
+            let _ = self.digest.set(digest);
+        }
+        let (chunks, bytes) = self.on_disk(plaintext);
+        self.progress.send_modify(|p| {
+            p.chunks = chunks;
+            p.bytes = bytes;
+            p.plaintext = plaintext;
+            p.done = true;
+        });
+    }
+
+    /// Encryption gave up; waiting fetches fail
+    pub fn fail(&self) {
+        self.progress.send_modify(|p| p.failed = true);
+    }
+
+    /// Wait until `ready` holds or encryption ends
+    async fn wait(&self, ready: impl Fn(&Sealed) -> bool) -> anyhow::Result<Sealed> {
+        let mut rx = self.progress.subscribe();
+        let sealed = *rx
+            .wait_for(|p| p.done || p.failed || ready(p))
+            .await
+            .map_err(|_| anyhow::anyhow!("Encryption stopped"))?;
+        if sealed.failed {
+            anyhow::bail!("Encryption failed");
+        }
+        Ok(sealed)
+    }
+}
+
+/// Live download bookkeeping for a drop
+#[derive(Default)]
+pub struct DownloadState {
+    pub completed: AtomicU32,             // Sessions that delivered everything
+    pub reserved: AtomicU32,              // Slots taken: completed + in flight (CAS-guarded)
+    pub admitted_ips: Mutex<Vec<IpAddr>>, // Distinct clients let in so far (first = pinned)
+    pub token_claimed_at: Mutex<Option<chrono::DateTime<chrono::Utc>>>, // First token use
+    pub token_spent: AtomicBool,          // A download completed on the one-time token
+    pub sessions: Mutex<HashMap<u64, DownloadSession>>, // In-flight downloads
+    pub blocked: Mutex<Vec<BlockedAttempt>>, // Most recent refusals, oldest first
+    next_session_id: AtomicU64,
+    retired: AtomicBool, // Burned, revoked or expired: no new sessions
+}
+
+/// A request the access policy turned away
+#[derive(Clone, Debug)]
+pub struct BlockedAttempt {
+    pub ip: IpAddr,
+    pub reason: Denied,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// How many blocked attempts a drop remembers
+const BLOCKED_HISTORY: usize = 100;
+
+/// Fetch path a download session uses
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transport {
+    Blob,
+    Chunks,
+    WebSocket,
+}
+
+impl Transport {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Transport::Blob => "HTTP",
+            Transport::Chunks => "chunked HTTP",
+            Transport::WebSocket => "WebSocket",
+        }
+    }
+}
+
+/// One recipient fetching the drop. Opened on first access and counted as
+/// a download exactly once — when every chunk (or the last byte) is delivered.
+pub struct DownloadSession {
+    pub client_ip: IpAddr,
+    pub transport: Transport,
+    pub token: Option<String>, // Chunked only: ties `/api/chunk` requests to the session
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub last_active: chrono::DateTime<chrono::Utc>,
+    pub chunks_served: HashSet<u64>,
+    pub bytes_served: u64,
+}
+
+/// Chunked sessions idle this long are treated as aborted
+pub const CHUNK_SESSION_IDLE: chrono::TimeDelta = chrono::TimeDelta::seconds(60);
+
 #[derive(Clone)]
 pub struct RecipientEnvelope {
     pub recipient_id: String,
@@ -30,44 +233,368 @@ pub struct RecipientEnvelope {
     pub encrypted_cek_b64: String,
 }
 
+impl Drop {
+    /// Run the access policy for `ip` and, if it passes, admit the client.
+    /// A one-time token is claimed by the first client that presents it,
+    /// and admits it for one download started within
+    /// [`crate::policy::TOKEN_CLAIM_TTL`]; otherwise up to `max_ips`
+    /// distinct clients are let in.
+    pub fn admit(
+        &self,
+        ip: IpAddr,
+        user_agent: Option<&str>,
+        token: Option<&str>,
+    ) -> Result<(), Denied> {
+        let result = self.check_admission(ip, user_agent, token);
+        if let Err(reason) = result {
+            let mut blocked = self.downloads.blocked.lock().unwrap();
+            if blocked.len() == BLOCKED_HISTORY {
+                blocked.remove(0);
+            }
+            blocked.push(BlockedAttempt {
+                ip,
+                reason,
+                at: chrono::Utc::now(),
+            });
+        }
+        result
+    }
+
+    fn check_admission(
+        &self,
+        ip: IpAddr,
+        user_agent: Option<&str>,
+        token: Option<&str>,
+    ) -> Result<(), Denied> {
+        self.policy.check_now(ip, user_agent, token)?;
+        if self.policy.token.is_some() {
+            return self.claim_token(ip);
+        }
+
+        let mut admitted = self.downloads.admitted_ips.lock().unwrap();
+        if admitted.contains(&ip) {
+            return Ok(());
+        }
+        if self.policy.max_ips != 0 && admitted.len() >= self.policy.max_ips as usize {
+            return Err(Denied::IpLimit);
+        }
+        admitted.push(ip);
+        Ok(())
+    }
+
+    /// The one-time token is the first client's. It keeps admitting that
+    /// client until a download on it completes or its claim runs out —
+    /// except for the requests of a download already under way.
+    fn claim_token(&self, ip: IpAddr) -> Result<(), Denied> {
+        let now = chrono::Utc::now();
+        let running = self
+            .downloads
+            .sessions
+            .lock()
+            .unwrap()
+            .values()
+            .any(|s| s.client_ip == ip);
+        let mut admitted = self.downloads.admitted_ips.lock().unwrap();
+        let mut claimed_at = self.downloads.token_claimed_at.lock().unwrap();
+        let Some(claimed) = *claimed_at else {
+            admitted.push(ip);
+            *claimed_at = Some(now);
+            return Ok(());
+        };
+        let fresh = !self.downloads.token_spent.load(Ordering::SeqCst)
+            && now < claimed + crate::policy::TOKEN_CLAIM_TTL;
+        if admitted.contains(&ip) && (running || fresh) {
+            return Ok(());
+        }
+        Err(Denied::TokenClaimed)
+    }
+
+    /// Whether the drop's size is not known yet: a folder archived as it
+    /// seals learns it once the archive ends. Until then the sizes below
+    /// read 0.
+    pub fn size_open(&self) -> bool {
+        self.sealing
+            .as_ref()
+            .is_some_and(|s| s.open && !s.progress().done)
+    }
+
+    /// Plaintext size
+    pub fn file_size(&self) -> u64 {
+        match &self.sealing {
+            Some(sealing) if sealing.open => sealing.progress().plaintext,
+            _ => self.file_size,
+        }
+    }
+
+    /// Size of the encrypted container
+    pub fn encrypted_size(&self) -> u64 {
+        match &self.sealing {
+            Some(sealing) if sealing.open => sealing.progress().bytes,
+            _ => self.encrypted_size,
+        }
+    }
+
+    /// Chunks in the container (in each slot, for a password drop)
+    pub fn total_chunks(&self) -> u64 {
+        match &self.sealing {
+            Some(sealing) if sealing.open => {
+                let progress = sealing.progress();
+                if progress.done { progress.chunks } else { 0 }
+            }
+            _ => self.total_chunks,
+        }
+    }
+
+    /// Chunks the chunk API serves: every slot's, for a password drop
+    pub fn served_chunks(&self) -> u64 {
+        match self.slot_size {
+            0 => self.total_chunks(),
+            _ => self.total_chunks() * crate::crypto::DENIABLE_SLOTS,
+        }
+    }
+
+    pub fn expires_at(&self) -> chrono::DateTime<chrono::Utc> {
+        *self.expires_at.lock().unwrap()
+    }
+
+    /// Push the expiry back (or forward, with a negative duration)
+    pub fn extend_expiry(&self, by: chrono::Duration) -> chrono::DateTime<chrono::Utc> {
+        let mut expires_at = self.expires_at.lock().unwrap();
+        *expires_at += by;
+        *expires_at
+    }
+
+    /// Atomically take a download slot. Fails once finished plus in-flight
+    /// sessions reach `max_downloads`, so parallel requests can't over-serve.
+    fn reserve_slot(&self) -> bool {
+        let reserved = &self.downloads.reserved;
+        if self.max_downloads == 0 {
+            reserved.fetch_add(1, Ordering::SeqCst);
+            return true;
+        }
+        reserved
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < self.max_downloads).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
+    fn insert_session(
+        &self,
+        sessions: &mut HashMap<u64, DownloadSession>,
+        client_ip: IpAddr,
+        transport: Transport,
+    ) -> Option<u64> {
+        if self.downloads.retired.load(Ordering::SeqCst) || !self.reserve_slot() {
+            return None;
+        }
+        let id = self
+            .downloads
+            .next_session_id
+            .fetch_add(1, Ordering::SeqCst);
+        let now = chrono::Utc::now();
+        sessions.insert(
+            id,
+            DownloadSession {
+                client_ip,
+                transport,
+                token: None,
+                started_at: now,
+                last_active: now,
+                chunks_served: HashSet::new(),
+                bytes_served: 0,
+            },
+        );
+        Some(id)
+    }
+
+    /// Open a fresh session (blob and WebSocket: one request = one session).
+    /// None when every download slot is taken.
+    pub fn open_session(&self, client_ip: IpAddr, transport: Transport) -> Option<u64> {
+        let mut sessions = self.downloads.sessions.lock().unwrap();
+        self.insert_session(&mut sessions, client_ip, transport)
+    }
+
+    /// Open a chunked session and issue its token. Chunk requests are
+    /// independent, so the token is what ties them together.
+    pub fn open_chunk_session(&self, client_ip: IpAddr) -> Option<(u64, String)> {
+        let mut sessions = self.downloads.sessions.lock().unwrap();
+        let id = self.insert_session(&mut sessions, client_ip, Transport::Chunks)?;
+        let mut raw = [0u8; 16];
+        rand::fill(&mut raw);
+        let token: String = raw.iter().map(|b| format!("{:02x}", b)).collect();
+        sessions.get_mut(&id)?.token = Some(token.clone());
+        Some((id, token))
+    }
+
+    /// The chunked session `token` was issued for, if it is still open and
+    /// belongs to this client
+    pub fn chunk_session(&self, client_ip: IpAddr, token: &str) -> Option<u64> {
+        self.downloads
+            .sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, s)| s.client_ip == client_ip && s.token.as_deref() == Some(token))
+            .map(|(id, _)| *id)
+    }
+
+    /// Note a served chunk. Returns true once every chunk has been served.
+    pub fn record_chunk(&self, session: u64, index: u64, bytes: u64) -> bool {
+        let mut sessions = self.downloads.sessions.lock().unwrap();
+        let Some(s) = sessions.get_mut(&session) else {
+            return false;
+        };
+        if s.chunks_served.insert(index) {
+            s.bytes_served += bytes;
+        }
+        s.last_active = chrono::Utc::now();
+        s.chunks_served.len() as u64 == self.served_chunks()
+    }
+
+    /// Add streamed bytes to a session
+    pub fn record_bytes(&self, session: u64, bytes: u64) {
+        if let Some(s) = self.downloads.sessions.lock().unwrap().get_mut(&session) {
+            s.bytes_served += bytes;
+            s.last_active = chrono::Utc::now();
+        }
+    }
+
+    /// Close a session that delivered everything and count the download.
+    /// Returns (current_count, should_delete), or None if the session was
+    /// already closed — so each session counts at most once.
+    pub fn complete_session(&self, session: u64) -> Option<(u32, bool)> {
+        self.downloads.sessions.lock().unwrap().remove(&session)?;
+        self.downloads.token_spent.store(true, Ordering::SeqCst);
+        let count = self.downloads.completed.fetch_add(1, Ordering::SeqCst) + 1;
+        let should_delete = self.max_downloads > 0 && count >= self.max_downloads;
+        Some((count, should_delete))
+    }
+
+    /// Drop a session that ended before delivering everything and hand its
+    /// slot back
+    pub fn abort_session(&self, session: u64) -> Option<DownloadSession> {
+        let aborted = self.downloads.sessions.lock().unwrap().remove(&session)?;
+        self.downloads.reserved.fetch_sub(1, Ordering::SeqCst);
+        Some(aborted)
+    }
+
+    /// Abort chunked sessions that went quiet. Returns the reaped sessions.
+    pub fn reap_idle_sessions(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<(u64, DownloadSession)> {
+        let idle: Vec<u64> = self
+            .downloads
+            .sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, s)| {
+                s.transport == Transport::Chunks && now - s.last_active > CHUNK_SESSION_IDLE
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        idle.into_iter()
+            .filter_map(|id| self.abort_session(id).map(|s| (id, s)))
+            .collect()
+    }
+
+    /// Transfers still running: streams in progress and chunked sessions
+    /// that have not fetched every chunk
+    pub fn in_flight(&self) -> usize {
+        self.downloads.sessions.lock().unwrap().len()
+    }
+
+    /// Plaintext digest sealed under the key; a sealing drop has one once
+    /// encryption finishes
+    pub fn sealed_digest(&self) -> Option<String> {
+        self.digest
+            .clone()
+            .or_else(|| self.sealing.as_ref().and_then(|s| s.digest.get().cloned()))
+    }
+
+    /// Wait until chunk `index` is on disk (at once unless still sealing)
+    pub async fn chunk_ready(&self, index: u64) -> anyhow::Result<()> {
+        if let Some(sealing) = &self.sealing {
+            sealing.wait(|p| p.chunks > index).await?;
+        }
+        Ok(())
+    }
+
+    /// Wait until the container has more than `offset` bytes on disk.
+    /// Returns how many it has. A drop whose size is still open has none
+    /// until it is sealed.
+    pub async fn bytes_ready(&self, offset: u64) -> anyhow::Result<u64> {
+        match &self.sealing {
+            Some(sealing) if self.size_open() || offset < self.encrypted_size() => Ok(sealing
+                .wait(|p| p.bytes > offset)
+                .await?
+                .bytes
+                .min(self.encrypted_size())),
+            _ => Ok(self.encrypted_size()),
+        }
+    }
+
+    /// Downloads still available to new sessions (None = unlimited)
+    pub fn remaining_downloads(&self) -> Option<u32> {
+        (self.max_downloads > 0).then(|| {
+            self.max_downloads
+                .saturating_sub(self.downloads.reserved.load(Ordering::SeqCst))
+        })
+    }
+}
+
 impl std::ops::Drop for Drop {
     fn drop(&mut self) {
         // Securely delete the encrypted temp file when the drop is removed
-        if let Some(ref path) = self.encrypted_path {
-            if path.exists() {
-                // Best-effort: overwrite with zeros before removing
-                if let Ok(file) = std::fs::OpenOptions::new().write(true).open(path) {
-                    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
-                    let zeros = vec![0u8; 64 * 1024];
-                    let mut writer = std::io::BufWriter::new(file);
-                    let mut remaining = size;
-                    while remaining > 0 {
-                        let to_write = remaining.min(zeros.len() as u64) as usize;
-                        if std::io::Write::write_all(&mut writer, &zeros[..to_write]).is_err() {
-                            break;
-                        }
-                        remaining -= to_write as u64;
+        if let Some(ref path) = self.encrypted_path
+            && path.exists()
+        {
+            // Best-effort: overwrite with zeros before removing
+            if let Ok(file) = std::fs::OpenOptions::new().write(true).open(path) {
+                let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+                let zeros = vec![0u8; 64 * 1024];
+                let mut writer = std::io::BufWriter::new(file);
+                let mut remaining = size;
+                while remaining > 0 {
+                    let to_write = remaining.min(zeros.len() as u64) as usize;
+                    if std::io::Write::write_all(&mut writer, &zeros[..to_write]).is_err() {
+                        break;
                     }
-                    let _ = std::io::Write::flush(&mut writer);
+                    remaining -= to_write as u64;
                 }
-                let _ = std::fs::remove_file(path);
+                let _ = std::io::Write::flush(&mut writer);
             }
+            let _ = std::fs::remove_file(path);
         }
         // In-memory ciphertext is dropped automatically (Vec deallocated)
     }
 }
 
+/// A drop that was downloaded and destroyed
+struct Burned {
+    at: chrono::DateTime<chrono::Utc>,
+    /// A page that loaded while the drop was still sealing fetches the
+    /// digest after the last byte — by then the drop may be gone
+    digest: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct BlobStore {
     drops: Arc<DashMap<String, Arc<Drop>>>,
-    burned: Arc<DashMap<String, chrono::DateTime<chrono::Utc>>>,
-    on_expire: Arc<dyn Fn() + Send + Sync>,
+    /// Removed drops kept until their in-flight transfers finish; the
+    /// ciphertext is wiped once the last one lets go
+    draining: Arc<DashMap<String, Arc<Drop>>>,
+    burned: Arc<DashMap<String, Burned>>,
+    on_expire: Arc<dyn Fn(&str) + Send + Sync>,
 }
 
 impl BlobStore {
-    pub fn new(on_expire: impl Fn() + Send + Sync + 'static) -> Self {
+    pub fn new(on_expire: impl Fn(&str) + Send + Sync + 'static) -> Self {
         Self {
             drops: Arc::new(DashMap::new()),
+            draining: Arc::new(DashMap::new()),
             burned: Arc::new(DashMap::new()),
             on_expire: Arc::new(on_expire),
         }
@@ -83,13 +610,29 @@ impl BlobStore {
         self.drops.get(id).map(|d| d.value().clone())
     }
 
+    /// The drop an in-flight chunked session belongs to, even if it was
+    /// removed since the session started
+    pub fn get_for_session(&self, id: &str) -> Option<Arc<Drop>> {
+        self.get(id)
+            .or_else(|| self.draining.get(id).map(|d| d.value().clone()))
+    }
+
+    /// Take a drop out of service. Transfers already running may finish;
+    /// secure deletion waits for them.
     pub fn remove(&self, id: &str) -> bool {
-        let removed = self.drops.remove(id).is_some();
-        if removed {
-            // Track burned drops so late visitors see "already downloaded"
-            self.burned.insert(id.to_string(), chrono::Utc::now());
-        }
-        removed
+        let Some((id, drop)) = self.drops.remove(id) else {
+            return false;
+        };
+        // Track burned drops so late visitors see "already downloaded"
+        self.burned.insert(
+            id.clone(),
+            Burned {
+                at: chrono::Utc::now(),
+                digest: drop.sealed_digest(),
+            },
+        );
+        retire(&self.draining, id, drop);
+        true
     }
 
     /// Check if a drop was already downloaded and destroyed
@@ -97,21 +640,39 @@ impl BlobStore {
         self.burned.contains_key(id)
     }
 
+    /// Sealed plaintext digest of a burned drop
+    pub fn burned_digest(&self, id: &str) -> Option<String> {
+        self.burned.get(id).and_then(|b| b.digest.clone())
+    }
+
+    /// Snapshot of every live drop
+    pub fn drops(&self) -> Vec<Arc<Drop>> {
+        self.drops.iter().map(|d| d.value().clone()).collect()
+    }
+
+    /// Live drops plus removed ones whose transfers are still running
+    pub fn all(&self) -> Vec<Arc<Drop>> {
+        self.drops
+            .iter()
+            .chain(self.draining.iter())
+            .map(|d| d.value().clone())
+            .collect()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.drops.is_empty()
     }
 
-    /// Increment download count. Returns (current_count, should_delete)
-    pub fn record_download(&self, id: &str) -> Option<(u32, bool)> {
-        let drop = self.get(id)?;
-        let count = drop.download_count.fetch_add(1, Ordering::SeqCst) + 1;
-        let should_delete = drop.max_downloads > 0 && count >= drop.max_downloads;
-        Some((count, should_delete))
+    /// Transfers running across every drop, removed ones included
+    pub fn in_flight(&self) -> usize {
+        self.all().iter().map(|d| d.in_flight()).sum()
     }
 
-    /// Background task: evict expired drops every 5 seconds
+    /// Background task: every 5 seconds, evict expired drops and release
+    /// removed ones whose transfers have finished
     pub fn spawn_reaper(&self) {
         let drops = self.drops.clone();
+        let draining = self.draining.clone();
         let burned = self.burned.clone();
         let on_expire = self.on_expire.clone();
         tokio::spawn(async move {
@@ -119,14 +680,29 @@ impl BlobStore {
             loop {
                 tick.tick().await;
                 let now = chrono::Utc::now();
-                let before = drops.len();
-                drops.retain(|_, drop| drop.expires_at > now);
-                if drops.len() < before {
-                    (on_expire)();
+                let expired: Vec<String> = drops
+                    .iter()
+                    .filter(|d| d.expires_at() <= now)
+                    .map(|d| d.key().clone())
+                    .collect();
+                for id in expired {
+                    if let Some((id, drop)) = drops.remove(&id) {
+                        (on_expire)(&id);
+                        retire(&draining, id, drop);
+                    }
                 }
+                draining.retain(|_, drop| drop.in_flight() > 0);
                 // Also clean burned entries older than 1 hour (no need to keep forever)
-                burned.retain(|_, burned_at| now.signed_duration_since(*burned_at).num_hours() < 1);
+                burned.retain(|_, b| now.signed_duration_since(b.at).num_hours() < 1);
             }
         });
     }
 }
+
+/// Close a removed drop to new sessions and park it while transfers finish
+fn retire(draining: &DashMap<String, Arc<Drop>>, id: String, drop: Arc<Drop>) {
+    drop.downloads.retired.store(true, Ordering::SeqCst);
+    if drop.in_flight() > 0 {
+        draining.insert(id, drop);
+    }
+}
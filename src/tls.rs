@@ -0,0 +1,75 @@
+use axum_server::tls_rustls::RustlsConfig;
+use sha2::{Digest, Sha256};
+
+/// Ephemeral self-signed certificate for `--tls`.
+/// Generated fresh on every run and never written to disk.
+pub struct TlsIdentity {
+    pub cert_der: Vec<u8>,
+    key_der: Vec<u8>,
+    /// Lowercase hex SHA-256 of the DER certificate (pinned via `?fp=` in links)
+    pub fingerprint: String,
+}
+
+impl TlsIdentity {
+    /// Generate a certificate valid for the given hostnames / IPs
+    pub fn generate(hosts: Vec<String>) -> anyhow::Result<Self> {
+        let certified = rcgen::generate_simple_self_signed(hosts)
+            .map_err(|e| anyhow::anyhow!("Certificate generation failed: {}", e))?;
+        let cert_der = certified.cert.der().to_vec();
+        let key_der = certified.signing_key.serialize_der();
+        let fingerprint = fingerprint(&cert_der);
+        Ok(Self {
+            cert_der,
+            key_der,
+            fingerprint,
+        })
+    }
+
+    /// Fingerprint in the colon-separated uppercase form browsers display
+    pub fn display_fingerprint(&self) -> String {
+        self.fingerprint
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| String::from_utf8_lossy(pair).to_uppercase())
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    /// Build the rustls server config used by axum-server
+    pub async fn rustls_config(&self) -> anyhow::Result<RustlsConfig> {
+        // Ring is already in the tree; make it the process-wide provider.
+        // Ignore the error if another component installed one first.
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        RustlsConfig::from_der(vec![self.cert_der.clone()], self.key_der.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!("TLS config error: {}", e))
+    }
+}
+
+impl Drop for TlsIdentity {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+        self.key_der.zeroize();
+    }
+}
+
+/// Lowercase hex SHA-256 of a DER-encoded certificate
+pub fn fingerprint(cert_der: &[u8]) -> String {
+    Sha256::digest(cert_der)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Append the `fp` pin to a link's query, before the `#fragment`
+pub fn pin_url(url: &str, fingerprint: &str) -> String {
+    let (base, fragment) = match url.split_once('#') {
+        Some((base, fragment)) => (base, Some(fragment)),
+        None => (url, None),
+    };
+    let sep = if base.contains('?') { '&' } else { '?' };
+    match fragment {
+        Some(fragment) => format!("{}{}fp={}#{}", base, sep, fingerprint, fragment),
+        None => format!("{}{}fp={}", base, sep, fingerprint),
+    }
+}
@@ -0,0 +1,590 @@
+//! Full-screen dashboard for `ded send --tui`.
+//!
+//! Runs on its own thread next to the server, redrawing a few times a
+//! second from `BlobStore`. While it is up, stderr (all the usual
+//! `eprintln!` logging) is captured into the log pane instead of tearing
+//! the screen.
+
+use crate::server::AppState;
+use crate::store::Drop;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState, Wrap};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Lines of captured log kept for the log pane
+const LOG_LINES: usize = 200;
+
+/// Share links for one drop
+#[derive(Clone, Debug, Default)]
+pub struct DropLinks {
+    pub lan: String,
+    pub localhost: String,
+    pub tunnel: Option<String>,
+    pub onion: Option<String>,
+}
+
+impl DropLinks {
+    /// The link worth sharing: the tunnel when it is up, else the LAN one
+    pub fn primary(&self) -> &str {
+        self.tunnel.as_deref().unwrap_or(&self.lan)
+    }
+}
+
+/// Services running next to the server
+#[derive(Clone, Debug, Default)]
+pub struct Status {
+    pub tunnel: bool,
+    pub tor: bool,
+    /// Certificate fingerprint when serving `--tls`
+    pub tls: Option<String>,
+}
+
+/// Handle to the running dashboard thread
+pub struct Dashboard {
+    stop: Arc<AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Dashboard {
+    /// Take over the terminal and start drawing
+    pub fn spawn(state: Arc<AppState>, links: HashMap<String, DropLinks>, status: Status) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = std::thread::spawn(move || {
+            let log = Arc::new(Mutex::new(VecDeque::new()));
+            let capture = capture::StderrCapture::start(log.clone());
+            let mut app = App {
+                state,
+                links,
+                status,
+                log,
+                table: TableState::default().with_selected(Some(0)),
+                flash: None,
+                confirm_revoke: None,
+            };
+            let mut terminal = ratatui::init();
+            while !thread_stop.load(Ordering::SeqCst) {
+                if terminal.draw(|frame| app.render(frame)).is_err() {
+                    break;
+                }
+                match event::poll(Duration::from_millis(250)) {
+                    Ok(true) => {
+                        if let Ok(Event::Key(key)) = event::read()
+                            && key.kind == KeyEventKind::Press
+                            && app.on_key(key.code, key.modifiers)
+                        {
+                            break;
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(_) => break,
+                }
+            }
+            ratatui::restore();
+            drop(capture);
+        });
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Give the terminal back (waits for the dashboard thread)
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct App {
+    state: Arc<AppState>,
+    links: HashMap<String, DropLinks>,
+    status: Status,
+    log: Arc<Mutex<VecDeque<String>>>,
+    table: TableState,
+    flash: Option<(String, Instant)>,
+    confirm_revoke: Option<String>,
+}
+
+impl App {
+    /// Live drops, oldest first
+    fn drops(&self) -> Vec<Arc<Drop>> {
+        let mut drops = self.state.store.drops();
+        drops.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        drops
+    }
+
+    fn selected(&self, drops: &[Arc<Drop>]) -> Option<Arc<Drop>> {
+        let i = self.table.selected()?.min(drops.len().checked_sub(1)?);
+        drops.get(i).cloned()
+    }
+
+    fn flash(&mut self, msg: String) {
+        self.flash = Some((msg, Instant::now()));
+    }
+
+    /// Handle a key press. Returns true when the dashboard should close.
+    fn on_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let drops = self.drops();
+        let selected = self.selected(&drops);
+
+        if let Some(id) = self.confirm_revoke.take() {
+            if matches!(code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                self.revoke(&id);
+            } else {
+                self.flash("Revoke cancelled".to_string());
+            }
+            return false;
+        }
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.state.shutdown.notify_one();
+                return true;
+            }
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.state.shutdown.notify_one();
+                return true;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let next = self.table.selected().map_or(0, |i| i + 1);
+                self.table
+                    .select(Some(next.min(drops.len().saturating_sub(1))));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let prev = self.table.selected().map_or(0, |i| i.saturating_sub(1));
+                self.table.select(Some(prev));
+            }
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                if let Some(drop) = selected {
+                    let by = if code == KeyCode::Char('E') {
+                        chrono::Duration::hours(1)
+                    } else {
+                        chrono::Duration::minutes(10)
+                    };
+                    let until = drop.extend_expiry(by);
+                    self.flash(format!(
+                        "Extended {} to {}",
+                        drop.id,
+                        until.with_timezone(&chrono::Local).format("%H:%M:%S")
+                    ));
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some(drop) = selected {
+                    self.confirm_revoke = Some(drop.id.clone());
+                }
+            }
+            KeyCode::Char('c') => {
+                if let Some(link) = selected.and_then(|d| self.links.get(&d.id)) {
+                    copy_to_clipboard(link.primary());
+                    self.flash("Link copied to clipboard".to_string());
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn revoke(&mut self, id: &str) {
+        if self.state.store.remove(id) {
+            eprintln!(" 🗑 Drop {} revoked from the dashboard", id);
+            self.state.events.emit(crate::events::Event::Burned {
+                drop_id: id.to_string(),
+                reason: "revoked",
+            });
+            self.flash(format!("Revoked {}", id));
+        }
+        if self.state.store.is_empty() {
+            self.state.shutdown.notify_one();
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let drops = self.drops();
+        if let Some(i) = self.table.selected()
+            && i >= drops.len()
+        {
+            self.table.select(Some(drops.len().saturating_sub(1)));
+        }
+        let selected = self.selected(&drops);
+
+        let [header, table, body, log, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(drops.len().clamp(1, 6) as u16 + 3),
+            Constraint::Min(10),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(self.header(drops.len()), header);
+        self.render_table(frame, table, &drops);
+        self.render_body(frame, body, selected.as_deref());
+        frame.render_widget(self.log_pane(log.height), log);
+        frame.render_widget(self.footer(), footer);
+    }
+
+    fn header(&self, drops: usize) -> Line<'static> {
+        let on_off = |on: bool| {
+            if on {
+                Span::styled("● up", Style::new().green())
+            } else {
+                Span::styled("○ off", Style::new().dark_gray())
+            }
+        };
+        let mut spans = vec![
+            Span::styled(" deadrop ", Style::new().black().on_green().bold()),
+            Span::raw(format!(
+                "  {} drop{}  ",
+                drops,
+                if drops == 1 { "" } else { "s" }
+            )),
+            Span::raw("Tunnel "),
+            on_off(self.status.tunnel),
+            Span::raw("  Tor "),
+            on_off(self.status.tor),
+        ];
+        if let Some(fp) = &self.status.tls {
+            spans.push(Span::raw("  TLS "));
+            spans.push(Span::styled(fp.clone(), Style::new().cyan()));
+        }
+        Line::from(spans)
+    }
+
+    fn render_table(&mut self, frame: &mut Frame, area: Rect, drops: &[Arc<Drop>]) {
+        let now = chrono::Utc::now();
+        let rows = drops.iter().map(|drop| {
+            let completed = drop.downloads.completed.load(Ordering::SeqCst);
+            let downloads = match drop.max_downloads {
+                0 => format!("{} used · unlimited", completed),
+                max => format!(
+                    "{}/{} used · {} left",
+                    completed,
+                    max,
+                    drop.remaining_downloads().unwrap_or(0)
+                ),
+            };
+            let active = drop.downloads.sessions.lock().unwrap().len();
+            let blocked = drop.downloads.blocked.lock().unwrap().len();
+            Row::new(vec![
+                drop.id.clone(),
+                drop.filename.clone(),
+                format_remaining(drop.expires_at() - now),
+                downloads,
+                active.to_string(),
+                blocked.to_string(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(17),
+                Constraint::Fill(1),
+                Constraint::Length(11),
+                Constraint::Length(22),
+                Constraint::Length(7),
+                Constraint::Length(8),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "ID",
+                "File",
+                "Expires in",
+                "Downloads",
+                "Active",
+                "Blocked",
+            ])
+            .style(Style::new().bold()),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(" Drops "));
+        frame.render_stateful_widget(table, area, &mut self.table);
+    }
+
+    fn render_body(&self, frame: &mut Frame, area: Rect, drop: Option<&Drop>) {
+        let Some(drop) = drop else {
+            frame.render_widget(
+                Paragraph::new("No live drops.").block(Block::bordered()),
+                area,
+            );
+            return;
+        };
+        let links = self.links.get(&drop.id);
+        let qr = links.and_then(|l| crate::qr::render_qr(l.primary()).ok());
+        let qr_width = qr
+            .as_ref()
+            .and_then(|q| q.lines().map(|l| l.chars().count()).max())
+            .unwrap_or(0) as u16
+            + 2;
+        let qr_height = qr.as_ref().map_or(0, |q| q.lines().count()) as u16 + 2;
+
+        // Only show the QR when it fits next to the details
+        let show_qr = qr.is_some() && area.width >= qr_width + 50 && area.height >= qr_height;
+        let (details_area, qr_area) = if show_qr {
+            let [details, qr] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(qr_width)]).areas(area);
+            (details, Some(qr))
+        } else {
+            (area, None)
+        };
+
+        let [links_area, sessions_area, blocked_area] = Layout::vertical([
+            Constraint::Length(6),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ])
+        .areas(details_area);
+
+        let mut link_lines = Vec::new();
+        if let Some(links) = links {
+            let mut push = |label: &'static str, url: &str| {
+                link_lines.push(Line::from(vec![
+                    Span::styled(format!("{:<10}", label), Style::new().dark_gray()),
+                    Span::styled(url.to_string(), Style::new().cyan()),
+                ]));
+            };
+            push("LAN", &links.lan);
+            push("Localhost", &links.localhost);
+            if let Some(tunnel) = &links.tunnel {
+                push("Tunnel", tunnel);
+            }
+            if let Some(onion) = &links.onion {
+                push("Tor", onion);
+            }
+        }
+        frame.render_widget(
+            Paragraph::new(link_lines)
+                .block(Block::bordered().title(format!(" {} · links ", drop.filename))),
+            links_area,
+        );
+
+        let now = chrono::Utc::now();
+        let sessions = drop.downloads.sessions.lock().unwrap();
+        let session_rows = sessions.values().map(|s| {
+            let total = drop.encrypted_size().max(1);
+            // A folder still being archived has no total to measure against
+            let served = match drop.size_open() {
+                true => bytesize::ByteSize::b(s.bytes_served).to_string(),
+                false => format!(
+                    "{} ({}%)",
+                    bytesize::ByteSize::b(s.bytes_served),
+                    (s.bytes_served * 100 / total).min(100)
+                ),
+            };
+            Row::new(vec![
+                s.client_ip.to_string(),
+                s.transport.label().to_string(),
+                served,
+                format_elapsed(now - s.started_at),
+            ])
+        });
+        frame.render_widget(
+            Table::new(
+                session_rows,
+                [
+                    Constraint::Length(40),
+                    Constraint::Length(13),
+                    Constraint::Length(20),
+                    Constraint::Fill(1),
+                ],
+            )
+            .header(Row::new(vec!["Client", "Transport", "Sent", "For"]).style(Style::new().bold()))
+            .block(Block::bordered().title(format!(" Active connections ({}) ", sessions.len()))),
+            sessions_area,
+        );
+        std::mem::drop(sessions);
+
+        let blocked = drop.downloads.blocked.lock().unwrap();
+        let blocked_rows = blocked.iter().rev().map(|b| {
+            Row::new(vec![
+                b.at.with_timezone(&chrono::Local)
+                    .format("%H:%M:%S")
+                    .to_string(),
+                b.ip.to_string(),
+                b.reason.reason().to_string(),
+            ])
+        });
+        frame.render_widget(
+            Table::new(
+                blocked_rows,
+                [
+                    Constraint::Length(9),
+                    Constraint::Length(40),
+                    Constraint::Fill(1),
+                ],
+            )
+            .block(
+                Block::bordered()
+                    .title(format!(" Blocked attempts ({}) ", blocked.len()))
+                    .border_style(if blocked.is_empty() {
+                        Style::new()
+                    } else {
+                        Style::new().red()
+                    }),
+            ),
+            blocked_area,
+        );
+
+        if let (Some(qr_area), Some(qr)) = (qr_area, qr) {
+            frame.render_widget(
+                Paragraph::new(qr).block(Block::bordered().title(" Scan ")),
+                qr_area,
+            );
+        }
+    }
+
+    fn log_pane(&self, height: u16) -> Paragraph<'static> {
+        let log = self.log.lock().unwrap();
+        let visible = height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = log
+            .iter()
+            .skip(log.len().saturating_sub(visible))
+            .map(|l| Line::from(l.clone()))
+            .collect();
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(" Log "))
+    }
+
+    fn footer(&self) -> Line<'static> {
+        if let Some(id) = &self.confirm_revoke {
+            return Line::from(vec![Span::styled(
+                format!(" Revoke {}? It is wiped immediately. [y/N] ", id),
+                Style::new().black().on_red().bold(),
+            )]);
+        }
+        if let Some((msg, at)) = &self.flash
+            && at.elapsed() < Duration::from_secs(3)
+        {
+            return Line::from(Span::styled(format!(" {} ", msg), Style::new().green()));
+        }
+        let key = |k: &'static str| Span::styled(k, Style::new().bold().cyan());
+        Line::from(vec![
+            Span::raw(" "),
+            key("↑↓"),
+            Span::raw(" select  "),
+            key("e"),
+            Span::raw(" +10m  "),
+            key("E"),
+            Span::raw(" +1h  "),
+            key("r"),
+            Span::raw(" revoke  "),
+            key("c"),
+            Span::raw(" copy link  "),
+            key("q"),
+            Span::raw(" quit"),
+        ])
+    }
+}
+
+/// "1h 02m", "9m 12s", "expired"
+fn format_remaining(left: chrono::Duration) -> String {
+    let secs = left.num_seconds();
+    if secs <= 0 {
+        return "expired".to_string();
+    }
+    match secs {
+        s if s >= 86_400 => format!("{}d {:02}h", s / 86_400, s % 86_400 / 3600),
+        s if s >= 3600 => format!("{}h {:02}m", s / 3600, s % 3600 / 60),
+        s => format!("{}m {:02}s", s / 60, s % 60),
+    }
+}
+
+fn format_elapsed(elapsed: chrono::Duration) -> String {
+    let secs = elapsed.num_seconds().max(0);
+    format!("{}m {:02}s", secs / 60, secs % 60)
+}
+
+/// Copy via the OSC 52 escape sequence — works in most terminals, over SSH too
+fn copy_to_clipboard(text: &str) {
+    use base64::engine::{Engine, general_purpose::STANDARD};
+    use std::io::Write;
+    let mut stdout = std::io::stdout();
+    let _ = write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text));
+    let _ = stdout.flush();
+}
+
+#[cfg(unix)]
+mod capture {
+    use std::collections::VecDeque;
+    use std::io::BufRead;
+    use std::os::fd::FromRawFd;
+    use std::sync::{Arc, Mutex};
+
+    /// Points fd 2 at a pipe for as long as it lives and feeds each line
+    /// into the log pane. Dropping it puts the real stderr back.
+    pub struct StderrCapture {
+        saved: libc::c_int,
+    }
+
+    impl StderrCapture {
+        pub fn start(log: Arc<Mutex<VecDeque<String>>>) -> Option<Self> {
+            let mut fds = [0 as libc::c_int; 2];
+            // SAFETY: plain fd juggling; every return value is checked
+            unsafe {
+                if libc::pipe(fds.as_mut_ptr()) != 0 {
+                    return None;
+                }
+                let saved = libc::dup(libc::STDERR_FILENO);
+                if saved < 0 || libc::dup2(fds[1], libc::STDERR_FILENO) < 0 {
+                    libc::close(fds[0]);
+                    libc::close(fds[1]);
+                    return None;
+                }
+                libc::close(fds[1]);
+                let reader = std::fs::File::from_raw_fd(fds[0]);
+                std::thread::spawn(move || {
+                    for line in std::io::BufReader::new(reader).lines() {
+                        let Ok(line) = line else { break };
+                        let line = console::strip_ansi_codes(&line).trim_end().to_string();
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+                        let mut log = log.lock().unwrap();
+                        if log.len() == super::LOG_LINES {
+                            log.pop_front();
+                        }
+                        log.push_back(line);
+                    }
+                });
+                Some(Self { saved })
+            }
+        }
+    }
+
+    impl Drop for StderrCapture {
+        fn drop(&mut self) {
+            // SAFETY: restores the descriptor saved in `start`
+            unsafe {
+                libc::dup2(self.saved, libc::STDERR_FILENO);
+                libc::close(self.saved);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod capture {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    /// No fd redirection off Unix; log output may draw over the dashboard
+    pub struct StderrCapture;
+
+    impl StderrCapture {
+        pub fn start(_log: Arc<Mutex<VecDeque<String>>>) -> Option<Self> {
+            None
+        }
+    }
+}
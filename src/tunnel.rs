@@ -6,9 +6,18 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 pub struct CloudflareTunnel {
     pub public_url: String,
     child: Option<tokio::process::Child>,
+    /// Loopback listener only cloudflared is pointed at. Its
+    /// `CF-Connecting-IP` is believed there and nowhere else — Tor and
+    /// other local clients reach the main port from 127.0.0.1 too.
+    origin: std::net::TcpListener,
 }
 
 impl CloudflareTunnel {
+    /// The origin listener, to serve the app on alongside the main port
+    pub fn origin(&self) -> std::io::Result<std::net::TcpListener> {
+        self.origin.try_clone()
+    }
+
     /// Build a full public URL with path and key fragment
     pub fn tunnel_url(&self, path: &str, key_fragment: &str) -> String {
         format!(
@@ -30,8 +39,9 @@ impl Drop for CloudflareTunnel {
 }
 
 /// Try to start a tunnel. Returns Some(tunnel) on success, None on failure (with a warning).
-pub async fn try_start_tunnel(local_port: u16) -> Option<CloudflareTunnel> {
-    match start_tunnel(local_port).await {
+/// `origin_tls` makes cloudflared talk HTTPS to a `--tls` origin.
+pub async fn try_start_tunnel(origin_tls: bool) -> Option<CloudflareTunnel> {
+    match start_tunnel(origin_tls).await {
         Ok(t) => Some(t),
         Err(e) => {
             eprintln!(
@@ -61,13 +71,13 @@ async fn resolve_cloudflared() -> anyhow::Result<PathBuf> {
     };
 
     // ── Tier 1: Bundled next to ded executable ──
-    if let Ok(exe_path) = std::env::current_exe() {
-        if let Some(exe_dir) = exe_path.parent() {
-            let bundled = exe_dir.join(bin_name);
-            if bundled.exists() {
-                eprintln!(" {} Using bundled cloudflared", console::style("☁").dim());
-                return Ok(bundled);
-            }
+    if let Ok(exe_path) = std::env::current_exe()
+        && let Some(exe_dir) = exe_path.parent()
+    {
+        let bundled = exe_dir.join(bin_name);
+        if bundled.exists() {
+            eprintln!(" {} Using bundled cloudflared", console::style("☁").dim());
+            return Ok(bundled);
         }
     }
 
@@ -208,20 +218,36 @@ fn get_cloudflared_download_url() -> anyhow::Result<String> {
     Ok(format!("{}/{}", base, filename))
 }
 
-/// Start a Cloudflare quick tunnel that forwards to the given local port.
-pub async fn start_tunnel(local_port: u16) -> anyhow::Result<CloudflareTunnel> {
+/// Start a Cloudflare quick tunnel that forwards to a dedicated loopback
+/// listener (see [`CloudflareTunnel::origin`]).
+pub async fn start_tunnel(origin_tls: bool) -> anyhow::Result<CloudflareTunnel> {
     eprintln!(
         " {} Starting Cloudflare tunnel...",
         console::style("☁").bold()
     );
 
+    let origin = std::net::TcpListener::bind("127.0.0.1:0")?;
+    origin.set_nonblocking(true)?;
+    let local_port = origin.local_addr()?.port();
+
     // Resolve binary with 3-tier fallback
     let cloudflared_path = resolve_cloudflared().await?;
 
-    let mut child = tokio::process::Command::new(&cloudflared_path)
-        .arg("tunnel")
-        .arg("--url")
-        .arg(format!("http://localhost:{}", local_port))
+    let mut command = tokio::process::Command::new(&cloudflared_path);
+    command.arg("tunnel");
+    if origin_tls {
+        // The origin certificate is self-signed and ephemeral
+        command
+            .arg("--url")
+            .arg(format!("https://127.0.0.1:{}", local_port))
+            .arg("--no-tls-verify");
+    } else {
+        command
+            .arg("--url")
+            .arg(format!("http://127.0.0.1:{}", local_port));
+    }
+
+    let mut child = command
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
@@ -258,6 +284,7 @@ pub async fn start_tunnel(local_port: u16) -> anyhow::Result<CloudflareTunnel> {
                             return Ok(CloudflareTunnel {
                                 public_url: url,
                                 child: Some(child),
+                                origin,
                             });
                         }
                     }
@@ -0,0 +1,155 @@
+//! Encrypted-at-rest receiving (`ded receive --vault`, `ded open`).
+//!
+//! With a vault, receive mode never writes plaintext: each upload is
+//! decrypted in memory and sealed again to a long-term age (X25519) public
+//! key before it touches the disk, saved as `name.ext.age`. Only the holder
+//! of the matching identity can open it — with `ded open`, or any age tool
+//! (`age -d -i key.txt`). Keys come from `age-keygen`.
+
+use crate::receive::{self, OnConflict};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Added to the name of every sealed file
+pub const EXTENSION: &str = "age";
+
+/// The public key received files are sealed to
+#[derive(Clone, Debug)]
+pub struct Vault {
+    recipient: age::x25519::Recipient,
+}
+
+impl Vault {
+    /// An `age1…` public key, or a file containing one (`age-keygen -y`
+    /// output or the identity file's `# public key:` comment)
+    pub fn parse(key: &str) -> anyhow::Result<Self> {
+        let text = if key.starts_with("age1") {
+            key.to_string()
+        } else {
+            std::fs::read_to_string(key).map_err(|e| {
+                anyhow::anyhow!(
+                    "--vault '{}' is not an age1… key or a readable file: {}",
+                    key,
+                    e
+                )
+            })?
+        };
+        let recipient = text
+            .lines()
+            .map(|line| line.trim().trim_start_matches("# public key:").trim())
+            .find(|line| line.starts_with("age1"))
+            .ok_or_else(|| anyhow::anyhow!("No age1… public key in --vault '{}'", key))?;
+        let recipient = recipient
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid vault key {}: {}", recipient, e))?;
+        Ok(Self { recipient })
+    }
+
+    /// The public key, for the banner
+    pub fn recipient(&self) -> String {
+        self.recipient.to_string()
+    }
+
+    /// Seal `data` to the vault key into `output`
+    pub fn seal(&self, mut data: impl Read, output: impl Write) -> std::io::Result<()> {
+        let encryptor = age::Encryptor::with_recipients(std::iter::once(
+            &self.recipient as &dyn age::Recipient,
+        ))
+        .map_err(std::io::Error::other)?;
+        let mut writer = encryptor.wrap_output(output)?;
+        std::io::copy(&mut data, &mut writer)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Seal an upload and save it under `output_dir` as `relative.age`.
+    /// Same guarantees and conflict handling as [`receive::save_file`].
+    pub fn save(
+        &self,
+        output_dir: &Path,
+        relative: &Path,
+        data: &[u8],
+        on_conflict: OnConflict,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        self.save_from(output_dir, relative, data, on_conflict)
+    }
+
+    /// [`Vault::save`], sealing whatever `data` reads
+    pub fn save_from(
+        &self,
+        output_dir: &Path,
+        relative: &Path,
+        data: impl Read,
+        on_conflict: OnConflict,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        receive::save_with(output_dir, &sealed_name(relative), on_conflict, |file| {
+            self.seal(data, file)
+        })
+    }
+}
+
+/// `report.pdf` → `report.pdf.age`
+pub fn sealed_name(relative: &Path) -> PathBuf {
+    let mut name = relative.as_os_str().to_owned();
+    name.push(".");
+    name.push(EXTENSION);
+    PathBuf::from(name)
+}
+
+/// `report.pdf.age` → `report.pdf`; anything else gets `.decrypted` added
+pub fn opened_name(sealed: &Path) -> PathBuf {
+    if sealed.extension().is_some_and(|ext| ext == EXTENSION) {
+        sealed.with_extension("")
+    } else {
+        let mut name = sealed.as_os_str().to_owned();
+        name.push(".decrypted");
+        PathBuf::from(name)
+    }
+}
+
+/// The X25519 identities (`AGE-SECRET-KEY-1…` lines) in an age identity file
+pub fn read_identities(path: &Path) -> anyhow::Result<Vec<age::x25519::Identity>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read identity file {}: {}", path.display(), e))?;
+    let identities = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            line.parse::<age::x25519::Identity>()
+                .map_err(|e| anyhow::anyhow!("Bad key in {}: {}", path.display(), e))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if identities.is_empty() {
+        anyhow::bail!("No AGE-SECRET-KEY in {}", path.display());
+    }
+    Ok(identities)
+}
+
+/// A reader over the plaintext of a sealed file
+pub fn open_reader(
+    sealed: impl Read,
+    identities: &[age::x25519::Identity],
+) -> anyhow::Result<impl Read> {
+    let decryptor = age::Decryptor::new(sealed)?;
+    Ok(decryptor.decrypt(identities.iter().map(|i| i as &dyn age::Identity))?)
+}
+
+/// Decrypt a sealed file into `output_dir` (never overwriting). Returns
+/// the path written.
+pub fn open_file(
+    sealed: &Path,
+    identities: &[age::x25519::Identity],
+    output_dir: &Path,
+) -> anyhow::Result<PathBuf> {
+    let file = std::fs::File::open(sealed)
+        .map_err(|e| anyhow::anyhow!("Cannot open {}: {}", sealed.display(), e))?;
+    let mut plaintext = open_reader(std::io::BufReader::new(file), identities)
+        .map_err(|e| anyhow::anyhow!("{}: {}", sealed.display(), e))?;
+    let name = opened_name(Path::new(sealed.file_name().unwrap_or_default()));
+    let saved = receive::save_with(output_dir, &name, OnConflict::Rename, |out| {
+        std::io::copy(&mut plaintext, out).map(|_| ())
+    })
+    .map_err(|e| anyhow::anyhow!("{}: {}", sealed.display(), e))?;
+    Ok(output_dir.join(saved.unwrap_or(name)))
+}
@@ -47,3 +47,1880 @@ fn test_config_duration_parsing() {
     .unwrap();
     assert_eq!(config.expiry_duration, chrono::Duration::minutes(30));
 }
+
+#[test]
+fn test_deniable_container_opens_real_and_decoy() {
+    use deadrop::crypto::{
+        CHUNK_SIZE, EncryptedHeader, EncryptionKey, MIN_CHUNK_SIZE, SlotInput, decrypt_deniable,
+        encrypt_deniable,
+    };
+
+    let salt: [u8; 16] = [7u8; 16];
+    let real_key = EncryptionKey::from_password("real", &salt).unwrap();
+    let decoy_key = EncryptionKey::from_password("duress", &salt).unwrap();
+    let wrong_key = EncryptionKey::from_password("wrong", &salt).unwrap();
+
+    let real_data = vec![0xAAu8; 70_000];
+    let decoy_data = b"grocery list".to_vec();
+
+    let mut with_decoy = Vec::new();
+    let layout = encrypt_deniable(
+        &mut with_decoy,
+        SlotInput {
+            reader: &mut Cursor::new(&real_data),
+            len: real_data.len() as u64,
+            filename: "plans.pdf".to_string(),
+            key: &real_key,
+        },
+        Some(SlotInput {
+            reader: &mut Cursor::new(&decoy_data),
+            len: decoy_data.len() as u64,
+            filename: "list.txt".to_string(),
+            key: &decoy_key,
+        }),
+        CHUNK_SIZE,
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(with_decoy.len() as u64, layout.size());
+    assert_eq!(layout.size(), layout.slot_size * 2);
+
+    let (name, data) = decrypt_deniable(&with_decoy, &real_key).unwrap();
+    assert_eq!(name, "plans.pdf");
+    assert_eq!(data, real_data);
+
+    let (name, data) = decrypt_deniable(&with_decoy, &decoy_key).unwrap();
+    assert_eq!(name, "list.txt");
+    assert_eq!(data, decoy_data);
+
+    assert!(decrypt_deniable(&with_decoy, &wrong_key).is_err());
+
+    // Without a decoy the container is the same size — filler is indistinguishable
+    let mut without_decoy = Vec::new();
+    encrypt_deniable(
+        &mut without_decoy,
+        SlotInput {
+            reader: &mut Cursor::new(&real_data),
+            len: real_data.len() as u64,
+            filename: "plans.pdf".to_string(),
+            key: &real_key,
+        },
+        None,
+        CHUNK_SIZE,
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(without_decoy.len(), with_decoy.len());
+    assert!(decrypt_deniable(&without_decoy, &decoy_key).is_err());
+
+    // A chosen chunk size goes into both slots alike
+    let mut sized = Vec::new();
+    let layout = encrypt_deniable(
+        &mut sized,
+        SlotInput {
+            reader: &mut Cursor::new(&real_data),
+            len: real_data.len() as u64,
+            filename: "plans.pdf".to_string(),
+            key: &real_key,
+        },
+        Some(SlotInput {
+            reader: &mut Cursor::new(&decoy_data),
+            len: decoy_data.len() as u64,
+            filename: "list.txt".to_string(),
+            key: &decoy_key,
+        }),
+        MIN_CHUNK_SIZE,
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(sized.len() as u64, layout.size());
+    assert_eq!(
+        layout.chunks,
+        layout.padded_len.div_ceil(MIN_CHUNK_SIZE as u64)
+    );
+    for slot in sized.chunks(layout.slot_size as usize) {
+        let header = EncryptedHeader::from_bytes(slot).unwrap();
+        assert_eq!(header.chunk_size, MIN_CHUNK_SIZE);
+        assert_eq!(header.total_chunks, layout.chunks);
+    }
+    assert_eq!(decrypt_deniable(&sized, &real_key).unwrap().1, real_data);
+    assert_eq!(decrypt_deniable(&sized, &decoy_key).unwrap().1, decoy_data);
+}
+
+#[test]
+fn test_small_decoy_padding_hides_the_real_size() {
+    use deadrop::crypto::{
+        CHUNK_SIZE, EncryptionKey, SlotInput, decrypt_container, deniable_layout, encrypt_deniable,
+    };
+
+    let salt: [u8; 16] = [9u8; 16];
+    let real_key = EncryptionKey::from_password("real", &salt).unwrap();
+    let decoy_key = EncryptionKey::from_password("duress", &salt).unwrap();
+    let decoy_data = b"grocery list".to_vec();
+
+    // What the duress password opens: the decoy's whole slot, padding included
+    let open_decoy = |real_len: usize| {
+        let real_data = vec![0xAAu8; real_len];
+        let mut container = Vec::new();
+        let layout = encrypt_deniable(
+            &mut container,
+            SlotInput {
+                reader: &mut Cursor::new(&real_data),
+                len: real_data.len() as u64,
+                filename: "plans.pdf".to_string(),
+                key: &real_key,
+            },
+            Some(SlotInput {
+                reader: &mut Cursor::new(&decoy_data),
+                len: decoy_data.len() as u64,
+                filename: "list.txt".to_string(),
+                key: &decoy_key,
+            }),
+            CHUNK_SIZE,
+            |_| {},
+        )
+        .unwrap();
+        container
+            .chunks(layout.slot_size as usize)
+            .find_map(|slot| decrypt_container(slot, &decoy_key).ok())
+            .unwrap()
+    };
+
+    // Two real files of different sizes leave the decoy looking the same:
+    // its padding runs to a power of two, not to the other slot's length
+    let (smaller, larger) = (open_decoy(40_000), open_decoy(60_000));
+    assert_eq!(smaller.len(), 65_536);
+    assert_eq!(smaller, larger);
+    assert!(smaller.ends_with(&[0u8; 1024]));
+
+    // A decoy in a bucket below the real file's is flagged for a warning
+    let mut empty = std::io::empty();
+    let layout = deniable_layout(
+        &SlotInput {
+            reader: &mut empty,
+            len: 60_000,
+            filename: "plans.pdf".to_string(),
+            key: &real_key,
+        },
+        None,
+        CHUNK_SIZE,
+    );
+    for (len, outgrown) in [(decoy_data.len() as u64, true), (50_000, false)] {
+        let decoy = SlotInput {
+            reader: &mut empty,
+            len,
+            filename: "list.txt".to_string(),
+            key: &decoy_key,
+        };
+        assert_eq!(layout.outgrows(&decoy), outgrown);
+    }
+}
+
+#[test]
+fn test_tls_identity_fingerprint_pinned_in_url() {
+    let id = deadrop::tls::TlsIdentity::generate(vec!["localhost".to_string()]).unwrap();
+    assert_eq!(id.fingerprint, deadrop::tls::fingerprint(&id.cert_der));
+    assert_eq!(id.fingerprint.len(), 64);
+    assert_eq!(id.display_fingerprint().split(':').count(), 32);
+
+    let pinned = deadrop::tls::pin_url("https://localhost:8080/d/abc#key", &id.fingerprint);
+    assert_eq!(
+        pinned,
+        format!("https://localhost:8080/d/abc?fp={}#key", id.fingerprint)
+    );
+}
+
+#[test]
+fn test_forwarding_headers_only_trusted_from_proxies() {
+    use axum::http::HeaderMap;
+    use deadrop::proxy::TrustedProxies;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("X-Forwarded-For", "6.6.6.6, 10.0.0.7".parse().unwrap());
+
+    // A LAN peer forging the header is resolved to its own address
+    let none = TrustedProxies::parse(&[]).unwrap();
+    let lan_peer: std::net::SocketAddr = "192.168.1.50:40000".parse().unwrap();
+    assert_eq!(
+        none.client_ip(&lan_peer, &headers).to_string(),
+        "192.168.1.50"
+    );
+
+    // Behind a trusted proxy chain, the rightmost untrusted hop wins
+    let proxies = TrustedProxies::parse(&["10.0.0.0/8".to_string()]).unwrap();
+    let proxy_peer: std::net::SocketAddr = "10.0.0.1:40000".parse().unwrap();
+    assert_eq!(
+        proxies.client_ip(&proxy_peer, &headers).to_string(),
+        "6.6.6.6"
+    );
+
+    // Loopback is only trusted on the tunnel's own origin listener
+    let loopback: std::net::SocketAddr = "127.0.0.1:40000".parse().unwrap();
+    headers.insert("CF-Connecting-IP", "203.0.113.9".parse().unwrap());
+    assert_eq!(none.client_ip(&loopback, &headers).to_string(), "127.0.0.1");
+    let tunnel = TrustedProxies::parse(&[]).unwrap().with_loopback();
+    assert_eq!(
+        tunnel.client_ip(&loopback, &headers).to_string(),
+        "203.0.113.9"
+    );
+
+    assert!(TrustedProxies::parse(&["not-a-cidr".to_string()]).is_err());
+}
+
+#[test]
+fn test_access_policy_networks_windows_and_token() {
+    use chrono::{NaiveTime, TimeZone, Utc};
+    use deadrop::policy::{self, AccessPolicy, Denied};
+
+    let lan: std::net::IpAddr = "192.168.1.20".parse().unwrap();
+    let guest: std::net::IpAddr = "192.168.1.99".parse().unwrap();
+    let outside: std::net::IpAddr = "203.0.113.5".parse().unwrap();
+    let now = Utc.with_ymd_and_hms(2030, 1, 1, 12, 0, 0).unwrap();
+    let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+    let firefox = Some("Mozilla/5.0 (X11; Linux x86_64) Gecko/20100101 Firefox/140.0");
+
+    let policy = AccessPolicy {
+        allow: policy::parse_cidrs(&["192.168.1.0/24".to_string()]).unwrap(),
+        deny: policy::parse_cidrs(&["192.168.1.99".to_string()]).unwrap(),
+        allow_agents: policy::parse_agents(&["Firefox".into(), "safari".into()]).unwrap(),
+        deny_agents: policy::parse_agents(&["Headless".to_string()]).unwrap(),
+        not_before: Some(Utc.with_ymd_and_hms(2030, 1, 1, 9, 0, 0).unwrap()),
+        between: Some(policy::parse_window("22:00-13:00").unwrap()),
+        max_ips: 1,
+        token: Some("s3cret".to_string()),
+    };
+
+    assert_eq!(
+        policy.check(lan, firefox, Some("s3cret"), now, noon),
+        Ok(())
+    );
+    assert_eq!(
+        policy.check(outside, firefox, Some("s3cret"), now, noon),
+        Err(Denied::Network)
+    );
+    // Deny wins over allow
+    assert_eq!(
+        policy.check(guest, firefox, Some("s3cret"), now, noon),
+        Err(Denied::Network)
+    );
+    assert_eq!(
+        policy.check(
+            lan,
+            firefox,
+            Some("s3cret"),
+            now - chrono::Duration::hours(4),
+            noon
+        ),
+        Err(Denied::TooEarly)
+    );
+    // Window wraps past midnight: 22:00–13:00 excludes the afternoon
+    let afternoon = NaiveTime::from_hms_opt(15, 30, 0).unwrap();
+    assert_eq!(
+        policy.check(lan, firefox, Some("s3cret"), now, afternoon),
+        Err(Denied::OutsideWindow)
+    );
+    assert_eq!(
+        policy.check(lan, firefox, None, now, noon),
+        Err(Denied::BadToken)
+    );
+    assert_eq!(
+        policy.check(lan, firefox, Some("guess"), now, noon),
+        Err(Denied::BadToken)
+    );
+    // User agents match case-insensitively; deny wins over allow
+    let headless = Some("Mozilla/5.0 HeadlessFirefox/140.0");
+    for agent in [Some("curl/8.5.0"), None, headless] {
+        assert_eq!(
+            policy.check(lan, agent, Some("s3cret"), now, noon),
+            Err(Denied::UserAgent)
+        );
+    }
+
+    assert!(policy::parse_window("9-17").is_err());
+    let empty = policy::parse_window("09:00-09:00").unwrap_err().to_string();
+    assert!(empty.contains("start and end are the same"), "{}", empty);
+    assert!(policy::parse_agents(&[" ".to_string()]).is_err());
+    assert!(policy::parse_cidrs(&["10.0.0.0/33".to_string()]).is_err());
+    assert!(policy::parse_not_before("2030-01-01T09:00:00Z").is_ok());
+    assert!(policy::parse_not_before("10m").unwrap() > Utc::now());
+}
+
+/// In-memory drop over `size` random bytes, for session and endpoint tests
+fn make_drop(id: &str, size: usize, max_downloads: u32) -> deadrop::store::Drop {
+    let plaintext = vec![7u8; size];
+    let key = deadrop::crypto::EncryptionKey::generate();
+    let ciphertext = deadrop::crypto::encrypt_file_streaming(
+        &mut Cursor::new(&plaintext),
+        &key,
+        size as u64,
+        |_| {},
+    )
+    .unwrap();
+    let header = deadrop::crypto::EncryptedHeader::from_bytes(&ciphertext).unwrap();
+    deadrop::store::Drop {
+        id: id.to_string(),
+        encrypted_path: None,
+        encrypted_size: ciphertext.len() as u64,
+        ciphertext: Some(ciphertext),
+        total_chunks: header.total_chunks,
+        slot_size: 0,
+        recipient_envelopes: Vec::new(),
+        filename: "test.bin".to_string(),
+        mime_type: "application/octet-stream".to_string(),
+        file_size: size as u64,
+        created_at: chrono::Utc::now(),
+        expires_at: std::sync::Mutex::new(chrono::Utc::now() + chrono::Duration::hours(1)),
+        max_downloads,
+        has_password: false,
+        digest: None,
+        policy: deadrop::policy::AccessPolicy {
+            max_ips: 0,
+            ..Default::default()
+        },
+        downloads: Default::default(),
+        sealing: None,
+    }
+}
+
+#[test]
+fn test_one_time_token_admits_one_download() {
+    use deadrop::policy::{AccessPolicy, Denied, TOKEN_CLAIM_TTL};
+    use deadrop::store::Transport;
+
+    let mut drop = make_drop("token", 1024, 0);
+    drop.policy = AccessPolicy {
+        token: Some("s3cret".to_string()),
+        ..Default::default()
+    };
+    let phone: std::net::IpAddr = "192.168.1.20".parse().unwrap();
+    let laptop: std::net::IpAddr = "192.168.1.21".parse().unwrap();
+    let token = Some("s3cret");
+
+    assert_eq!(drop.admit(phone, None, token), Ok(()));
+    assert_eq!(drop.admit(laptop, None, token), Err(Denied::TokenClaimed));
+    // The first device may come back while its claim is fresh
+    assert_eq!(drop.admit(phone, None, token), Ok(()));
+
+    // Once the claim runs out, only the download already under way goes on
+    let session = drop.open_session(phone, Transport::Chunks).unwrap();
+    let expired = chrono::Utc::now() - TOKEN_CLAIM_TTL;
+    *drop.downloads.token_claimed_at.lock().unwrap() = Some(expired);
+    assert_eq!(drop.admit(phone, None, token), Ok(()));
+    drop.abort_session(session);
+    assert_eq!(drop.admit(phone, None, token), Err(Denied::TokenClaimed));
+
+    // A completed download burns it, fresh or not
+    *drop.downloads.token_claimed_at.lock().unwrap() = Some(chrono::Utc::now());
+    let session = drop.open_session(phone, Transport::Blob).unwrap();
+    drop.complete_session(session).unwrap();
+    assert_eq!(drop.admit(phone, None, token), Err(Denied::TokenClaimed));
+    assert_eq!(drop.downloads.blocked.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn test_download_slots_reserved_atomically() {
+    use deadrop::store::Transport;
+    use std::sync::Arc;
+
+    let drop = Arc::new(make_drop("slots", 200 * 1024, 3));
+    let ip: std::net::IpAddr = "192.168.1.20".parse().unwrap();
+
+    // 64 threads race for 3 slots
+    let handles: Vec<_> = (0..64)
+        .map(|_| {
+            let drop = drop.clone();
+            std::thread::spawn(move || drop.open_session(ip, Transport::Blob))
+        })
+        .collect();
+    let sessions: Vec<u64> = handles
+        .into_iter()
+        .filter_map(|h| h.join().unwrap())
+        .collect();
+    assert_eq!(sessions.len(), 3);
+    assert_eq!(drop.remaining_downloads(), Some(0));
+    assert!(drop.open_chunk_session(ip).is_none());
+
+    // An aborted transfer hands its slot back; a completed one keeps it
+    assert!(drop.abort_session(sessions[0]).is_some());
+    assert_eq!(drop.remaining_downloads(), Some(1));
+    assert_eq!(drop.complete_session(sessions[1]), Some((1, false)));
+    assert_eq!(drop.complete_session(sessions[1]), None);
+
+    // Chunked sessions are found by their token, for the client it was
+    // issued to only, and count once every chunk has been served
+    let (chunked, token) = drop.open_chunk_session(ip).unwrap();
+    assert_eq!(token.len(), 32);
+    assert_eq!(drop.chunk_session(ip, &token), Some(chunked));
+    assert_eq!(
+        drop.chunk_session(ip, "0123456789abcdef0123456789abcdef"),
+        None
+    );
+    assert_eq!(
+        drop.chunk_session("192.168.1.21".parse().unwrap(), &token),
+        None
+    );
+    assert!(drop.total_chunks > 2);
+    assert!(!drop.record_chunk(chunked, drop.total_chunks - 1, 100));
+    for idx in 0..drop.total_chunks - 2 {
+        assert!(!drop.record_chunk(chunked, idx, 100));
+    }
+    // A repeated chunk doesn't stand in for a missing one
+    assert!(!drop.record_chunk(chunked, 0, 100));
+    assert!(drop.record_chunk(chunked, drop.total_chunks - 2, 100));
+    assert_eq!(drop.complete_session(chunked), Some((2, false)));
+    assert_eq!(drop.chunk_session(ip, &token), None);
+    assert_eq!(drop.complete_session(sessions[2]), Some((3, true)));
+}
+
+#[test]
+fn test_removed_drop_drains_in_flight_transfers() {
+    use deadrop::store::{BlobStore, Transport};
+
+    let store = BlobStore::new(|_| {});
+    store.insert(make_drop("draining", 300 * 1024, 0));
+    let drop = store.get("draining").unwrap();
+    let ip: std::net::IpAddr = "192.168.1.30".parse().unwrap();
+    let (chunked, token) = drop.open_chunk_session(ip).unwrap();
+    for idx in 0..drop.total_chunks - 1 {
+        assert!(!drop.record_chunk(chunked, idx, 100));
+    }
+    assert_eq!(store.in_flight(), 1);
+
+    // Revoked mid-transfer: gone for newcomers, still there for the session
+    assert!(store.remove("draining"));
+    assert!(store.is_burned("draining"));
+    assert!(store.is_empty());
+    assert!(store.get("draining").is_none());
+    let same = store.get_for_session("draining").unwrap();
+    assert_eq!(same.chunk_session(ip, &token), Some(chunked));
+    assert!(same.open_session(ip, Transport::Blob).is_none());
+    assert!(same.open_chunk_session(ip).is_none());
+    assert_eq!(store.in_flight(), 1);
+
+    assert!(same.record_chunk(chunked, same.total_chunks - 1, 100));
+    assert!(same.complete_session(chunked).is_some());
+    assert_eq!(store.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn test_parallel_requests_cannot_over_serve_drop() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let store = deadrop::store::BlobStore::new(|_| {});
+    // Several chunks, so neither path finishes in a single request
+    let one_ip = make_drop("one-ip", 200 * 1024, 1);
+    let many_ips = make_drop("many-ips", 200 * 1024, 1);
+    let sealed = [
+        one_ip.ciphertext.clone().unwrap(),
+        many_ips.ciphertext.clone().unwrap(),
+    ];
+    assert!(one_ip.total_chunks > 2);
+    store.insert(one_ip);
+    store.insert(many_ips);
+    // A password drop, cut small so each slot takes a few chunks
+    let (real_key, decoy_key) = (
+        deadrop::crypto::EncryptionKey::generate(),
+        deadrop::crypto::EncryptionKey::generate(),
+    );
+    let real_data = vec![0xAAu8; 40_000];
+    let mut deniable = Vec::new();
+    let layout = deadrop::crypto::encrypt_deniable(
+        &mut deniable,
+        deadrop::crypto::SlotInput {
+            reader: &mut Cursor::new(&real_data),
+            len: real_data.len() as u64,
+            filename: "plans.pdf".to_string(),
+            key: &real_key,
+        },
+        Some(deadrop::crypto::SlotInput {
+            reader: &mut Cursor::new(b"grocery list"),
+            len: 12,
+            filename: "list.txt".to_string(),
+            key: &decoy_key,
+        }),
+        deadrop::crypto::MIN_CHUNK_SIZE,
+        |_| {},
+    )
+    .unwrap();
+    let mut password = make_drop("password", 0, 1);
+    password.encrypted_size = deniable.len() as u64;
+    password.ciphertext = Some(deniable.clone());
+    (password.total_chunks, password.slot_size) = (layout.chunks, layout.slot_size);
+    password.has_password = true;
+    store.insert(password);
+    let events = Collect::default();
+    let metrics = deadrop::metrics::Metrics::new();
+    let state = Arc::new(deadrop::server::AppState {
+        store: store.clone(),
+        shutdown: Arc::new(tokio::sync::Notify::new()),
+        proxies: deadrop::proxy::TrustedProxies::parse(&[]).unwrap(),
+        transfers: deadrop::progress::TransferBoard::new(),
+        events: deadrop::events::Events::new()
+            .with_sink(events.clone())
+            .with_sink(metrics.clone()),
+        metrics: metrics.clone(),
+        receipts: Default::default(),
+    });
+    let app = deadrop::server::send_router(state);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    let limited = Arc::new(AtomicUsize::new(0));
+    // Each client connects from its own loopback address; a client that
+    // hits the rate limiter waits it out like the page does
+    let get = {
+        let limited = limited.clone();
+        move |url: String, ip: std::net::IpAddr| {
+            let limited = limited.clone();
+            async move {
+                let client = reqwest::Client::builder()
+                    .local_address(ip)
+                    .build()
+                    .unwrap();
+                loop {
+                    let resp = client.get(&url).send().await.unwrap();
+                    if resp.status() != 429 {
+                        return resp;
+                    }
+                    limited.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+                }
+            }
+        }
+    };
+
+    // Race full downloads — whole blob, or session then every chunk — and
+    // return the payloads that came through complete
+    let race = |id: &'static str, ip_of: fn(usize) -> std::net::IpAddr| {
+        let get = get.clone();
+        let base = base.clone();
+        async move {
+            let downloads = (0..4).map(|i| {
+                let get = get.clone();
+                let base = base.clone();
+                tokio::spawn(async move {
+                    let ip = ip_of(i);
+                    if i % 2 == 0 {
+                        let resp = get(format!("{}/api/blob/{}", base, id), ip).await;
+                        if resp.status() != 200 {
+                            return None;
+                        }
+                        return Some(resp.bytes().await.ok()?.to_vec());
+                    }
+                    let resp = get(format!("{}/api/chunks/{}", base, id), ip).await;
+                    if resp.status() != 200 {
+                        return None;
+                    }
+                    let meta: serde_json::Value =
+                        serde_json::from_slice(&resp.bytes().await.unwrap()).unwrap();
+                    let session = meta["session"].as_str().unwrap().to_string();
+                    let mut chunks = Vec::new();
+                    for idx in 0..meta["total_chunks"].as_u64().unwrap() {
+                        let url = format!("{}/api/chunk/{}/{}?session={}", base, id, idx, session);
+                        let resp = get(url, ip).await;
+                        if resp.status() != 200 {
+                            return None;
+                        }
+                        chunks.push(resp.bytes().await.unwrap().to_vec());
+                    }
+                    Some(chunks.concat())
+                })
+            });
+            // Chunk requests without the issued session never get anything
+            let freeloaders = ["", "?session=0123456789abcdef0123456789abcdef"].map(|q| {
+                let url = format!("{}/api/chunk/{}/0{}", base, id, q);
+                let fetch = get(url, ip_of(1));
+                tokio::spawn(async move { fetch.await.status().as_u16() })
+            });
+            for status in futures_util::future::join_all(freeloaders).await {
+                assert!(matches!(status.unwrap(), 403 | 404));
+            }
+            futures_util::future::join_all(downloads)
+                .await
+                .into_iter()
+                .filter_map(|r| r.unwrap())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // A password drop's chunks run through one slot and then the other,
+    // each with its slot's nonce: all of them rebuild the container, and
+    // the last one completes the download
+    let ip = [127, 0, 0, 3].into();
+    let resp = get(format!("{}/api/chunks/password", base), ip).await;
+    let meta: serde_json::Value = serde_json::from_slice(&resp.bytes().await.unwrap()).unwrap();
+    assert_eq!(meta["slot_chunks"], layout.chunks);
+    assert_eq!(meta["total_chunks"], layout.chunks * 2);
+    assert!(meta.get("nonce").is_none());
+    let mut rebuilt = Vec::new();
+    for idx in 0..layout.chunks * 2 {
+        let url = format!(
+            "{}/api/chunk/password/{}?session={}",
+            base,
+            idx,
+            meta["session"].as_str().unwrap()
+        );
+        let resp = get(url, ip).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers()["x-total-chunks"],
+            (layout.chunks * 2).to_string()
+        );
+        if idx % layout.chunks == 0 {
+            let nonce = resp.headers()["x-slot-nonce"].to_str().unwrap();
+            let nonce =
+                base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, nonce)
+                    .unwrap();
+            let header = deadrop::crypto::EncryptedHeader {
+                nonce: nonce.try_into().unwrap(),
+                total_chunks: layout.chunks,
+                original_size: layout.padded_len,
+                chunk_size: layout.chunk_size,
+            };
+            rebuilt.extend_from_slice(&header.to_bytes());
+        }
+        let frame = resp.bytes().await.unwrap();
+        rebuilt.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        rebuilt.extend_from_slice(&frame);
+    }
+    assert_eq!(rebuilt, deniable);
+    let (name, data) = deadrop::crypto::decrypt_deniable(&rebuilt, &real_key).unwrap();
+    assert_eq!((name.as_str(), data), ("plans.pdf", real_data));
+    assert!(store.get("password").is_none(), "the download completed");
+
+    // A complete payload is the whole blob, or every chunk frame without
+    // its length prefix
+    let complete = |payload: &[u8], blob: &[u8]| {
+        let mut frames = Vec::new();
+        let mut offset = deadrop::crypto::EncryptedHeader::len_of(blob);
+        while offset < blob.len() {
+            let len = u32::from_le_bytes(blob[offset..offset + 4].try_into().unwrap()) as usize;
+            frames.extend_from_slice(&blob[offset + 4..offset + 4 + len]);
+            offset += 4 + len;
+        }
+        payload == blob || payload == frames
+    };
+
+    // Tabs sharing one address are separate sessions: one of them wins
+    let payloads = race("one-ip", |_| [127, 0, 0, 2].into()).await;
+    assert_eq!(payloads.len(), 1);
+    assert!(complete(&payloads[0], &sealed[0]));
+
+    // ...and so does one recipient among many devices
+    let payloads = race("many-ips", |i| [127, 0, 0, 10 + i as u8].into()).await;
+    assert_eq!(payloads.len(), 1);
+    assert!(complete(&payloads[0], &sealed[1]));
+
+    // The event stream tells the same story: one completed download and
+    // one burn per drop
+    let names = events.names();
+    let count = |name: &str| names.iter().filter(|n| *n == name).count();
+    assert_eq!(count("download_completed"), 3, "events: {:?}", names);
+    assert_eq!(count("burned"), 3, "events: {:?}", names);
+
+    // ...and so do the metrics
+    let exposition = metrics.render(store.drops().len());
+    assert!(exposition.contains("deadrop_active_drops 0\n"));
+    assert!(exposition.contains("deadrop_downloads_total{outcome=\"completed\"} 3\n"));
+    assert!(exposition.contains("deadrop_burned_total{reason=\"download_limit\"} 3\n"));
+    assert!(exposition.contains(&format!(
+        "deadrop_rate_limited_total {}\n",
+        limited.load(Ordering::SeqCst)
+    )));
+}
+
+/// Event sink that keeps every record as JSON, for assertions
+#[derive(Clone, Default)]
+struct Collect(std::sync::Arc<std::sync::Mutex<Vec<serde_json::Value>>>);
+
+impl Collect {
+    fn names(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|v| v["event"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+}
+
+impl deadrop::events::Sink for Collect {
+    fn emit(&self, record: &deadrop::events::Record) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(serde_json::to_value(record).unwrap());
+    }
+}
+
+#[test]
+fn test_events_serialize_as_tagged_json() {
+    use deadrop::events::{Event, Events, Urls};
+
+    let events = Collect::default();
+    let bus = Events::new().with_sink(events.clone());
+    bus.emit(Event::Ready {
+        mode: "send",
+        drop_id: Some("abc".into()),
+        urls: Urls {
+            lan: "http://10.0.0.2:8080/d/abc#k".into(),
+            localhost: "http://localhost:8080/d/abc#k".into(),
+            tunnel: None,
+            onion: Some("http://x.onion/d/abc#k".into()),
+        },
+        key_fragment: "k".into(),
+        expires_at: chrono::Utc::now(),
+        max_downloads: Some(1),
+    });
+    bus.emit(Event::Blocked {
+        drop_id: "abc".into(),
+        ip: "203.0.113.9".into(),
+        reason: deadrop::policy::Denied::IpLimit.code(),
+    });
+
+    let records = events.0.lock().unwrap();
+    assert_eq!(records[0]["event"], "ready");
+    assert_eq!(records[0]["urls"]["onion"], "http://x.onion/d/abc#k");
+    assert!(records[0]["urls"].get("tunnel").is_none());
+    assert!(records[0]["ts"].is_string());
+    assert_eq!(records[1]["event"], "blocked");
+    assert_eq!(records[1]["reason"], "ip_limit");
+}
+
+#[tokio::test]
+async fn test_hooks_run_command_and_post_signed_webhook() {
+    use axum::routing::post;
+    use deadrop::events::{Event, Events};
+    use std::sync::{Arc, Mutex};
+
+    // Local stand-in for the webhook receiver: (event, signature, body)
+    type Deliveries = Arc<Mutex<Vec<(String, String, Vec<u8>)>>>;
+    let seen: Deliveries = Default::default();
+    let recorder = seen.clone();
+    let app = axum::Router::new().route(
+        "/hook",
+        post(
+            move |headers: axum::http::HeaderMap, body: axum::body::Bytes| {
+                let recorder = recorder.clone();
+                async move {
+                    let header = |name: &str| {
+                        headers
+                            .get(name)
+                            .and_then(|v| v.to_str().ok())
+                            .unwrap_or_default()
+                            .to_string()
+                    };
+                    recorder.lock().unwrap().push((
+                        header("x-deadrop-event"),
+                        header("x-deadrop-signature"),
+                        body.to_vec(),
+                    ));
+                    "ok"
+                }
+            },
+        ),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("downloaded");
+    let hooks = deadrop::hooks::HookConfig {
+        on_download: Some(format!(
+            "echo \"$DEADROP_DROP_ID $DEADROP_COUNT\" > '{}'",
+            marker.display()
+        )),
+        webhook: Some(format!("http://{}/hook", addr)),
+        webhook_secret: Some("s3cret".into()),
+        ..Default::default()
+    };
+    let events = Events::new().with_sink(deadrop::hooks::Hooks::spawn(hooks).unwrap());
+
+    events.emit(Event::Ready {
+        mode: "send",
+        drop_id: Some("abc".into()),
+        urls: deadrop::events::Urls {
+            lan: "http://192.168.1.2:8080/d/abc#SECRETKEY".into(),
+            localhost: "http://localhost:8080/d/abc#SECRETKEY".into(),
+            tunnel: Some("https://x.trycloudflare.com/d/abc?fp=00#SECRETKEY".into()),
+            onion: None,
+        },
+        key_fragment: "SECRETKEY".into(),
+        expires_at: chrono::Utc::now(),
+        max_downloads: Some(1),
+    });
+    events.emit(Event::DownloadCompleted {
+        drop_id: "abc".into(),
+        session: 0,
+        ip: "127.0.0.1".into(),
+        transport: "HTTP",
+        bytes: 42,
+        count: 1,
+        max_downloads: 1,
+    });
+    events.emit(Event::Burned {
+        drop_id: "abc".into(),
+        reason: "download_limit",
+    });
+    events.settle(std::time::Duration::from_secs(10)).await;
+
+    let seen = seen.lock().unwrap();
+    let names: Vec<&str> = seen.iter().map(|(event, _, _)| event.as_str()).collect();
+    assert_eq!(names, ["ready", "download_completed", "burned"]);
+    for (_, signature, body) in seen.iter() {
+        assert_eq!(*signature, deadrop::hooks::sign(b"s3cret", body));
+        // The decryption key never leaves for the webhook
+        assert!(!String::from_utf8_lossy(body).contains("SECRETKEY"));
+    }
+    let ready: serde_json::Value = serde_json::from_slice(&seen[0].2).unwrap();
+    assert_eq!(
+        ready["urls"]["tunnel"],
+        "https://x.trycloudflare.com/d/abc?fp=00"
+    );
+    assert!(ready.get("key_fragment").is_none());
+    let payload: serde_json::Value = serde_json::from_slice(&seen[1].2).unwrap();
+    assert_eq!(payload["drop_id"], "abc");
+
+    #[cfg(unix)]
+    assert_eq!(std::fs::read_to_string(&marker).unwrap().trim(), "abc 1");
+}
+
+#[test]
+fn test_audit_log_chain_detects_tampering() {
+    use deadrop::audit::{AuditLog, verify};
+    use deadrop::events::{Event, Events};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.log");
+
+    // Two runs: the second continues the first one's chain
+    for run in 0..2 {
+        let log = AuditLog::open(&path).unwrap();
+        let events = Events::new().with_sink(std::sync::Arc::new(log));
+        events.emit(Event::Blocked {
+            drop_id: format!("drop{}", run),
+            ip: "198.51.100.7".into(),
+            reason: "ip_limit",
+        });
+        events.emit(Event::Ready {
+            mode: "send",
+            drop_id: Some("drop".into()),
+            urls: Default::default(),
+            key_fragment: "SECRET-KEY".into(),
+            expires_at: chrono::Utc::now(),
+            max_downloads: None,
+        });
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(events.finish(std::time::Duration::from_secs(1)));
+    }
+
+    let summary = verify(&path).unwrap();
+    assert_eq!((summary.entries, summary.runs), (6, 2));
+    assert!(summary.sealed);
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        !text.contains("SECRET-KEY"),
+        "key fragment leaked into audit log"
+    );
+
+    // Edit one entry
+    std::fs::write(&path, text.replacen("198.51.100.7", "203.0.113.1", 1)).unwrap();
+    let err = verify(&path).unwrap_err().to_string();
+    assert!(err.contains("line 2"), "{}", err);
+
+    // Drop one entry
+    let lines: Vec<&str> = text.lines().collect();
+    let mut cut = lines.clone();
+    cut.remove(2);
+    std::fs::write(&path, cut.join("\n") + "\n").unwrap();
+    assert!(verify(&path).is_err());
+
+    // Cut off the tail: the chain holds, but the last run is unsealed
+    std::fs::write(&path, lines[..5].join("\n") + "\n").unwrap();
+    assert!(!verify(&path).unwrap().sealed);
+}
+
+#[tokio::test]
+async fn test_receipt_proves_which_payload_was_decrypted() {
+    use deadrop::receipt::{Expected, Receipts, Rejected, mac};
+    use std::io::Read;
+
+    let hex = |b: &[u8]| b.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    let key = deadrop::crypto::EncryptionKey::generate();
+    let decoy_key = deadrop::crypto::EncryptionKey::generate();
+
+    // The digest falls out of the encryption pass
+    let mut reader = deadrop::crypto::HashingReader::new(Cursor::new(b"the real thing".to_vec()));
+    let mut sink = Vec::new();
+    reader.read_to_end(&mut sink).unwrap();
+    let real: [u8; 32] = reader.finish();
+    let decoy: [u8; 32] = {
+        use sha2::Digest;
+        sha2::Sha256::digest(b"nothing to see").into()
+    };
+
+    let receipts = Receipts::new();
+    receipts.expect(
+        "drop1",
+        vec![
+            Expected::new(&key.0, real, false),
+            Expected::new(&decoy_key.0, decoy, true),
+        ],
+    );
+
+    let (alice, bob) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+    let good = hex(&mac(&key.0, "drop1", &real));
+    assert_eq!(
+        receipts.verify("drop1", alice, &hex(&real), &good),
+        Ok(false)
+    );
+    let opened_decoy = hex(&mac(&decoy_key.0, "drop1", &decoy));
+    assert_eq!(
+        receipts.verify("drop1", bob, &hex(&decoy), &opened_decoy),
+        Ok(true)
+    );
+    receipts.wait_for("drop1", 2).await;
+
+    // A replayed receipt counts once, however often it is posted
+    assert_eq!(
+        receipts.verify("drop1", alice, &hex(&real), &good),
+        Err(Rejected::Repeated)
+    );
+    let third = tokio::time::timeout(
+        std::time::Duration::from_millis(50),
+        receipts.wait_for("drop1", 3),
+    );
+    assert!(third.await.is_err());
+
+    // Right key, wrong content; right content, someone else's drop; no key at all
+    assert_eq!(
+        receipts.verify("drop1", alice, &hex(&decoy), &good),
+        Err(Rejected::BadMac)
+    );
+    let elsewhere = hex(&mac(&key.0, "drop2", &real));
+    assert_eq!(
+        receipts.verify("drop1", alice, &hex(&real), &elsewhere),
+        Err(Rejected::BadMac)
+    );
+    assert_eq!(
+        receipts.verify("drop1", alice, &hex(&real), &hex(&[0u8; 32])),
+        Err(Rejected::BadMac)
+    );
+    assert_eq!(
+        receipts.verify("drop2", alice, &hex(&real), &good),
+        Err(Rejected::UnknownDrop)
+    );
+    assert_eq!(
+        receipts.verify("drop1", alice, "zz", &good),
+        Err(Rejected::Malformed)
+    );
+
+    // 64 bytes, but not 64 hex digits: multi-byte characters are refused, not sliced
+    let non_ascii = format!("a{}b", "é".repeat(31));
+    assert_eq!(non_ascii.len(), 64);
+    assert_eq!(
+        receipts.verify("drop1", alice, &non_ascii, &good),
+        Err(Rejected::Malformed)
+    );
+    assert_eq!(
+        receipts.verify("drop1", alice, &hex(&real), &non_ascii),
+        Err(Rejected::Malformed)
+    );
+}
+
+#[test]
+fn test_sealed_digest_checks_the_whole_plaintext() {
+    use deadrop::crypto::{
+        EncryptionKey, decrypt_container, open_digest, seal_digest, seal_slot_digests, sha256,
+        to_hex, verify_digest,
+    };
+
+    let key = EncryptionKey::generate();
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let ct = deadrop::crypto::encrypt_file_streaming(
+        &mut Cursor::new(&data),
+        &key,
+        data.len() as u64,
+        |_| {},
+    )
+    .unwrap();
+    let sealed = seal_digest(&key, &sha256(&data)).unwrap();
+    assert_eq!(open_digest(&key, &sealed).unwrap(), sha256(&data));
+    assert_eq!(
+        to_hex(&sha256(b"abc")),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    // Sealed, not just encoded: fresh nonce each time, opaque without the key
+    assert_ne!(seal_digest(&key, &sha256(&data)).unwrap(), sealed);
+    assert!(open_digest(&EncryptionKey::generate(), &sealed).is_err());
+
+    let opened = decrypt_container(&ct, &key).unwrap();
+    verify_digest(&key, &sealed, &sha256(&opened)).unwrap();
+
+    // Every chunk authenticates, yet the file is short: the digest catches it
+    let mut cut = ct[..ct.len() - (data.len() - 3 * deadrop::crypto::CHUNK_SIZE + 16 + 4)].to_vec();
+    cut[24..32].copy_from_slice(&3u64.to_le_bytes());
+    let truncated = decrypt_container(&cut, &key).unwrap();
+    assert_eq!(truncated, data[..3 * deadrop::crypto::CHUNK_SIZE]);
+    assert!(verify_digest(&key, &sealed, &sha256(&truncated)).is_err());
+    assert!(verify_digest(&key, "not-a-digest", &sha256(&opened)).is_err());
+
+    // Password drops: one digest per slot, each key opens only its own
+    let decoy_key = EncryptionKey::generate();
+    let pair = seal_slot_digests(
+        (&key, &sha256(&data)),
+        Some((&decoy_key, &sha256(b"decoy"))),
+    )
+    .unwrap();
+    assert_eq!(pair.split('.').count(), 2);
+    assert_eq!(open_digest(&key, &pair).unwrap(), sha256(&data));
+    assert_eq!(open_digest(&decoy_key, &pair).unwrap(), sha256(b"decoy"));
+    let alone = seal_slot_digests((&key, &sha256(&data)), None).unwrap();
+    assert_eq!(alone.len(), pair.len());
+    verify_digest(&key, &alone, &sha256(&data)).unwrap();
+    assert!(open_digest(&decoy_key, &alone).is_err());
+}
+
+#[test]
+fn test_receive_session_paths_and_limits() {
+    use deadrop::crypto::safe_relative_path;
+    use deadrop::receive::{Limits, Refused, Session};
+    use std::path::PathBuf;
+
+    // Folder structure survives; nothing can climb out or be absolute
+    assert_eq!(
+        safe_relative_path("photos/2024/a.jpg"),
+        PathBuf::from("photos/2024/a.jpg")
+    );
+    assert_eq!(
+        safe_relative_path("../../etc/passwd"),
+        PathBuf::from("etc/passwd")
+    );
+    assert_eq!(
+        safe_relative_path("/abs/./x.txt"),
+        PathBuf::from("abs/x.txt")
+    );
+    assert_eq!(
+        safe_relative_path("C:\\Users\\me\\notes.txt"),
+        PathBuf::from("C_/Users/me/notes.txt")
+    );
+    assert_eq!(safe_relative_path("../.."), PathBuf::from("file"));
+
+    let session = Session::new(
+        Limits {
+            max_files: 3,
+            max_total_size: Some(100),
+            ..Default::default()
+        },
+        std::env::temp_dir(),
+    );
+    session.admit(40).unwrap();
+    assert!(!session.record("a".into(), 40));
+    assert_eq!(session.admit(70), Err(Refused::TooLarge));
+    // A failed upload gives its room back
+    session.admit(50).unwrap();
+    session.release(50);
+    session.admit(60).unwrap();
+    assert!(session.record("b".into(), 60), "total size used up");
+
+    let session = Session::new(
+        Limits {
+            max_files: 2,
+            ..Default::default()
+        },
+        std::env::temp_dir(),
+    );
+    session.admit(1).unwrap();
+    session.admit(1).unwrap();
+    assert_eq!(session.admit(1), Err(Refused::TooManyFiles));
+    assert!(session.close());
+    assert!(!session.close(), "only the first close counts");
+    assert_eq!(session.admit(1), Err(Refused::Closed));
+}
+
+#[test]
+fn test_receive_size_and_disk_limits() {
+    use deadrop::receive::{Limits, Refused, Session};
+
+    let session = Session::new(
+        Limits {
+            max_size: 1000,
+            ..Default::default()
+        },
+        std::env::temp_dir(),
+    );
+    // The announced (encrypted) size is judged against max_size once encrypted
+    let ceiling = deadrop::crypto::encrypted_len(1000);
+    assert_eq!(ceiling, 40 + 1000 + 20);
+    session.reserve_announced(ceiling).unwrap();
+    assert_eq!(
+        session.reserve_announced(ceiling + 1),
+        Err(Refused::FileTooLarge)
+    );
+    assert_eq!(session.admit(1001), Err(Refused::FileTooLarge));
+    session.admit(1000).unwrap();
+
+    // An announced upload holds its room until its header settles it
+    let session = Session::new(
+        Limits {
+            max_files: 2,
+            max_total_size: Some(2100),
+            ..Default::default()
+        },
+        std::env::temp_dir(),
+    );
+    session.reserve_announced(ceiling).unwrap();
+    assert_eq!(session.reserve_announced(ceiling), Err(Refused::TooLarge));
+    session.settle(ceiling, 1000);
+    session.reserve_announced(ceiling).unwrap();
+    assert_eq!(session.reserve_announced(1), Err(Refused::TooManyFiles));
+    session.release(ceiling);
+    session.reserve_announced(1).unwrap();
+
+    #[cfg(unix)]
+    {
+        let free = deadrop::receive::free_space(&std::env::temp_dir()).unwrap();
+        let session = Session::new(
+            Limits {
+                min_free_disk: Some(free.saturating_add(1 << 40)),
+                ..Default::default()
+            },
+            std::env::temp_dir(),
+        );
+        assert_eq!(session.admit(1), Err(Refused::DiskFull));
+        assert_eq!(session.reserve_announced(1), Err(Refused::DiskFull));
+
+        // Uploads started side by side can't both count on the same room
+        let session = Session::new(
+            Limits {
+                max_size: free,
+                min_free_disk: Some(free / 2),
+                ..Default::default()
+            },
+            std::env::temp_dir(),
+        );
+        session.reserve_announced(free / 3).unwrap();
+        assert_eq!(session.reserve_announced(free / 3), Err(Refused::DiskFull));
+    }
+}
+
+#[tokio::test]
+async fn test_chunked_upload_resumes_from_acknowledged_offset() {
+    use deadrop::receive::{Append, Partials};
+    use std::io::Read;
+
+    let dir = tempfile::tempdir().unwrap();
+    let partials = Partials::default();
+    let id = partials
+        .start(dir.path(), "a/b.bin".into(), 10, None)
+        .unwrap();
+    let partial = partials.get(&id).unwrap();
+    let mut partial = partial.lock().await;
+
+    assert_eq!(partial.append(0, b"hello").unwrap(), 5);
+    // A resent chunk (its ack was lost) is refused with where to resume
+    assert!(matches!(
+        partial.append(0, b"hello"),
+        Err(Append::WrongOffset(5))
+    ));
+    assert!(matches!(partial.append(5, b"world!"), Err(Append::TooLong)));
+    assert!(!partial.is_complete());
+    assert_eq!(partial.append(5, b"world").unwrap(), 10);
+    assert!(partial.is_complete());
+
+    let mut taken = String::new();
+    let mut file = partial.take().unwrap();
+    file.read_to_string(&mut taken).unwrap();
+    assert_eq!(taken, "helloworld");
+    assert!(matches!(partial.append(10, b"x"), Err(Append::Finished)));
+    // The temp file is gone once what was taken is dropped
+    drop(file);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    drop(partial);
+
+    partials.start(dir.path(), "c".into(), 1, None).unwrap();
+    assert_eq!(partials.unfinished(), 2);
+    partials.clear();
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    // Idle uploads are given up, handing back what they reserved; one
+    // finished is forgotten without a reservation to hand back
+    let idle = partials.start(dir.path(), "d".into(), 7, None).unwrap();
+    let done = partials.start(dir.path(), "e".into(), 9, None).unwrap();
+    partials.get(&done).unwrap().lock().await.finished = Some(serde_json::json!({}));
+    let busy = partials.start(dir.path(), "f".into(), 3, None).unwrap();
+    let busy = partials.get(&busy).unwrap();
+    let _finishing = busy.lock().await;
+    assert!(
+        partials
+            .reap_idle(std::time::Duration::from_secs(60))
+            .is_empty()
+    );
+    assert_eq!(partials.reap_idle(std::time::Duration::ZERO), vec![7]);
+    assert!(partials.get(&idle).is_none() && partials.get(&done).is_none());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_received_names_sanitized_and_never_clobbered() {
+    use deadrop::crypto::{is_reserved_name, numbered_filename, safe_filename};
+    use deadrop::receive::{OnConflict, check_path, save_file};
+    use std::path::Path;
+
+    assert_eq!(
+        safe_filename("re\u{0007}port<1>?.pdf", false),
+        "report1.pdf"
+    );
+    assert_eq!(safe_filename("notes.txt. . ", false), "notes.txt");
+    assert_eq!(safe_filename("...", false), "file");
+    assert_eq!(numbered_filename("report.pdf", 1), "report (1).pdf");
+    assert_eq!(numbered_filename("backup.tar.gz", 2), "backup (2).tar.gz");
+    assert_eq!(numbered_filename(".bashrc", 1), ".bashrc (1)");
+    assert!(is_reserved_name("CON") && is_reserved_name("nul.txt") && is_reserved_name("com1"));
+    assert!(!is_reserved_name("console.log") && !is_reserved_name("COM0"));
+
+    assert!(check_path(Path::new("docs/a.txt"), false).is_ok());
+    assert!(check_path(Path::new(".bashrc"), false).is_err());
+    assert!(check_path(Path::new(".git/config"), false).is_err());
+    assert!(check_path(Path::new(".bashrc"), true).is_ok());
+    assert!(check_path(Path::new("aux/x.txt"), true).is_err());
+
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path();
+    let save = |data: &[u8], mode| save_file(out, Path::new("r/a.txt"), data, mode).unwrap();
+    assert_eq!(save(b"one", OnConflict::Rename), Some("r/a.txt".into()));
+    assert_eq!(save(b"two", OnConflict::Rename), Some("r/a (1).txt".into()));
+    assert_eq!(save(b"three", OnConflict::Skip), None);
+    assert_eq!(std::fs::read(out.join("r/a.txt")).unwrap(), b"one");
+    assert_eq!(save(b"four", OnConflict::Overwrite), Some("r/a.txt".into()));
+    assert_eq!(std::fs::read(out.join("r/a.txt")).unwrap(), b"four");
+
+    #[cfg(unix)]
+    {
+        let outside = tempfile::tempdir().unwrap();
+        let victim = outside.path().join("victim");
+        std::fs::write(&victim, b"keep").unwrap();
+        std::os::unix::fs::symlink(&victim, out.join("link")).unwrap();
+        let link = Path::new("link");
+        assert!(save_file(out, link, b"x", OnConflict::Overwrite).is_err());
+        assert_eq!(
+            save_file(out, link, b"x", OnConflict::Rename).unwrap(),
+            Some("link (1)".into())
+        );
+        std::os::unix::fs::symlink(outside.path(), out.join("escape")).unwrap();
+        assert!(save_file(out, Path::new("escape/x"), b"x", OnConflict::Rename).is_err());
+        assert_eq!(std::fs::read(&victim).unwrap(), b"keep");
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_quarantine_holds_what_the_scanner_flags() {
+    use deadrop::receive::OnConflict;
+    use deadrop::scan::{Outcome, Quarantine, Scanner, Verdict, parse_clamd_reply};
+    use std::path::Path;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    assert_eq!(parse_clamd_reply("stream: OK"), Verdict::Clean);
+    assert_eq!(
+        parse_clamd_reply("stream: Eicar-Test-Signature FOUND"),
+        Verdict::Infected("Eicar-Test-Signature".into())
+    );
+    assert!(matches!(
+        parse_clamd_reply("INSTREAM size limit exceeded. ERROR"),
+        Verdict::Failed(_)
+    ));
+
+    // Stub scanner: anything containing the marker is "infected"
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("out");
+    let scanner = Scanner::Command(
+        "if grep -q EVIL \"$DEADROP_PATH\"; then echo Stub.Evil; exit 1; fi".into(),
+    );
+    let quarantine = Quarantine::new(dir.path().join("q"), scanner).unwrap();
+    let admit = |name: &'static str, data: &'static [u8]| {
+        let (quarantine, out) = (quarantine.clone(), out.clone());
+        async move {
+            quarantine
+                .admit(&out, Path::new(name), data, OnConflict::Rename)
+                .await
+                .unwrap()
+        }
+    };
+
+    match admit("docs/ok.txt", b"hello").await {
+        Outcome::Saved(saved) => assert_eq!(saved, Some("docs/ok.txt".into())),
+        other => panic!("clean file not saved: {:?}", other),
+    }
+    assert_eq!(std::fs::read(out.join("docs/ok.txt")).unwrap(), b"hello");
+    assert!(!dir.path().join("q/docs/ok.txt").exists());
+
+    match admit("bad.exe", b"xx EVIL xx").await {
+        Outcome::Quarantined { path, verdict } => {
+            assert_eq!(verdict, Verdict::Infected("Stub.Evil".into()));
+            assert_eq!(path, dir.path().join("q/bad.exe"));
+            assert_eq!(std::fs::read(path).unwrap(), b"xx EVIL xx");
+        }
+        other => panic!("infected file saved: {:?}", other),
+    }
+    assert!(!out.join("bad.exe").exists());
+
+    // A scanner that breaks is not a clean bill of health
+    let broken = Quarantine::new(dir.path().join("q"), Scanner::Command("exit 2".into())).unwrap();
+    let held = broken
+        .admit(&out, Path::new("c.txt"), b"c", OnConflict::Rename)
+        .await
+        .unwrap();
+    assert!(matches!(
+        held,
+        Outcome::Quarantined {
+            verdict: Verdict::Failed(_),
+            ..
+        }
+    ));
+    assert!(!out.join("c.txt").exists());
+
+    // Stub clamd on a unix socket, speaking zINSTREAM
+    let socket = dir.path().join("clamd.sock");
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut conn, _)) = listener.accept().await {
+            let mut command = Vec::new();
+            while command.last() != Some(&0) {
+                command.push(conn.read_u8().await.unwrap());
+            }
+            let reply = if command == b"zPING\0" {
+                "PONG".to_string()
+            } else {
+                let mut data = Vec::new();
+                loop {
+                    let len = conn.read_u32().await.unwrap() as usize;
+                    if len == 0 {
+                        break;
+                    }
+                    let start = data.len();
+                    data.resize(start + len, 0);
+                    conn.read_exact(&mut data[start..]).await.unwrap();
+                }
+                if data.windows(4).any(|w| w == b"EVIL") {
+                    "stream: Stub.Evil FOUND".into()
+                } else {
+                    "stream: OK".into()
+                }
+            };
+            conn.write_all(format!("{}\0", reply).as_bytes())
+                .await
+                .unwrap();
+        }
+    });
+
+    let clamd = Scanner::Clamd(socket.display().to_string());
+    clamd.check().await.unwrap();
+    let file = dir.path().join("scan-me");
+    std::fs::write(&file, vec![b'a'; 200_000]).unwrap();
+    assert_eq!(clamd.scan(&file).await, Verdict::Clean);
+    std::fs::write(&file, b"EVIL").unwrap();
+    assert_eq!(
+        clamd.scan(&file).await,
+        Verdict::Infected("Stub.Evil".into())
+    );
+    assert!(matches!(
+        Scanner::Clamd(dir.path().join("nope.sock").display().to_string())
+            .scan(&file)
+            .await,
+        Verdict::Failed(_)
+    ));
+}
+
+#[test]
+fn test_vault_seals_at_rest_and_opens_with_identity() {
+    use deadrop::receive::OnConflict;
+    use deadrop::vault::{self, Vault};
+    use std::path::Path;
+
+    let identity = age::x25519::Identity::generate();
+    let dir = tempfile::tempdir().unwrap();
+    let key_file = dir.path().join("key.txt");
+    std::fs::write(
+        &key_file,
+        format!(
+            "# created: today\n# public key: {}\n{}\n",
+            identity.to_public(),
+            age::secrecy::ExposeSecret::expose_secret(&identity.to_string())
+        ),
+    )
+    .unwrap();
+    // The identity file doubles as the public key source
+    let vault = Vault::parse(key_file.to_str().unwrap()).unwrap();
+    assert_eq!(vault.recipient(), identity.to_public().to_string());
+    assert!(Vault::parse("age1nope").is_err());
+
+    let out = dir.path().join("inbox");
+    let secret = b"plaintext never hits the disk";
+    let rel = Path::new("docs/tax.pdf");
+    let saved = vault.save(&out, rel, secret, OnConflict::Rename).unwrap();
+    assert_eq!(saved, Some("docs/tax.pdf.age".into()));
+    let again = vault.save(&out, rel, secret, OnConflict::Rename).unwrap();
+    assert_eq!(again, Some("docs/tax (1).pdf.age".into()));
+    let sealed = std::fs::read(out.join("docs/tax.pdf.age")).unwrap();
+    assert!(!sealed.windows(secret.len()).any(|w| w == secret));
+
+    let identities = vault::read_identities(&key_file).unwrap();
+    let opened = vault::open_file(&out.join("docs/tax.pdf.age"), &identities, &out).unwrap();
+    assert_eq!(opened, out.join("tax.pdf"));
+    assert_eq!(std::fs::read(&opened).unwrap(), secret);
+    // Opening twice never clobbers the first copy
+    let twice = vault::open_file(&out.join("docs/tax.pdf.age"), &identities, &out).unwrap();
+    assert_eq!(twice, out.join("tax (1).pdf"));
+
+    let stranger = vec![age::x25519::Identity::generate()];
+    assert!(vault::open_file(&out.join("docs/tax.pdf.age"), &stranger, dir.path()).is_err());
+    assert!(!dir.path().join("tax.pdf").exists());
+}
+
+#[test]
+fn test_stream_decryptor_releases_plaintext_chunk_by_chunk() {
+    use deadrop::crypto::{CHUNK_SIZE, EncryptionKey, StreamDecryptor, encrypt_file_streaming};
+
+    let key = EncryptionKey::generate();
+    let data: Vec<u8> = (0..CHUNK_SIZE * 3 + 123).map(|i| (i % 251) as u8).collect();
+    let ct =
+        encrypt_file_streaming(&mut Cursor::new(&data), &key, data.len() as u64, |_| {}).unwrap();
+
+    // Odd-sized pieces, like upload chunks that don't line up with ours
+    let mut stream = StreamDecryptor::new(&key).unwrap();
+    let mut out = Vec::new();
+    for piece in ct.chunks(10_007) {
+        out.extend(stream.push(piece).unwrap());
+        assert!(out.len() <= data.len());
+    }
+    assert_eq!(stream.finish().unwrap(), data.len() as u64);
+    assert_eq!(out, data);
+
+    // Plaintext comes out before the upload is over, and a short stream fails
+    let mut stream = StreamDecryptor::new(&key).unwrap();
+    let first = stream.push(&ct[..ct.len() / 2]).unwrap();
+    assert!(!first.is_empty() && data.starts_with(&first));
+    assert!(stream.finish().is_err());
+
+    let mut tampered = ct.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    let mut stream = StreamDecryptor::new(&key).unwrap();
+    assert!(stream.push(&tampered).is_err());
+
+    let mut wrong_key = StreamDecryptor::new(&EncryptionKey::generate()).unwrap();
+    assert!(wrong_key.push(&ct).is_err());
+
+    // A streamed chunked upload never touches the disk
+    let partials = deadrop::receive::Partials::default();
+    let id = partials
+        .start_stream(&key, "x.tar".into(), ct.len() as u64, None)
+        .unwrap();
+    let partial = partials.get(&id).unwrap();
+    let mut partial = partial.try_lock().unwrap();
+    partial.append(0, &ct[..70_000]).unwrap();
+    assert_eq!(std::mem::take(&mut partial.plaintext), data[..CHUNK_SIZE]);
+    partial.append(70_000, &ct[70_000..]).unwrap();
+    assert!(partial.take().is_err());
+    assert_eq!(
+        partial.finish_stream().unwrap(),
+        (data.len() as u64, deadrop::crypto::sha256(&data))
+    );
+}
+
+#[test]
+fn test_decrypting_reader_checks_the_container_as_it_reads() {
+    use deadrop::crypto::{CHUNK_SIZE, DecryptingReader, EncryptionKey, encrypt_file_streaming};
+    use std::io::Read;
+
+    let key = EncryptionKey::generate();
+    let data: Vec<u8> = (0..CHUNK_SIZE * 3 + 123).map(|i| (i % 251) as u8).collect();
+    let sha256 = deadrop::crypto::sha256(&data);
+    let ct =
+        encrypt_file_streaming(&mut Cursor::new(&data), &key, data.len() as u64, |_| {}).unwrap();
+
+    let mut reader = DecryptingReader::new(Cursor::new(&ct), &key, Some(sha256)).unwrap();
+    assert_eq!(reader.original_size(), data.len() as u64);
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out).unwrap();
+    assert_eq!(out, data);
+    assert_eq!(reader.sha256(), sha256);
+    assert!(reader.failure().is_none());
+
+    let read_all = |ct: &[u8], expected: Option<[u8; 32]>| {
+        let mut reader = DecryptingReader::new(Cursor::new(ct.to_vec()), &key, expected).unwrap();
+        let read = reader.read_to_end(&mut Vec::new());
+        assert_eq!(read.is_err(), reader.failure().is_some());
+        reader.failure().map(str::to_string)
+    };
+    // A cut-off container, a doctored chunk, and the wrong plaintext all fail
+    let short = &ct[..ct.len() - CHUNK_SIZE];
+    assert!(
+        read_all(short, None)
+            .unwrap()
+            .starts_with("Decryption failed")
+    );
+    let mut tampered = ct.clone();
+    tampered[100] ^= 1;
+    assert!(
+        read_all(&tampered, None)
+            .unwrap()
+            .starts_with("Decryption failed")
+    );
+    let altered = read_all(&ct, Some([0u8; 32])).unwrap();
+    assert!(altered.starts_with("Integrity check failed"));
+
+    assert!(DecryptingReader::new(Cursor::new(&ct[..20]), &key, None).is_err());
+}
+
+#[test]
+fn test_parallel_pipeline_matches_serial_layout() {
+    use deadrop::crypto::{EncryptedHeader, EncryptionKey, Pipeline, decrypt_container};
+
+    let key = EncryptionKey::generate();
+    // Uneven length so the last chunk is short
+    let data: Vec<u8> = (0..1_000_003u32).map(|i| (i * 31 % 251) as u8).collect();
+
+    for workers in [1, 2, 8] {
+        let pipeline = Pipeline {
+            chunk_size: deadrop::crypto::CHUNK_SIZE,
+            workers,
+        };
+        let mut out = vec![0u8; EncryptedHeader::SIZE];
+        let last = std::cell::Cell::new(0);
+        let header = pipeline
+            .encrypt(&mut Cursor::new(&data), &key, &mut out, |bytes| {
+                assert!(bytes > last.get(), "progress must only grow");
+                last.set(bytes);
+            })
+            .unwrap();
+        assert_eq!(header.original_size, data.len() as u64);
+        assert_eq!(header.total_chunks, 16);
+        assert_eq!(last.get(), data.len() as u64);
+        out[..EncryptedHeader::SIZE].copy_from_slice(&header.to_bytes());
+
+        assert_eq!(decrypt_container(&out, &key).unwrap(), data);
+    }
+
+    // Empty input still yields a valid, empty container
+    let mut out = vec![0u8; EncryptedHeader::SIZE];
+    let header = Pipeline::default()
+        .encrypt(&mut Cursor::new(&[]), &key, &mut out, |_| {})
+        .unwrap();
+    assert_eq!(header.total_chunks, 0);
+    out[..EncryptedHeader::SIZE].copy_from_slice(&header.to_bytes());
+    assert!(decrypt_container(&out, &key).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_growing_drop_serves_chunks_as_they_seal() {
+    use deadrop::crypto::{CHUNK_SIZE, EncryptionKey, GrowingContainer, decrypt_container};
+    use std::time::Duration;
+
+    let key = EncryptionKey::generate();
+    let data = vec![9u8; CHUNK_SIZE * 3 + 17];
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("growing.enc");
+    let mut file = std::fs::File::create(&path).unwrap();
+    let container = GrowingContainer::start(&mut file, data.len() as u64, CHUNK_SIZE).unwrap();
+    assert_eq!(container.header().total_chunks, 4);
+
+    let mut drop = make_drop("growing", 0, 1);
+    drop.encrypted_path = Some(path.clone());
+    drop.ciphertext = None;
+    drop.encrypted_size = deadrop::crypto::encrypted_len(data.len() as u64);
+    drop.total_chunks = 4;
+    drop.file_size = data.len() as u64;
+    drop.sealing = Some(deadrop::store::Sealing::new(CHUNK_SIZE));
+    let drop = std::sync::Arc::new(drop);
+
+    // Only the header is down: chunks wait, the header can be read
+    let header_len = deadrop::crypto::EncryptedHeader::SIZE as u64;
+    assert_eq!(drop.bytes_ready(0).await.unwrap(), header_len);
+    let pending = tokio::time::timeout(Duration::from_millis(50), drop.chunk_ready(0)).await;
+    assert!(pending.is_err(), "chunk 0 is not written yet");
+
+    let sealer = drop.clone();
+    let key2 = key.clone();
+    let input = data.clone();
+    let sealed = tokio::task::spawn_blocking(move || {
+        let sealing = sealer.sealing.as_ref().unwrap();
+        container
+            .seal(&mut Cursor::new(&input), &key2, &mut file, |bytes| {
+                sealing.advance(bytes)
+            })
+            .unwrap();
+        sealing.finish(input.len() as u64, Some("digest".to_string()));
+    });
+    drop.chunk_ready(3).await.unwrap();
+    sealed.await.unwrap();
+    assert_eq!(drop.bytes_ready(0).await.unwrap(), drop.encrypted_size);
+    assert_eq!(drop.sealed_digest().as_deref(), Some("digest"));
+    assert!(drop.sealing.as_ref().unwrap().progress().done);
+
+    let container = std::fs::read(&path).unwrap();
+    assert_eq!(container.len() as u64, drop.encrypted_size);
+    assert_eq!(decrypt_container(&container, &key).unwrap(), data);
+
+    // A failed seal releases everyone waiting, with an error
+    let mut failing = make_drop("failing", 0, 1);
+    failing.sealing = Some(deadrop::store::Sealing::new(CHUNK_SIZE));
+    failing.encrypted_size = 1 << 20;
+    failing.sealing.as_ref().unwrap().fail();
+    assert!(failing.chunk_ready(1).await.is_err());
+    assert!(failing.bytes_ready(100).await.is_err());
+
+    // Input that is not the announced length is refused
+    let mut out = Vec::new();
+    let short = GrowingContainer::start(&mut out, 1000, CHUNK_SIZE).unwrap();
+    assert!(
+        short
+            .seal(&mut Cursor::new(vec![0u8; 999]), &key, &mut out, |_| {})
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_folder_archived_as_it_seals() {
+    use deadrop::crypto::{CHUNK_SIZE, EncryptionKey, GrowingContainer, decrypt_container};
+    use std::io::Read;
+    use std::time::Duration;
+
+    let key = EncryptionKey::generate();
+    let dir = tempfile::tempdir().unwrap();
+    let folder = dir.path().join("photos");
+    std::fs::create_dir(&folder).unwrap();
+    // Random, so the archive comes to several chunks
+    let photo: Vec<u8> = (0..CHUNK_SIZE * 3).map(|_| rand::random::<u8>()).collect();
+    std::fs::write(folder.join("a.jpg"), &photo).unwrap();
+    std::fs::write(folder.join("notes.txt"), b"hello").unwrap();
+
+    let path = dir.path().join("folder.enc");
+    let mut file = std::fs::File::create(&path).unwrap();
+    let container = GrowingContainer::start_open(&mut file, CHUNK_SIZE).unwrap();
+
+    let mut drop = make_drop("folder", 0, 1);
+    drop.encrypted_path = Some(path.clone());
+    drop.ciphertext = None;
+    (drop.encrypted_size, drop.total_chunks, drop.file_size) = (0, 0, 0);
+    drop.sealing = Some(deadrop::store::Sealing::open(CHUNK_SIZE));
+    let drop = std::sync::Arc::new(drop);
+
+    // No size and no byte of the stream until the header is final
+    assert!(drop.size_open());
+    assert_eq!(drop.total_chunks(), 0);
+    let pending = tokio::time::timeout(Duration::from_millis(50), drop.bytes_ready(0)).await;
+    assert!(pending.is_err(), "the header is not final yet");
+
+    let sealer = drop.clone();
+    let (key2, folder2) = (key.clone(), folder.clone());
+    let sealed = tokio::task::spawn_blocking(move || {
+        let sealing = sealer.sealing.as_ref().unwrap();
+        let mut archive =
+            deadrop::archive::stream_folder(&folder2, indicatif::ProgressBar::hidden()).unwrap();
+        let header = container
+            .seal_open(&mut archive, &key2, &mut file, |bytes| {
+                sealing.advance(bytes)
+            })
+            .unwrap();
+        sealing.finish(header.original_size, Some("digest".to_string()));
+        header
+    });
+    drop.chunk_ready(0).await.unwrap();
+    let header = sealed.await.unwrap();
+    assert!(header.total_chunks > 1);
+
+    assert!(!drop.size_open());
+    assert_eq!(drop.total_chunks(), header.total_chunks);
+    assert_eq!(drop.file_size(), header.original_size);
+    let container = std::fs::read(&path).unwrap();
+    assert_eq!(container.len() as u64, drop.encrypted_size());
+    assert_eq!(drop.bytes_ready(0).await.unwrap(), drop.encrypted_size());
+
+    // The rewritten header opens the container, and the archive is whole
+    let archive = decrypt_container(&container, &key).unwrap();
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(&archive[..]));
+    let mut files = std::collections::BTreeMap::new();
+    for entry in tar.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).unwrap();
+        files.insert(entry.path().unwrap().display().to_string(), content);
+    }
+    assert_eq!(files["photos/a.jpg"], photo);
+    assert_eq!(files["photos/notes.txt"], b"hello");
+
+    // Any chunk may be the last, so the latest one waits for the end
+    let sealing = deadrop::store::Sealing::open(CHUNK_SIZE);
+    sealing.advance(CHUNK_SIZE as u64 * 2);
+    let progress = sealing.progress();
+    assert_eq!((progress.chunks, progress.bytes), (1, 0));
+
+    // A folder that isn't there fails before anything is sealed
+    assert!(
+        deadrop::archive::stream_folder(
+            &dir.path().join("missing"),
+            indicatif::ProgressBar::hidden()
+        )
+        .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_growing_password_drop_serves_slots_as_they_seal() {
+    use deadrop::crypto::{
+        CHUNK_SIZE, EncryptionKey, MIN_CHUNK_SIZE, SlotInput, decrypt_deniable, deniable_layout,
+        encrypt_deniable,
+    };
+    use std::time::Duration;
+
+    let key = EncryptionKey::from_password("real", &[3u8; 16]).unwrap();
+    let data = vec![5u8; CHUNK_SIZE * 2 + 100];
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("deniable.enc");
+    let mut file = std::fs::File::create(&path).unwrap();
+
+    let layout = {
+        let mut reader = Cursor::new(&data);
+        let input = SlotInput {
+            reader: &mut reader,
+            len: data.len() as u64,
+            filename: "plans.pdf".to_string(),
+            key: &key,
+        };
+        deniable_layout(&input, None, MIN_CHUNK_SIZE)
+    };
+    assert_eq!(layout.chunks, 16);
+
+    let mut drop = make_drop("deniable", 0, 1);
+    drop.encrypted_path = Some(path.clone());
+    drop.ciphertext = None;
+    drop.encrypted_size = layout.size();
+    drop.slot_size = layout.slot_size;
+    drop.has_password = true;
+    drop.sealing = Some(deadrop::store::Sealing::deniable(layout));
+    let drop = std::sync::Arc::new(drop);
+
+    // Not even a header is down yet
+    let pending = tokio::time::timeout(Duration::from_millis(50), drop.bytes_ready(0)).await;
+    assert!(pending.is_err(), "nothing is written yet");
+
+    // Every byte reported ready is on disk: readers never run ahead of the file
+    let (sealer, seal_key, input, seal_path) =
+        (drop.clone(), key.clone(), data.clone(), path.clone());
+    let sealed = tokio::task::spawn_blocking(move || {
+        let sealing = sealer.sealing.as_ref().unwrap();
+        let total = layout.plaintext_len();
+        encrypt_deniable(
+            &mut file,
+            SlotInput {
+                reader: &mut Cursor::new(&input),
+                len: input.len() as u64,
+                filename: "plans.pdf".to_string(),
+                key: &seal_key,
+            },
+            None,
+            MIN_CHUNK_SIZE,
+            |bytes| {
+                if bytes < total {
+                    sealing.advance(bytes);
+                }
+                let on_disk = std::fs::metadata(&seal_path).unwrap().len();
+                assert!(sealing.progress().bytes <= on_disk);
+            },
+        )
+        .unwrap();
+        sealing.finish(total, None);
+    });
+
+    // A reader waiting inside the first slot is let through once it is sealed
+    let first = drop.bytes_ready(layout.slot_size - 1).await.unwrap();
+    assert!(first >= layout.slot_size);
+    sealed.await.unwrap();
+    assert_eq!(drop.bytes_ready(0).await.unwrap(), layout.size());
+
+    let container = std::fs::read(&path).unwrap();
+    assert_eq!(container.len() as u64, layout.size());
+    let (name, opened) = decrypt_deniable(&container, &key).unwrap();
+    assert_eq!(name, "plans.pdf");
+    assert_eq!(opened, data);
+}
+
+#[test]
+fn test_recorded_chunk_size_drives_decryption() {
+    use deadrop::crypto::{
+        CHUNK_SIZE, EncryptedHeader, EncryptionKey, MAX_CHUNK_SIZE, MIN_CHUNK_SIZE, Pipeline,
+        StreamDecryptor, check_chunk_size, decrypt_container, encrypted_len_with,
+    };
+
+    let key = EncryptionKey::generate();
+    let data: Vec<u8> = (0..3_000_001u32).map(|i| (i * 7 % 253) as u8).collect();
+
+    for chunk_size in [MIN_CHUNK_SIZE, CHUNK_SIZE, 1024 * 1024] {
+        let pipeline = Pipeline {
+            chunk_size,
+            ..Default::default()
+        };
+        let ct = pipeline
+            .encrypt_to_vec(&mut Cursor::new(&data), &key, data.len() as u64, |_| {})
+            .unwrap();
+        assert_eq!(
+            ct.len() as u64,
+            encrypted_len_with(data.len() as u64, chunk_size)
+        );
+
+        // The default size keeps the original 40-byte header
+        let header = EncryptedHeader::from_bytes(&ct).unwrap();
+        assert_eq!(header.chunk_size, chunk_size);
+        assert_eq!(header.total_chunks, data.len().div_ceil(chunk_size) as u64);
+        assert_eq!(header.encoded_len(), EncryptedHeader::len_of(&ct));
+        let expected_len = match chunk_size {
+            CHUNK_SIZE => EncryptedHeader::SIZE,
+            _ => EncryptedHeader::SIZED,
+        };
+        assert_eq!(header.encoded_len(), expected_len);
+
+        assert_eq!(decrypt_container(&ct, &key).unwrap(), data);
+        let mut stream = StreamDecryptor::new(&key).unwrap();
+        let mut out = Vec::new();
+        for piece in ct.chunks(10_007) {
+            out.extend(stream.push(piece).unwrap());
+        }
+        assert_eq!(stream.finish().unwrap(), data.len() as u64);
+        assert_eq!(out, data);
+    }
+
+    // Chunks bigger than the header admits are refused
+    let big = Pipeline {
+        chunk_size: 1024 * 1024,
+        ..Default::default()
+    };
+    let mut ct = big
+        .encrypt_to_vec(&mut Cursor::new(&data), &key, data.len() as u64, |_| {})
+        .unwrap();
+    ct[4..8].copy_from_slice(&(MIN_CHUNK_SIZE as u32).to_le_bytes());
+    assert!(decrypt_container(&ct, &key).is_err());
+    assert!(StreamDecryptor::new(&key).unwrap().push(&ct).is_err());
+
+    // Sizes out of range are refused, on the command line and in a header
+    assert!(check_chunk_size(MIN_CHUNK_SIZE as u64 - 1).is_err());
+    assert!(check_chunk_size(MAX_CHUNK_SIZE as u64 + 1).is_err());
+    assert_eq!(
+        check_chunk_size(MAX_CHUNK_SIZE as u64).unwrap(),
+        MAX_CHUNK_SIZE
+    );
+    ct[4..8].copy_from_slice(&(MAX_CHUNK_SIZE as u32 * 2).to_le_bytes());
+    assert!(EncryptedHeader::from_bytes(&ct).is_err());
+}
@@ -11,6 +11,47 @@ use zeroize::Zeroize;
 
 const HEADER_SIZE: usize = 40; // 24 (nonce) + 8 (chunk_count) + 8 (original_size)
 
+/// A header that records a non-default chunk size starts with this magic
+/// and a u32 LE chunk size — matches the server's `crypto::EncryptedHeader`
+const SIZED_HEADER_MAGIC: &[u8; 4] = b"DDcs";
+const SIZED_HEADER_SIZE: usize = 4 + 4 + HEADER_SIZE;
+const MIN_CHUNK_SIZE: usize = 16 * 1024;
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// A parsed container header
+struct Header {
+    nonce: [u8; 24],
+    total_chunks: u64,
+    original_size: u64,
+    chunk_size: usize,
+    len: usize, // Bytes the header takes up
+}
+
+fn read_header(data: &[u8]) -> Result<Header, JsValue> {
+    let (chunk_size, len) = match data.strip_prefix(SIZED_HEADER_MAGIC) {
+        Some(rest) if data.len() >= SIZED_HEADER_SIZE => {
+            let chunk_size = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+            if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+                return Err(JsValue::from_str("Bad chunk size in header"));
+            }
+            (chunk_size, SIZED_HEADER_SIZE)
+        }
+        Some(_) => return Err(JsValue::from_str("Data too short to contain header")),
+        None if data.len() >= HEADER_SIZE => (CHUNK_SIZE, HEADER_SIZE),
+        None => return Err(JsValue::from_str("Data too short to contain header")),
+    };
+    let fields = &data[len - HEADER_SIZE..len];
+    let mut nonce = [0u8; 24];
+    nonce.copy_from_slice(&fields[..24]);
+    Ok(Header {
+        nonce,
+        total_chunks: u64::from_le_bytes(fields[24..32].try_into().unwrap()),
+        original_size: u64::from_le_bytes(fields[32..40].try_into().unwrap()),
+        chunk_size,
+        len,
+    })
+}
+
 // ═══════════════════════════════════════════════════════════════
 // Argon2id key derivation — matches server's crypto::EncryptionKey::from_password
 // Params: Argon2id v0x13, m=65536 (64 MB), t=3, p=1, output=32 bytes
@@ -95,14 +136,16 @@ pub fn decrypt_chunk(
     Ok(decrypted)
 }
 
-/// Parse the 40-byte header from the encrypted blob.
-/// Returns [nonce(24), total_chunks(8), original_size(8)] as a flat Uint8Array.
+/// Parse the header from the encrypted blob (40 bytes, or 48 when it
+/// records a chunk size). Returns [nonce(24), total_chunks(8),
+/// original_size(8), chunk_size(4)] as a flat Uint8Array.
 #[wasm_bindgen]
 pub fn parse_header(data: &[u8]) -> Result<Vec<u8>, JsValue> {
-    if data.len() < HEADER_SIZE {
-        return Err(JsValue::from_str("Data too short to contain header"));
-    }
-    Ok(data[..HEADER_SIZE].to_vec())
+    let header = read_header(data)?;
+    let mut out = Vec::with_capacity(HEADER_SIZE + 4);
+    out.extend_from_slice(&data[header.len - HEADER_SIZE..header.len]);
+    out.extend_from_slice(&(header.chunk_size as u32).to_le_bytes());
+    Ok(out)
 }
 
 /// Full in-memory decryption (for small files or when streaming isn't available).
@@ -111,31 +154,252 @@ pub fn decrypt_blob(
     encrypted_data: &[u8],
     key_base64: &str,
 ) -> Result<Vec<u8>, JsValue> {
-    if encrypted_data.len() < HEADER_SIZE {
-        return Err(JsValue::from_str("Data too short"));
+    let key_bytes = URL_SAFE_NO_PAD
+        .decode(key_base64)
+        .map_err(|e| JsValue::from_str(&format!("Invalid key: {}", e)))?;
+    if key_bytes.len() != 32 {
+        return Err(JsValue::from_str("Invalid key length"));
     }
+    decrypt_container(encrypted_data, &key_bytes)
+}
 
+/// Plaintext bytes per chunk — matches the server's `crypto::CHUNK_SIZE`
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Encrypt a whole file into the standard container (receive-mode uploads).
+/// Layout matches `decrypt_blob`: header, then `[u32 len][ct+tag]` per chunk.
+#[wasm_bindgen]
+pub fn encrypt_blob(plaintext: &[u8], key_base64: &str) -> Result<Vec<u8>, JsValue> {
     let key_bytes = URL_SAFE_NO_PAD
         .decode(key_base64)
         .map_err(|e| JsValue::from_str(&format!("Invalid key: {}", e)))?;
     if key_bytes.len() != 32 {
         return Err(JsValue::from_str("Invalid key length"));
     }
+    let cipher = XChaCha20Poly1305::new_from_slice(&key_bytes)
+        .map_err(|_| JsValue::from_str("Failed to init cipher"))?;
+
+    let nonce_bytes = fresh_nonce()?;
+    let total_chunks = plaintext.len().div_ceil(CHUNK_SIZE) as u64;
+
+    let mut out = Vec::with_capacity(HEADER_SIZE + plaintext.len() + total_chunks as usize * 20);
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&total_chunks.to_le_bytes());
+    out.extend_from_slice(&(plaintext.len() as u64).to_le_bytes());
+
+    for (chunk_index, chunk) in plaintext.chunks(CHUNK_SIZE).enumerate() {
+        let mut chunk_nonce = nonce_bytes;
+        let idx_bytes = (chunk_index as u64).to_le_bytes();
+        for i in 0..8 {
+            chunk_nonce[i] ^= idx_bytes[i];
+        }
+        let encrypted = cipher
+            .encrypt(&chacha20poly1305::XNonce::from(chunk_nonce), chunk)
+            .map_err(|_| JsValue::from_str("Encryption failed"))?;
+        out.extend_from_slice(&(encrypted.len() as u32).to_le_bytes());
+        out.extend_from_slice(&encrypted);
+    }
+    Ok(out)
+}
 
-    // Parse header
+/// A base nonce for a new container. One that reads as the sized-header
+/// magic is drawn again.
+fn fresh_nonce() -> Result<[u8; 24], JsValue> {
     let mut nonce_bytes = [0u8; 24];
-    nonce_bytes.copy_from_slice(&encrypted_data[..24]);
-    let total_chunks = u64::from_le_bytes(
-        encrypted_data[24..32].try_into().map_err(|_| JsValue::from_str("Bad header"))?
-    );
-    let original_size = u64::from_le_bytes(
-        encrypted_data[32..40].try_into().map_err(|_| JsValue::from_str("Bad header"))?
-    );
+    loop {
+        getrandom::fill(&mut nonce_bytes)
+            .map_err(|e| JsValue::from_str(&format!("No randomness: {}", e)))?;
+        if !nonce_bytes.starts_with(SIZED_HEADER_MAGIC) {
+            return Ok(nonce_bytes);
+        }
+    }
+}
 
-    let cipher = XChaCha20Poly1305::new_from_slice(&key_bytes)
+/// Encrypts an upload chunk by chunk as the worker reads the file, so no
+/// more than a chunk of it is ever in memory. A chunk's ciphertext depends
+/// only on its index under the container's nonce, so a resumed upload can
+/// encrypt again whichever chunks it has to resend. The plaintext is
+/// hashed once, in order, for the sealed digest sent on finish.
+#[wasm_bindgen]
+pub struct UploadEncryptor {
+    cipher: XChaCha20Poly1305,
+    nonce: [u8; 24],
+    size: u64,
+    hasher: sha2::Sha256,
+    hashed: u64, // Chunks hashed so far
+}
+
+#[wasm_bindgen]
+impl UploadEncryptor {
+    /// Start a container for `size` plaintext bytes under the given key
+    #[wasm_bindgen(constructor)]
+    pub fn new(key_base64: &str, size: u64) -> Result<UploadEncryptor, JsValue> {
+        use sha2::Digest;
+        Ok(UploadEncryptor {
+            cipher: digest_cipher(key_base64)?,
+            nonce: fresh_nonce()?,
+            size,
+            hasher: sha2::Sha256::new(),
+            hashed: 0,
+        })
+    }
+
+    fn total_chunks(&self) -> u64 {
+        self.size.div_ceil(CHUNK_SIZE as u64)
+    }
+
+    /// The container header: the first bytes of the upload
+    pub fn header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(&self.nonce);
+        header.extend_from_slice(&self.total_chunks().to_le_bytes());
+        header.extend_from_slice(&self.size.to_le_bytes());
+        header
+    }
+
+    /// Size of the whole container: what the upload announces
+    #[wasm_bindgen(getter)]
+    pub fn encrypted_size(&self) -> u64 {
+        HEADER_SIZE as u64 + self.size + self.total_chunks() * (4 + 16)
+    }
+
+    /// Plaintext bytes per chunk
+    #[wasm_bindgen(getter)]
+    pub fn chunk_size(&self) -> u32 {
+        CHUNK_SIZE as u32
+    }
+
+    /// Encrypt chunk `index` into its `[u32 len][ct+tag]` frame. The
+    /// plaintext must be exactly [`UploadEncryptor::chunk_size`] bytes,
+    /// except for the final chunk, which holds the rest of the file.
+    pub fn chunk(&mut self, index: u64, plaintext: &[u8]) -> Result<Vec<u8>, JsValue> {
+        use sha2::Digest;
+        if index >= self.total_chunks() {
+            return Err(JsValue::from_str("Chunk past the end of the file"));
+        }
+        // Every chunk but the last is full; the last holds what's left
+        let expected = (self.size - index * CHUNK_SIZE as u64).min(CHUNK_SIZE as u64);
+        if plaintext.len() as u64 != expected {
+            return Err(JsValue::from_str(&format!(
+                "Chunk {} is {} bytes, expected {}", index, plaintext.len(), expected
+            )));
+        }
+        let mut chunk_nonce = self.nonce;
+        for (n, b) in index.to_le_bytes().iter().enumerate() {
+            chunk_nonce[n] ^= b;
+        }
+        let encrypted = self
+            .cipher
+            .encrypt(&chacha20poly1305::XNonce::from(chunk_nonce), plaintext)
+            .map_err(|_| JsValue::from_str("Encryption failed"))?;
+        if index == self.hashed {
+            self.hasher.update(plaintext);
+            self.hashed += 1;
+        }
+        let mut frame = Vec::with_capacity(4 + encrypted.len());
+        frame.extend_from_slice(&(encrypted.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&encrypted);
+        Ok(frame)
+    }
+
+    /// The plaintext SHA-256 sealed under the key, once every chunk has
+    /// been through [`UploadEncryptor::chunk`]
+    pub fn sealed_digest(&self) -> Result<String, JsValue> {
+        use sha2::Digest;
+        if self.hashed != self.total_chunks() {
+            return Err(JsValue::from_str("Not every chunk has been encrypted"));
+        }
+        seal_sha256(&self.cipher, &self.hasher.clone().finalize())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Deniable two-slot containers (password drops) — matches server's
+// crypto::encrypt_deniable. Slot plaintext layout:
+//   [data_len u64 LE][name_len u16 LE][name][data][zero padding]
+// ═══════════════════════════════════════════════════════════════
+
+const DENIABLE_SLOTS: usize = 2;
+const SLOT_PREFIX_SIZE: usize = 8 + 2;
+
+/// Payload recovered from whichever slot the derived key opened.
+#[wasm_bindgen]
+pub struct SlotPayload {
+    filename: String,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl SlotPayload {
+    #[wasm_bindgen(getter)]
+    pub fn filename(&self) -> String {
+        self.filename.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// Try the key against both slots of a password drop and return the one it
+/// opens. Neither the caller nor the server learns whether the other slot
+/// holds a decoy or random filler.
+#[wasm_bindgen]
+pub fn decrypt_slots(
+    encrypted_data: &[u8],
+    key_base64: &str,
+) -> Result<SlotPayload, JsValue> {
+    if encrypted_data.is_empty() || !encrypted_data.len().is_multiple_of(DENIABLE_SLOTS) {
+        return Err(JsValue::from_str("Not a password-protected container"));
+    }
+    let key_bytes = URL_SAFE_NO_PAD
+        .decode(key_base64)
+        .map_err(|e| JsValue::from_str(&format!("Invalid key: {}", e)))?;
+    if key_bytes.len() != 32 {
+        return Err(JsValue::from_str("Invalid key length"));
+    }
+
+    let slot_len = encrypted_data.len() / DENIABLE_SLOTS;
+    for slot in encrypted_data.chunks(slot_len) {
+        if let Ok(mut plaintext) = decrypt_container(slot, &key_bytes) {
+            let payload = unpack_slot(&plaintext);
+            plaintext.zeroize();
+            return payload;
+        }
+    }
+    Err(JsValue::from_str("Decryption failed — wrong password or corrupted data"))
+}
+
+fn unpack_slot(plaintext: &[u8]) -> Result<SlotPayload, JsValue> {
+    if plaintext.len() < SLOT_PREFIX_SIZE {
+        return Err(JsValue::from_str("Slot too short"));
+    }
+    let data_len = u64::from_le_bytes(plaintext[..8].try_into().unwrap()) as usize;
+    let name_len = u16::from_le_bytes(plaintext[8..10].try_into().unwrap()) as usize;
+    let name_end = SLOT_PREFIX_SIZE + name_len;
+    if name_end + data_len > plaintext.len() {
+        return Err(JsValue::from_str("Slot length prefix exceeds slot size"));
+    }
+    Ok(SlotPayload {
+        filename: String::from_utf8_lossy(&plaintext[SLOT_PREFIX_SIZE..name_end]).to_string(),
+        data: plaintext[name_end..name_end + data_len].to_vec(),
+    })
+}
+
+fn decrypt_container(encrypted_data: &[u8], key_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let Header {
+        nonce: nonce_bytes,
+        total_chunks,
+        original_size,
+        chunk_size,
+        len,
+    } = read_header(encrypted_data)?;
+
+    let cipher = XChaCha20Poly1305::new_from_slice(key_bytes)
         .map_err(|_| JsValue::from_str("Failed to init cipher"))?;
 
-    let chunk_data = &encrypted_data[HEADER_SIZE..];
+    let chunk_data = &encrypted_data[len..];
     let mut plaintext = Vec::with_capacity(original_size as usize);
     let mut offset = 0;
 
@@ -147,6 +411,9 @@ pub fn decrypt_blob(
         let chunk_len = u32::from_le_bytes(
             chunk_data[offset..offset + 4].try_into().unwrap()
         ) as usize;
+        if chunk_len > chunk_size + 16 {
+            return Err(JsValue::from_str("Chunk larger than the recorded chunk size"));
+        }
         offset += 4;
 
         if offset + chunk_len > chunk_data.len() {
@@ -203,6 +470,161 @@ pub fn decrypt_envelope(shared_key_base64: &str, payload: &[u8]) -> Result<Vec<u
     Ok(decrypted)
 }
 
+// ═══════════════════════════════════════════════════════════════
+// Proof-of-download receipts — matches server's receipt.rs:
+//   receipt_key = HMAC-SHA256(content_key, "deadrop-receipt-v1")
+//   mac = HMAC-SHA256(receipt_key, "deadrop-receipt-v1" || drop_id || sha256(plaintext))
+// ═══════════════════════════════════════════════════════════════
+
+const RECEIPT_CONTEXT: &[u8] = b"deadrop-receipt-v1";
+
+/// Hex digest and MAC to post to `/api/receipt/{id}`.
+#[wasm_bindgen]
+pub struct Receipt {
+    sha256: String,
+    mac: String,
+}
+
+#[wasm_bindgen]
+impl Receipt {
+    #[wasm_bindgen(getter)]
+    pub fn sha256(&self) -> String {
+        self.sha256.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn mac(&self) -> String {
+        self.mac.clone()
+    }
+}
+
+/// Build the receipt for a fully decrypted payload. `key_base64` is the
+/// content key that opened it (derived from the password for password drops).
+#[wasm_bindgen]
+pub fn receipt_for(
+    plaintext: &[u8],
+    key_base64: &str,
+    drop_id: &str,
+) -> Result<Receipt, JsValue> {
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256};
+    type HmacSha256 = Hmac<Sha256>;
+
+    let mut key_bytes = URL_SAFE_NO_PAD
+        .decode(key_base64)
+        .map_err(|e| JsValue::from_str(&format!("Invalid key: {}", e)))?;
+    if key_bytes.len() != 32 {
+        return Err(JsValue::from_str("Invalid key length"));
+    }
+
+    let digest = Sha256::digest(plaintext);
+    let mut kdf = <HmacSha256 as Mac>::new_from_slice(&key_bytes)
+        .map_err(|_| JsValue::from_str("Failed to init HMAC"))?;
+    key_bytes.zeroize();
+    kdf.update(RECEIPT_CONTEXT);
+    let mut receipt_key = kdf.finalize().into_bytes();
+
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(&receipt_key)
+        .map_err(|_| JsValue::from_str("Failed to init HMAC"))?;
+    receipt_key.zeroize();
+    mac.update(RECEIPT_CONTEXT);
+    mac.update(drop_id.as_bytes());
+    mac.update(&digest);
+
+    Ok(Receipt {
+        sha256: to_hex(&digest),
+        mac: to_hex(&mac.finalize().into_bytes()),
+    })
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Sealed plaintext digest — matches server's crypto::seal_digest:
+//   URL-safe base64 of [nonce 24][XChaCha20-Poly1305(sha256(plaintext), aad = context)]
+// ═══════════════════════════════════════════════════════════════
+
+const DIGEST_CONTEXT: &[u8] = b"deadrop-digest-v1";
+
+fn digest_cipher(key_base64: &str) -> Result<XChaCha20Poly1305, JsValue> {
+    let mut key_bytes = URL_SAFE_NO_PAD
+        .decode(key_base64)
+        .map_err(|e| JsValue::from_str(&format!("Invalid key: {}", e)))?;
+    if key_bytes.len() != 32 {
+        return Err(JsValue::from_str("Invalid key length"));
+    }
+    let cipher = XChaCha20Poly1305::new_from_slice(&key_bytes)
+        .map_err(|_| JsValue::from_str("Failed to init cipher"));
+    key_bytes.zeroize();
+    cipher
+}
+
+/// Seal the SHA-256 of an upload's plaintext for the receiver to check
+/// (sent as `digest` when starting a receive-mode upload).
+#[wasm_bindgen]
+pub fn seal_digest(plaintext: &[u8], key_base64: &str) -> Result<String, JsValue> {
+    use sha2::{Digest, Sha256};
+
+    seal_sha256(&digest_cipher(key_base64)?, &Sha256::digest(plaintext))
+}
+
+fn seal_sha256(cipher: &XChaCha20Poly1305, digest: &[u8]) -> Result<String, JsValue> {
+    use chacha20poly1305::aead::Payload;
+
+    let mut nonce = [0u8; 24];
+    getrandom::fill(&mut nonce)
+        .map_err(|e| JsValue::from_str(&format!("No randomness: {}", e)))?;
+    let sealed = cipher
+        .encrypt(
+            &chacha20poly1305::XNonce::from(nonce),
+            Payload { msg: digest, aad: DIGEST_CONTEXT },
+        )
+        .map_err(|_| JsValue::from_str("Encryption failed"))?;
+    Ok(URL_SAFE_NO_PAD.encode([&nonce[..], &sealed].concat()))
+}
+
+/// Check a decrypted file against the sender's sealed digest (`digest` in
+/// `/api/meta`). Password drops carry one per slot, joined by `.`; the key
+/// opens at most one. Returns the hex SHA-256, or an error if it doesn't match.
+#[wasm_bindgen]
+pub fn verify_digest(
+    plaintext: &[u8],
+    sealed_base64: &str,
+    key_base64: &str,
+) -> Result<String, JsValue> {
+    use sha2::{Digest, Sha256};
+
+    let cipher = digest_cipher(key_base64)?;
+    let expected = sealed_base64
+        .split('.')
+        .find_map(|part| open_digest(&cipher, part))
+        .ok_or_else(|| JsValue::from_str("Digest does not open with this key"))?;
+    let digest = Sha256::digest(plaintext);
+    if digest[..] != expected[..] {
+        return Err(JsValue::from_str(
+            "SHA-256 does not match the sender's — the file was altered in transit",
+        ));
+    }
+    Ok(to_hex(&digest))
+}
+
+fn open_digest(cipher: &XChaCha20Poly1305, sealed_base64: &str) -> Option<Vec<u8>> {
+    use chacha20poly1305::aead::Payload;
+
+    let sealed = URL_SAFE_NO_PAD.decode(sealed_base64).ok()?;
+    if sealed.len() != 24 + 32 + 16 {
+        return None;
+    }
+    cipher
+        .decrypt(
+            chacha20poly1305::XNonce::from_slice(&sealed[..24]),
+            Payload { msg: &sealed[24..], aad: DIGEST_CONTEXT },
+        )
+        .ok()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 /// Zeroize a vector of bytes passed from JS (e.g., derived key material).
 #[wasm_bindgen]
 pub fn zeroize_vec(mut v: Vec<u8>) {
@@ -221,4 +643,55 @@ mod tests {
         // cannot directly assert memory wiped, but ensure function runs
         assert!(true);
     }
+
+    #[test]
+    fn test_encrypt_blob_roundtrip() {
+        let key = URL_SAFE_NO_PAD.encode([7u8; 32]);
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 5).map(|i| i as u8).collect();
+        let encrypted = encrypt_blob(&data, &key).unwrap();
+        assert_eq!(decrypt_container(&encrypted, &[7u8; 32]).unwrap(), data);
+    }
+
+    #[test]
+    fn test_sized_header_is_read() {
+        let key = URL_SAFE_NO_PAD.encode([7u8; 32]);
+        let data: Vec<u8> = (0..CHUNK_SIZE + 5).map(|i| i as u8).collect();
+        let mut sized = SIZED_HEADER_MAGIC.to_vec();
+        sized.extend_from_slice(&(CHUNK_SIZE as u32).to_le_bytes());
+        sized.extend_from_slice(&encrypt_blob(&data, &key).unwrap());
+        assert_eq!(decrypt_container(&sized, &[7u8; 32]).unwrap(), data);
+        assert_eq!(parse_header(&sized).unwrap()[40..], (CHUNK_SIZE as u32).to_le_bytes());
+    }
+
+    #[test]
+    fn test_verify_digest_opens_its_own_slot() {
+        let key = URL_SAFE_NO_PAD.encode([7u8; 32]);
+        let other = URL_SAFE_NO_PAD.encode([9u8; 32]);
+        let data = b"plans".to_vec();
+        let sealed = seal_digest(&data, &key).unwrap();
+        let decoy = seal_digest(b"grocery list", &other).unwrap();
+
+        let expected = verify_digest(&data, &sealed, &key).unwrap();
+        assert_eq!(verify_digest(&data, &format!("{}.{}", decoy, sealed), &key).unwrap(), expected);
+        assert_eq!(verify_digest(&data, &format!("{}.{}", sealed, decoy), &key).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_upload_encryptor_matches_a_whole_container() {
+        let key = URL_SAFE_NO_PAD.encode([7u8; 32]);
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 5).map(|i| i as u8).collect();
+        let mut encryptor = UploadEncryptor::new(&key, data.len() as u64).unwrap();
+        let mut container = encryptor.header();
+        let chunks: Vec<&[u8]> = data.chunks(CHUNK_SIZE).collect();
+        for (index, chunk) in chunks.iter().enumerate() {
+            container.extend_from_slice(&encryptor.chunk(index as u64, chunk).unwrap());
+        }
+        // A resent chunk encrypts the same and is not hashed twice
+        assert_eq!(encryptor.chunk(1, chunks[1]).unwrap(), container[HEADER_SIZE + 4 + CHUNK_SIZE + 16..][..4 + CHUNK_SIZE + 16]);
+        assert_eq!(container.len() as u64, encryptor.encrypted_size());
+        assert_eq!(decrypt_container(&container, &[7u8; 32]).unwrap(), data);
+
+        let digest = encryptor.sealed_digest().unwrap();
+        assert_eq!(verify_digest(&data, &digest, &key).unwrap(), verify_digest(&data, &seal_digest(&data, &key).unwrap(), &key).unwrap());
+    }
 }
@@ -6,6 +6,8 @@
         throw new Error('Invalid drop route');
     }
     const dropId = decodeURIComponent(dropIdMatch[1]);
+    const accessToken = new URLSearchParams(window.location.search).get('t');
+    const tokenQuery = accessToken ? `?t=${encodeURIComponent(accessToken)}` : '';
 
     let key = null;
     if (window.location.hash && window.location.hash.length > 1) {
@@ -174,6 +176,7 @@
                 action: 'decrypt',
                 dropId: dropId,
                 key: key,
+                token: accessToken,
                 filename: fileMeta.filename,
                 mime: fileMeta.mime,
             });
@@ -183,7 +186,7 @@
             try {
                 const wasm = await import('/wasm/deadrop_wasm.js');
                 await wasm.default();
-                const res = await fetch(`/api/blob/${dropId}`);
+                const res = await fetch(`/api/blob/${dropId}${tokenQuery}`);
                 const encrypted = new Uint8Array(await res.arrayBuffer());
                 progressFill.style.width = '50%';
                 const plaintext = wasm.decrypt_blob(encrypted, key);
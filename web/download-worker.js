@@ -1,106 +1,175 @@
 // Streaming download worker (ES module worker)
-// Receives: { type: 'start', dropId, key }
-// Posts: { type: 'meta', ... }, { type: 'progress', idx, total }, { type: 'chunk', index, data }, { type: 'done' }, { type: 'error', message }
+// Receives: { type: 'start', dropId, key, token?, session?, filename?, mime? }
+// Posts: { type: 'progress', percent }, { type: 'complete', data, filename, mime }, { type: 'error', message }
+
+// A dropped chunk request is retried this many times within the session
+const CHUNK_ATTEMPTS = 3;
 
 self.onmessage = async (ev) => {
     const msg = ev.data;
     if (msg.type === 'start') {
         const dropId = msg.dropId;
         const key = msg.key; // URL-safe base64 key or password-derived key
-        const recipient_priv = msg.recipient_priv || null;
+        const params = new URLSearchParams();
+        if (msg.token) params.set('t', msg.token);
         try {
             // Load WASM
             const wasmImport = await import('/wasm/deadrop_wasm.js');
             await wasmImport.default('/wasm/deadrop_wasm_bg.wasm');
             const wasm = wasmImport;
 
-            // Fetch header metadata
+            // Fetch header metadata, picking up the session the page opened
+            if (msg.session) params.set('session', msg.session);
             self.postMessage({ type: 'debug', message: `fetch /api/chunks/${dropId}` });
-            const resp = await fetchWithTimeout(`/api/chunks/${encodeURIComponent(dropId)}`, 8000);
+            const resp = await fetchWithTimeout(`/api/chunks/${encodeURIComponent(dropId)}?${params}`, 8000);
             if (!resp.ok) {
                 const txt = await resp.text().catch(() => '');
                 throw new Error(`Failed to fetch chunk metadata: ${resp.status} ${txt}`);
             }
             const meta = await resp.json();
+            // Every chunk request goes out under the session the server issued
+            params.set('session', meta.session);
+            const query = `?${params}`;
+            const chunkAt = (idx) => {
+                self.postMessage({ type: 'debug', message: `fetch /api/chunk/${dropId}/${idx}` });
+                return fetchChunk(`/api/chunk/${encodeURIComponent(dropId)}/${idx}${query}`, idx);
+            };
 
-            // Decode nonce
-            const nonce_b64 = meta.nonce;
-            const nonce_bytes = Uint8Array.from(atob(nonce_b64.replace(/_/g,'/').replace(/-/g,'+')), c => c.charCodeAt(0));
-            // above base64 variant handling; server uses URL_SAFE_NO_PAD
-            // Better: use atob after transforming URL-safe
+            if (meta.slot_chunks !== undefined) {
+                const slot = await downloadSlots(wasm, key, meta, chunkAt);
+                self.postMessage({ type: 'complete', data: slot.data.buffer, filename: slot.filename, mime: msg.mime }, [slot.data.buffer]);
+                return;
+            }
 
-            const total = meta.total_chunks;
-            self.postMessage({ type: 'meta', filename: msg.filename || 'file', total_chunks: total, original_size: meta.original_size });
-            // Open IndexedDB to resume progress
-            const db = await openResumeDB();
-            const last = await getLastIndex(db, dropId);
-            let start_idx = (last !== null) ? (last + 1) : 0;
+            const nonce_bytes = decodeNonce(meta.nonce);
 
-            for (let idx = start_idx; idx < total; idx++) {
-                self.postMessage({ type: 'debug', message: `fetch /api/chunk/${dropId}/${idx}` });
-                const chunkResp = await fetchWithTimeout(`/api/chunk/${encodeURIComponent(dropId)}/${idx}`, 10000);
-                if (!chunkResp.ok) {
-                    const txt = await chunkResp.text().catch(() => '');
-                    throw new Error(`Failed to fetch chunk ${idx}: ${chunkResp.status} ${txt}`);
-                }
-                const encrypted = new Uint8Array(await chunkResp.arrayBuffer());
+            // A folder still being archived has no count yet: its chunks
+            // carry it once the archive has ended
+            let total = meta.total_chunks;
+            const plain = [];
+            let size = 0;
+            for (let idx = 0; total === null || idx < total; idx++) {
+                const chunk = await chunkAt(idx);
+                const encrypted = chunk.data;
+                if (total === null) total = chunk.total;
 
-                // Decrypt chunk using wasm
-                // pass chunk index as Number (wasm-bindgen expects a JS number for u64)
+                // Decrypt chunk using wasm (chunk index as BigInt for u64)
                 const decrypted = wasm.decrypt_chunk(encrypted, key, nonce_bytes, BigInt(idx));
-
-                // Send decrypted chunk as transferable
-                self.postMessage({ type: 'chunk', index: idx, data: decrypted }, [decrypted.buffer]);
-                // Update resume DB with last completed index
-                await putLastIndex(db, dropId, idx);
-                self.postMessage({ type: 'progress', index: idx + 1, total });
+                plain.push(decrypted);
+                size += decrypted.length;
+                if (total !== null) {
+                    self.postMessage({ type: 'progress', percent: Math.round(((idx + 1) / total) * 100) });
+                }
             }
 
-            self.postMessage({ type: 'done' });
+            const data = new Uint8Array(size);
+            let offset = 0;
+            for (const part of plain) {
+                data.set(part, offset);
+                offset += part.length;
+            }
+            self.postMessage({ type: 'complete', data: data.buffer, filename: msg.filename, mime: msg.mime }, [data.buffer]);
         } catch (e) {
             self.postMessage({ type: 'error', message: e.message || String(e) });
         }
     }
 };
 
-function fetchWithTimeout(url, timeoutMs) {
-    const controller = new AbortController();
-    const timeout = setTimeout(() => controller.abort(), timeoutMs);
-    return fetch(url, { signal: controller.signal }).finally(() => clearTimeout(timeout));
+// Password drops: every chunk of both slots, in order, whatever the
+// password opens — the server sees the same fetches either way. The chunks
+// of the slot the password opens are kept, the other slot's are dropped.
+async function downloadSlots(wasm, key, meta, chunkAt) {
+    const total = meta.total_chunks;
+    const plain = [];
+    let mine = null;
+    for (let idx = 0; idx < total; idx++) {
+        const slot = Math.floor(idx / meta.slot_chunks);
+        const index = idx % meta.slot_chunks;
+        const chunk = await chunkAt(idx);
+        const nonce = decodeNonce(chunk.nonce);
+        if (mine === null && index === 0) {
+            try {
+                plain.push(wasm.decrypt_chunk(chunk.data, key, nonce, BigInt(index)));
+                mine = slot;
+            } catch (e) {
+                // The other slot: a decoy, or filler under a key nobody keeps
+                if (slot === meta.slots - 1) {
+                    throw new Error('Decryption failed — wrong password? The key didn\'t match.');
+                }
+            }
+        } else if (slot === mine) {
+            plain.push(wasm.decrypt_chunk(chunk.data, key, nonce, BigInt(index)));
+        }
+        self.postMessage({ type: 'progress', percent: Math.round(((idx + 1) / total) * 100) });
+    }
+    return unpackSlot(plain);
 }
 
-// IndexedDB helpers for resume
-function openResumeDB() {
-    return new Promise((resolve, reject) => {
-        const req = indexedDB.open('deadrop-resume', 1);
-        req.onupgradeneeded = () => {
-            req.result.createObjectStore('downloads', { keyPath: 'id' });
-        };
-        req.onsuccess = () => resolve(req.result);
-        req.onerror = () => reject(req.error);
-    });
+// A slot's plaintext is [data_len u64 LE][name_len u16 LE][name][data][padding]
+// — keep the data, the prefix fits in the first chunk
+function unpackSlot(plain) {
+    const first = plain[0];
+    const view = new DataView(first.buffer, first.byteOffset, first.byteLength);
+    const dataLen = Number(view.getBigUint64(0, true));
+    const nameEnd = 10 + view.getUint16(8, true);
+    if (nameEnd > first.length) throw new Error('Slot prefix does not fit its first chunk');
+    const filename = new TextDecoder().decode(first.subarray(10, nameEnd));
+
+    const data = new Uint8Array(dataLen);
+    let skip = nameEnd;
+    let offset = 0;
+    for (const part of plain) {
+        const take = part.subarray(Math.min(skip, part.length), skip + dataLen - offset);
+        data.set(take, offset);
+        offset += take.length;
+        skip = Math.max(skip - part.length, 0);
+        if (offset === dataLen) break;
+    }
+    if (offset !== dataLen) throw new Error('Slot length prefix exceeds slot size');
+    return { data, filename };
 }
 
-function getLastIndex(db, id) {
-    return new Promise((resolve, reject) => {
-        const tx = db.transaction('downloads', 'readonly');
-        const store = tx.objectStore('downloads');
-        const rq = store.get(id);
-        rq.onsuccess = () => {
-            const val = rq.result;
-            if (!val) resolve(null);
-            else resolve(val.last_index);
-        };
-        rq.onerror = () => reject(rq.error);
-    });
+// Nonces go out URL-safe base64 without padding
+function decodeNonce(b64) {
+    return Uint8Array.from(atob(b64.replace(/_/g, '/').replace(/-/g, '+')), c => c.charCodeAt(0));
 }
 
-function putLastIndex(db, id, idx) {
-    return new Promise((resolve, reject) => {
-        const tx = db.transaction('downloads', 'readwrite');
-        const store = tx.objectStore('downloads');
-        const rq = store.put({ id, last_index: idx, updated_at: Date.now() });
-        rq.onsuccess = () => resolve(true);
-        rq.onerror = () => reject(rq.error);
-    });
+// One chunk, the chunk count if the server knows it yet and, for a
+// password drop, its slot's nonce; retried while the session is still open
+async function fetchChunk(url, idx) {
+    for (let attempt = 1; ; attempt++) {
+        let resp;
+        try {
+            resp = await fetchWithTimeout(url, 10000);
+        } catch (e) {
+            if (attempt >= CHUNK_ATTEMPTS) throw e;
+            continue;
+        }
+        if (resp.ok) {
+            const total = resp.headers.get('X-Total-Chunks');
+            return {
+                data: new Uint8Array(await resp.arrayBuffer()),
+                total: total === null ? null : parseInt(total, 10),
+                nonce: resp.headers.get('X-Slot-Nonce'),
+            };
+        }
+        // Rate limited: wait it out, that's not a failed attempt
+        if (resp.status === 429) {
+            const wait = parseInt(resp.headers.get('Retry-After') || '1', 10);
+            await new Promise((r) => setTimeout(r, Math.max(wait, 1) * 1000));
+            attempt--;
+            continue;
+        }
+        // 403/404/410: the session or the drop is gone, retrying won't help
+        if (resp.status < 500 || attempt >= CHUNK_ATTEMPTS) {
+            const txt = await resp.text().catch(() => '');
+            throw new Error(`Failed to fetch chunk ${idx}: ${resp.status} ${txt}`);
+        }
+    }
+}
+
+function fetchWithTimeout(url, timeoutMs) {
+    const controller = new AbortController();
+    const timeout = setTimeout(() => controller.abort(), timeoutMs);
+    return fetch(url, { signal: controller.signal }).finally(() => clearTimeout(timeout));
 }
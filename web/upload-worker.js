@@ -7,10 +7,19 @@
  * fragment — same zero-knowledge model as download mode.
  *
  * Flow:
- * 1. Main thread reads file + extracts #key from URL
- * 2. Worker encrypts file via WASM (XChaCha20-Poly1305, 64KB chunks)
- * 3. Worker uploads encrypted blob to /api/upload
- * 4. Server decrypts with same key and saves to disk
+ * 1. Main thread hands over each File (not its bytes) + the #key from URL
+ * 2. Worker uploads the file in chunks (protocol in src/receive.rs):
+ *    POST /api/upload/start → PUT /api/upload/{id}?offset=N … → POST …/finish
+ *    Each PUT body is read from the file and encrypted via WASM
+ *    (XChaCha20-Poly1305, 64KB chunks) just before it goes out, so only
+ *    a chunk of the file is ever in memory. Each PUT is acknowledged with
+ *    the new offset; after a dropped connection the worker asks
+ *    GET /api/upload/{id} and resumes there, encrypting that part again
+ * 3. The finish POST carries the sealed SHA-256 of the plaintext
+ * 4. Server decrypts with same key as the upload streams off its disk and
+ *    saves under its output directory (after a virus scan, if it runs
+ *    one — the reply carries the verdict)
+ * 5. After the last file the page POSTs /api/upload/done
  */
 
 let wasmModule = null;
@@ -30,63 +39,181 @@ function formatBytes(bytes) {
     return (bytes / Math.pow(1024, i)).toFixed(i > 0 ? 1 : 0) + " " + units[i];
 }
 
-async function encryptAndUpload(data, filename, mime, keyBase64) {
-    const wasm = await initWasm();
+const sleep = (ms) => new Promise((r) => setTimeout(r, ms));
 
-    postMessage({ type: "status", message: "Encrypting file..." });
-    postMessage({ type: "progress", percent: 5 });
+// How long to keep retrying while the connection is down
+const OFFLINE_PATIENCE_MS = 10 * 60 * 1000;
 
-    // ─── Encrypt via WASM using the key from URL fragment ───
-    const plaintext = new Uint8Array(data);
-    let encrypted;
-    try {
-        encrypted = wasm.encrypt_blob(plaintext, keyBase64);
-    } catch (e) {
-        throw new Error("Encryption failed: " + String(e));
+// Keep trying through rate limits (many small files) and dropped
+// connections (phone left Wi-Fi, tunnel hiccup)
+async function fetchPersistently(url, init) {
+    const deadline = Date.now() + OFFLINE_PATIENCE_MS;
+    for (let attempt = 0; ; attempt++) {
+        let response;
+        try {
+            response = await fetch(url, init);
+        } catch (e) {
+            if (Date.now() > deadline) throw new Error("Connection lost: " + e.message);
+            postMessage({ type: "status", message: "Connection lost — waiting to resume..." });
+            await sleep(Math.min(1000 * 2 ** attempt, 5000));
+            continue;
+        }
+        if (response.status === 429 && Date.now() < deadline) {
+            const wait = parseInt(response.headers.get("retry-after") || "1", 10);
+            postMessage({ type: "status", message: "Rate limited — retrying in " + wait + "s..." });
+            await sleep(Math.max(wait, 1) * 1000);
+            continue;
+        }
+        return response;
     }
+}
 
-    postMessage({ type: "progress", percent: 50 });
-    postMessage({
-        type: "status",
-        message: "Encrypted " + formatBytes(plaintext.length) + " — uploading..."
+async function failure(what, response) {
+    const errText = await response.text().catch(() => "Unknown error");
+    return new Error(what + " (HTTP " + response.status + "): " + errText);
+}
+
+// Ask the server how much of the upload it has
+async function resumeOffset(id) {
+    const response = await fetchPersistently("/api/upload/" + id);
+    if (!response.ok) throw await failure("Upload lost by the receiver", response);
+    return (await response.json()).offset;
+}
+
+// Size of one encrypted frame: [u32 len][ciphertext + 16-byte tag]
+const FRAME_OVERHEAD = 4 + 16;
+
+// Bytes [start, end) of the encrypted container, read and encrypted from
+// the file on demand. The last frame encrypted is kept, since upload
+// chunks rarely end on a frame boundary.
+async function encryptedRange(file, encryptor, header, cache, start, end) {
+    const frameSize = encryptor.chunk_size + FRAME_OVERHEAD;
+    const out = new Uint8Array(end - start);
+    let pos = start;
+    while (pos < end) {
+        if (pos < header.length) {
+            const part = header.subarray(pos, Math.min(header.length, end));
+            out.set(part, pos - start);
+            pos += part.length;
+            continue;
+        }
+        const index = Math.floor((pos - header.length) / frameSize);
+        if (cache.index !== index) {
+            const from = index * encryptor.chunk_size;
+            const plaintext = new Uint8Array(
+                await file.slice(from, Math.min(from + encryptor.chunk_size, file.size)).arrayBuffer()
+            );
+            cache.frame = encryptor.chunk(BigInt(index), plaintext);
+            cache.index = index;
+        }
+        const frameStart = header.length + index * frameSize;
+        const part = cache.frame.subarray(pos - frameStart, Math.min(cache.frame.length, end - frameStart));
+        out.set(part, pos - start);
+        pos += part.length;
+    }
+    return out;
+}
+
+async function uploadChunked(file, filename, encryptor) {
+    const size = Number(encryptor.encrypted_size);
+    const header = encryptor.header();
+    const cache = { index: -1, frame: null };
+
+    const start = await fetchPersistently("/api/upload/start", {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ path: filename, size }),
     });
+    if (!start.ok) throw await failure("Upload refused", start);
+    const started = await start.json();
+    // The receiver already has a file by this name and keeps it
+    if (started.status === "skipped") return { ...started, size: 0 };
+    const { id, chunk_size } = started;
+
+    let offset = 0;
+    while (offset < size) {
+        const chunk = await encryptedRange(file, encryptor, header, cache, offset, Math.min(offset + chunk_size, size));
+        let response;
+        try {
+            response = await fetch("/api/upload/" + id + "?offset=" + offset, {
+                method: "PUT",
+                headers: { "Content-Type": "application/octet-stream" },
+                body: chunk,
+            });
+        } catch (e) {
+            // Dropped mid-chunk — the server may or may not have it
+            offset = await resumeOffset(id);
+            continue;
+        }
+        if (response.status === 429) {
+            const wait = parseInt(response.headers.get("retry-after") || "1", 10);
+            await sleep(Math.max(wait, 1) * 1000);
+            continue;
+        }
+        if (response.status === 409) {
+            offset = await response.json().then((r) => r.offset).catch(() => resumeOffset(id));
+            continue;
+        }
+        if (!response.ok) throw await failure("Upload failed", response);
+        offset = (await response.json()).offset;
+        postMessage({ type: "progress", percent: 5 + Math.round((offset / size) * 90) });
+    }
 
-    // ─── Upload encrypted blob ───
-    const response = await fetch("/api/upload", {
+    // Sealed SHA-256 of the plaintext: the receiver checks the file against it
+    const finish = await fetchPersistently("/api/upload/" + id + "/finish", {
         method: "POST",
-        headers: {
-            "Content-Type": "application/octet-stream",
-            "X-Filename": encodeURIComponent(filename),
-            "X-Mime": encodeURIComponent(mime),
-            "X-Original-Size": plaintext.length.toString(),
-        },
-        body: encrypted,
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ digest: encryptor.sealed_digest() }),
     });
+    if (!finish.ok) throw await failure("Upload failed", finish);
+    return finish.json();
+}
+
+async function encryptAndUpload(file, filename, mime, keyBase64) {
+    const wasm = await initWasm();
 
-    if (!response.ok) {
-        const errText = await response.text().catch(() => "Unknown error");
-        throw new Error("Upload failed (HTTP " + response.status + "): " + errText);
+    let encryptor;
+    try {
+        encryptor = new wasm.UploadEncryptor(keyBase64, BigInt(file.size));
+    } catch (e) {
+        throw new Error("Encryption failed: " + String(e));
     }
 
-    postMessage({ type: "progress", percent: 95 });
+    postMessage({ type: "progress", percent: 5 });
+    postMessage({
+        type: "status",
+        message: "Encrypting " + formatBytes(file.size) + " as it uploads..."
+    });
 
-    const result = await response.json();
+    // ─── Encrypt and upload in resumable chunks (filename may be a relative path) ───
+    let result;
+    try {
+        result = await uploadChunked(file, filename, encryptor);
+    } finally {
+        encryptor.free();
+    }
 
     postMessage({
         type: "complete",
         savedAs: result.saved_as,
         size: result.size,
+        sha256: result.sha256,
+        skipped: result.status === "skipped",
+        // The receiver scans uploads; anything but clean stays in its quarantine
+        quarantined: result.status === "quarantined",
+        verdict: result.verdict,
+        threat: result.threat,
     });
 }
 
 // ─── Worker message handler ───
 self.onmessage = async function (e) {
     if (!e.data || typeof e.data !== "object") return;
-    const { action, data, filename, mime, key } = e.data;
+    const { action, file, filename, mime, key } = e.data;
 
     if (action === "encrypt_and_upload") {
-        if (!data) {
-            postMessage({ type: "error", message: "No file data provided" });
+        if (!file) {
+            postMessage({ type: "error", message: "No file provided" });
             return;
         }
         if (!key) {
@@ -94,7 +221,7 @@ self.onmessage = async function (e) {
             return;
         }
         try {
-            await encryptAndUpload(data, filename, mime, key);
+            await encryptAndUpload(file, filename, mime, key);
         } catch (err) {
             postMessage({ type: "error", message: err.message || String(err) });
         }
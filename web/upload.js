@@ -10,7 +10,9 @@ if (window.location.hash && window.location.hash.length > 1) {
 
 const dropZone = document.getElementById('drop-zone');
 const fileInput = document.getElementById('file-input');
+const folderInput = document.getElementById('folder-input');
 const browseLink = document.getElementById('browse-link');
+const folderLink = document.getElementById('folder-link');
 const fileInfo = document.getElementById('file-info');
 const fileName = document.getElementById('file-name');
 const fileSize = document.getElementById('file-size');
@@ -20,7 +22,8 @@ const progressCont = document.getElementById('progress-container');
 const progressFill = document.getElementById('progress-fill');
 const progressText = document.getElementById('progress-text');
 
-let selectedFile = null;
+// Each entry is { file, path } — path is relative, e.g. "photos/2024/a.jpg"
+let selected = [];
 
 // ─── Validate key presence ───
 if (!key) {
@@ -36,6 +39,26 @@ function formatBytes(bytes) {
     return (bytes / Math.pow(1024, i)).toFixed(i > 0 ? 1 : 0) + ' ' + units[i];
 }
 
+// ─── Folder traversal for dropped directories ───
+function readEntries(reader) {
+    return new Promise((resolve, reject) => reader.readEntries(resolve, reject));
+}
+
+async function collectEntry(entry, prefix, out) {
+    if (entry.isFile) {
+        const file = await new Promise((resolve, reject) => entry.file(resolve, reject));
+        out.push({ file, path: prefix + file.name });
+    } else if (entry.isDirectory) {
+        const reader = entry.createReader();
+        // readEntries returns at most ~100 entries per call
+        for (let batch = await readEntries(reader); batch.length > 0; batch = await readEntries(reader)) {
+            for (const child of batch) {
+                await collectEntry(child, prefix + entry.name + '/', out);
+            }
+        }
+    }
+}
+
 // ─── Drag & drop ───
 dropZone.addEventListener('dragover', (e) => {
     e.preventDefault();
@@ -46,11 +69,20 @@ dropZone.addEventListener('dragleave', () => {
     dropZone.classList.remove('dragover');
 });
 
-dropZone.addEventListener('drop', (e) => {
+dropZone.addEventListener('drop', async (e) => {
     e.preventDefault();
     dropZone.classList.remove('dragover');
-    if (e.dataTransfer.files.length > 0) {
-        handleFile(e.dataTransfer.files[0]);
+    const entries = Array.from(e.dataTransfer.items || [])
+        .map((item) => item.webkitGetAsEntry && item.webkitGetAsEntry())
+        .filter(Boolean);
+    if (entries.length > 0) {
+        const out = [];
+        for (const entry of entries) {
+            await collectEntry(entry, '', out);
+        }
+        handleFiles(out);
+    } else {
+        handleFiles(Array.from(e.dataTransfer.files).map((file) => ({ file, path: file.name })));
     }
 });
 
@@ -59,81 +91,138 @@ browseLink.addEventListener('click', (e) => {
     e.stopPropagation();
     fileInput.click();
 });
+folderLink.addEventListener('click', (e) => {
+    e.stopPropagation();
+    folderInput.click();
+});
 dropZone.addEventListener('click', () => fileInput.click());
 fileInput.addEventListener('change', () => {
-    if (fileInput.files.length > 0) {
-        handleFile(fileInput.files[0]);
-    }
+    handleFiles(Array.from(fileInput.files).map((file) => ({ file, path: file.name })));
+});
+folderInput.addEventListener('change', () => {
+    handleFiles(Array.from(folderInput.files).map((file) => ({
+        file,
+        path: file.webkitRelativePath || file.name,
+    })));
 });
 
-// ─── File selected ───
-function handleFile(file) {
-    selectedFile = file;
-    fileName.textContent = file.name;
-    fileSize.textContent = formatBytes(file.size);
+// ─── Files selected ───
+function handleFiles(entries) {
+    if (entries.length === 0) return;
+    selected = entries;
+    const total = entries.reduce((sum, e) => sum + e.file.size, 0);
+    fileName.textContent = entries.length === 1
+        ? entries[0].path
+        : entries.length + ' files';
+    fileSize.textContent = formatBytes(total);
     fileInfo.style.display = 'block';
     btn.style.display = 'block';
     statusEl.textContent = 'Ready to encrypt & send';
     dropZone.style.display = 'none';
 }
 
-// ─── Encrypt & Upload via Web Worker ───
-btn.addEventListener('click', async () => {
-    if (!selectedFile || !key) return;
-
-    btn.disabled = true;
-    btn.textContent = 'Encrypting...';
-    progressCont.style.display = 'block';
-
-    if (typeof Worker !== 'undefined') {
-        const worker = new Worker('/assets/upload-worker.js', { type: 'module' });
-
+// ─── One file through the worker: resolves with the server's reply ───
+function uploadOne(worker, entry, index) {
+    return new Promise((resolve, reject) => {
         worker.onmessage = (e) => {
             const msg = e.data;
             switch (msg.type) {
-                case 'progress':
-                    progressFill.style.width = msg.percent + '%';
+                case 'progress': {
+                    const overall = ((index + msg.percent / 100) / selected.length) * 100;
+                    progressFill.style.width = overall + '%';
                     break;
+                }
                 case 'status':
-                    progressText.textContent = msg.message;
+                    progressText.textContent = '(' + (index + 1) + '/' + selected.length + ') ' + msg.message;
                     break;
                 case 'complete':
-                    progressFill.style.width = '100%';
-                    progressFill.style.background = '#00ff88';
-                    statusEl.innerHTML = '<span class="success">✅ File encrypted and sent successfully!</span>';
-                    btn.style.display = 'none';
-                    progressText.textContent = 'Complete — ' + formatBytes(msg.size);
-                    // Nuke key from memory
-                    key = null;
-                    worker.terminate();
+                    resolve(msg);
                     break;
                 case 'error':
-                    statusEl.innerHTML = '<span class="error">' + msg.message + '</span>';
-                    progressFill.style.background = '#ff4444';
-                    btn.textContent = 'Retry';
-                    btn.disabled = false;
-                    worker.terminate();
+                    reject(new Error(msg.message));
                     break;
             }
         };
+        worker.onerror = (e) => reject(new Error('Worker error: ' + e.message));
 
-        worker.onerror = (e) => {
-            statusEl.innerHTML = '<span class="error">Worker error: ' + e.message + '</span>';
-            btn.textContent = 'Retry';
-            btn.disabled = false;
-        };
-
-        // Read file and send to worker with the encryption key
-        const arrayBuffer = await selectedFile.arrayBuffer();
+        // Hand the file to the worker with the encryption key — it reads
+        // and encrypts one chunk at a time
         worker.postMessage({
             action: 'encrypt_and_upload',
-            data: arrayBuffer,
-            filename: selectedFile.name,
-            mime: selectedFile.type || 'application/octet-stream',
+            file: entry.file,
+            filename: entry.path,
+            mime: entry.file.type || 'application/octet-stream',
             key: key,
-        }, [arrayBuffer]);
+        });
+    });
+}
 
-    } else {
+// ─── Encrypt & Upload via Web Worker, one file at a time ───
+btn.addEventListener('click', async () => {
+    if (selected.length === 0 || !key) return;
+
+    if (typeof Worker === 'undefined') {
         statusEl.innerHTML = '<span class="error">Web Workers not supported — please use a modern browser</span>';
+        return;
+    }
+
+    btn.disabled = true;
+    btn.textContent = 'Encrypting...';
+    progressCont.style.display = 'block';
+    progressFill.style.background = '#00ff88';
+
+    const worker = new Worker('/assets/upload-worker.js', { type: 'module' });
+    let sent = 0;
+    let skipped = 0;
+    let bytes = 0;
+    // Held back by the receiver's virus scanner
+    const quarantined = [];
+    // What the receiver computed — to read back to them over another channel
+    let sha256 = null;
+    try {
+        for (let i = 0; i < selected.length; i++) {
+            const result = await uploadOne(worker, selected[i], i);
+            sent++;
+            if (result.skipped) skipped++;
+            if (result.quarantined) quarantined.push(result.savedAs + ' (' + (result.threat || result.verdict) + ')');
+            bytes += result.size;
+            sha256 = result.sha256;
+        }
+        // Tell the server the batch is complete (it may be rate limiting us)
+        for (let attempt = 0; attempt < 5; attempt++) {
+            const done = await fetch('/api/upload/done', { method: 'POST' }).catch(() => null);
+            if (!done || done.status !== 429) break;
+            await new Promise((r) => setTimeout(r, 1000));
+        }
+    } catch (err) {
+        statusEl.innerHTML = '<span class="error">' + (sent > 0 ? sent + ' sent, then: ' : '') + err.message + '</span>';
+        progressFill.style.background = '#ff4444';
+        // Retry picks up with the files that did not make it
+        selected = selected.slice(sent);
+        btn.textContent = 'Retry';
+        btn.disabled = false;
+        worker.terminate();
+        return;
+    }
+
+    progressFill.style.width = '100%';
+    statusEl.innerHTML = '<span class="success">✅ ' + (sent === 1 ? 'File' : sent + ' files') + ' encrypted and sent successfully!</span>'
+        + (skipped > 0 ? '<br><span class="status">' + skipped + ' already on the receiver — skipped</span>' : '');
+    if (quarantined.length > 0) {
+        const held = document.createElement('span');
+        held.className = 'error';
+        held.textContent = '☣ Held back by the receiver\'s virus scan: ' + quarantined.join(', ');
+        statusEl.append(document.createElement('br'), held);
+    }
+    if (sent === 1 && sha256) {
+        const digest = document.createElement('span');
+        digest.className = 'status';
+        digest.textContent = 'SHA-256 ' + sha256;
+        statusEl.append(document.createElement('br'), digest);
     }
+    btn.style.display = 'none';
+    progressText.textContent = 'Complete — ' + formatBytes(bytes);
+    // Nuke key from memory
+    key = null;
+    worker.terminate();
 });
@@ -7,17 +7,25 @@
  * encrypted blob in memory. Peak RAM ≈ plaintext size + one chunk buffer.
  *
  * Protocol:
- *   [40-byte header][4-byte chunk_len][chunk_ciphertext]...[repeat]
+ *   [header][4-byte chunk_len][chunk_ciphertext]...[repeat]
  *
- * Header:
+ * Header (40 bytes, default 64 KiB chunks):
  *   bytes  0..24  = XChaCha20 nonce (192-bit)
  *   bytes 24..32  = total_chunks (u64 LE)
  *   bytes 32..40  = original_size (u64 LE)
+ *
+ * A sender-chosen chunk size is recorded in front (48 bytes):
+ *   bytes  0..4   = "DDcs"
+ *   bytes  4..8   = chunk_size (u32 LE)
+ *   bytes  8..48  = the 40-byte header above
  */
 
 const HEADER_SIZE = 40;
+const SIZED_HEADER_SIZE = 48;
+const SIZED_HEADER_MAGIC = [0x44, 0x44, 0x63, 0x73]; // "DDcs"
+const DEFAULT_CHUNK_SIZE = 64 * 1024;
 const CHUNK_LEN_SIZE = 4;
-const MAX_CHUNK_SIZE = 1024 * 1024; // 1MB sanity limit
+const AUTH_TAG_SIZE = 16;
 
 let wasmModule = null;
 
@@ -29,19 +37,30 @@ async function initWasm() {
     return wasm;
 }
 
+/** Bytes the header takes up, or 0 while too few have arrived to tell */
+function headerLength(data) {
+    if (data.length < SIZED_HEADER_MAGIC.length) return 0;
+    const sized = SIZED_HEADER_MAGIC.every(function (b, i) { return data[i] === b; });
+    const length = sized ? SIZED_HEADER_SIZE : HEADER_SIZE;
+    return data.length >= length ? length : 0;
+}
+
 function parseHeader(data) {
-    if (data.length < HEADER_SIZE) {
+    const length = headerLength(data);
+    if (length === 0) {
         throw new Error("Encrypted data is too short to contain a valid header");
     }
     const view = new DataView(data.buffer, data.byteOffset, data.byteLength);
-    const nonce = data.slice(0, 24);
-    const totalChunksLow = view.getUint32(24, true);
-    const totalChunksHigh = view.getUint32(28, true);
+    const base = length - HEADER_SIZE;
+    const chunkSize = base ? view.getUint32(4, true) : DEFAULT_CHUNK_SIZE;
+    const nonce = data.slice(base, base + 24);
+    const totalChunksLow = view.getUint32(base + 24, true);
+    const totalChunksHigh = view.getUint32(base + 28, true);
     const totalChunks = totalChunksLow + totalChunksHigh * 0x100000000;
-    const originalSizeLow = view.getUint32(32, true);
-    const originalSizeHigh = view.getUint32(36, true);
+    const originalSizeLow = view.getUint32(base + 32, true);
+    const originalSizeHigh = view.getUint32(base + 36, true);
     const originalSize = originalSizeLow + originalSizeHigh * 0x100000000;
-    return { nonce, totalChunks, originalSize };
+    return { nonce, totalChunks, originalSize, chunkSize, length };
 }
 
 function readU32LE(data, offset) {
@@ -80,19 +99,20 @@ function appendBuffer(existing, newData) {
 /**
  * Streaming decryption pipeline:
  *   1. Fetch encrypted blob as a ReadableStream
- *   2. Parse 40-byte header from first arriving bytes
+ *   2. Parse the header from first arriving bytes
  *   3. As network chunks arrive, extract and decrypt crypto-chunks immediately
  *   4. Never hold the full encrypted blob — only a sliding buffer
  *   5. Accumulate plaintext parts (or stream to File System Access API if available)
  */
-async function decryptStream(dropId, key, filename, mime) {
+async function decryptStream(dropId, key, filename, mime, token) {
     const wasm = await initWasm();
 
     postMessage({ type: "status", message: "Connecting..." });
     postMessage({ type: "progress", percent: 2 });
 
     const safeDropId = encodeURIComponent(dropId);
-    const response = await fetch("/api/blob/" + safeDropId);
+    const query = token ? "?t=" + encodeURIComponent(token) : "";
+    const response = await fetch("/api/blob/" + safeDropId + query);
     if (!response.ok) {
         throw new Error(
             response.status === 404
@@ -110,6 +130,7 @@ async function decryptStream(dropId, key, filename, mime) {
     let nonce = null;
     let totalChunks = 0;
     let originalSize = 0;
+    let maxChunkLen = 0;
     let chunkIndex = 0;
     let receivedBytes = 0;
     let decryptedBytes = 0;
@@ -137,13 +158,14 @@ async function decryptStream(dropId, key, filename, mime) {
             }
         }
 
-        // ── Parse header from first 40 bytes ──
-        if (!headerParsed && buffer.length >= HEADER_SIZE) {
+        // ── Parse header from first bytes ──
+        if (!headerParsed && headerLength(buffer) > 0) {
             const header = parseHeader(buffer);
             nonce = header.nonce;
             totalChunks = header.totalChunks;
             originalSize = header.originalSize;
-            buffer = buffer.slice(HEADER_SIZE); // consume header
+            maxChunkLen = header.chunkSize + AUTH_TAG_SIZE;
+            buffer = buffer.slice(header.length); // consume header
             headerParsed = true;
 
             if (totalChunks === 0) {
@@ -160,7 +182,7 @@ async function decryptStream(dropId, key, filename, mime) {
                 const chunkLen = readU32LE(buffer, 0);
 
                 // Sanity check
-                if (chunkLen === 0 || chunkLen > MAX_CHUNK_SIZE) {
+                if (chunkLen === 0 || chunkLen > maxChunkLen) {
                     throw new Error("Invalid chunk length at chunk " + chunkIndex + ": " + chunkLen);
                 }
 
@@ -237,7 +259,7 @@ async function decryptStream(dropId, key, filename, mime) {
 // ── Worker message handler ──
 self.onmessage = async function (e) {
     if (!e.data || typeof e.data !== "object") return;
-    const { action, dropId, key, filename, mime } = e.data;
+    const { action, dropId, key, filename, mime, token } = e.data;
 
     if (action === "decrypt") {
         if (!dropId || !key) {
@@ -245,7 +267,7 @@ self.onmessage = async function (e) {
             return;
         }
         try {
-            await decryptStream(dropId, key, filename, mime);
+            await decryptStream(dropId, key, filename, mime, token);
         } catch (err) {
             postMessage({ type: "error", message: err.message || String(err) });
         }